@@ -0,0 +1,261 @@
+use crate::{Error, Span};
+use std::error::Error as ErrorTrait;
+
+/// How serious a diagnostic is. Only errors prevent a program from running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Information every error type of the interpreter provides so it can be
+/// turned into a [`Diagnostic`]
+pub trait Diagnose: ErrorTrait {
+    /// A short and stable identifier of the kind of error, e.g. `E0201`
+    fn code(&self) -> &'static str;
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// Suggestions on how to fix the error
+    fn help(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Additional context about the error
+    fn notes(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A range of the source code, optionally annotated with a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+}
+
+impl Label {
+    #[must_use]
+    pub const fn new(span: Span) -> Self {
+        Self {
+            span,
+            message: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// A call that was in progress when a runtime error happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Name of the function being called, `None` if it is anonymous
+    pub function: Option<String>,
+
+    /// Where the function was called from
+    pub call_site: Span,
+}
+
+/// A problem found in a program by one of the phases of the interpreter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+
+    /// Where the problem is
+    pub primary: Label,
+
+    /// Other positions related to the problem
+    pub secondary: Vec<Label>,
+
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+
+    /// The calls in progress when the problem happened, innermost first
+    pub trace: Vec<Frame>,
+}
+
+impl Diagnostic {
+    #[must_use]
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            primary: Label::new(Span::at(0)),
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_primary(mut self, label: Label) -> Self {
+        self.primary = label;
+        self
+    }
+
+    #[must_use]
+    pub fn with_secondary(mut self, label: Label) -> Self {
+        self.secondary.push(label);
+        self
+    }
+
+    #[must_use]
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_trace(mut self, trace: Vec<Frame>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl<E: Diagnose> From<&Error<E>> for Diagnostic {
+    fn from(error: &Error<E>) -> Self {
+        Self {
+            severity: error.source.severity(),
+            code: error.source.code(),
+            message: error.source.to_string(),
+            primary: Label::new(error.span),
+            secondary: Vec::new(),
+            notes: error.source.notes(),
+            help: error.source.help(),
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl<E: Diagnose> From<Error<E>> for Diagnostic {
+    fn from(error: Error<E>) -> Self {
+        Self::from(&error)
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// A collection of diagnostics produced while running a program
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn push(&mut self, diagnostic: impl Into<Diagnostic>) {
+        self.0.push(diagnostic.into());
+    }
+
+    pub fn extend(&mut self, diagnostics: Self) {
+        self.0.extend(diagnostics.0);
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether any of the diagnostics is an error
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.0.iter().any(Diagnostic::is_error)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.0.iter()
+    }
+}
+
+impl<E: Diagnose> From<Vec<Error<E>>> for Diagnostics {
+    fn from(errors: Vec<Error<E>>) -> Self {
+        Self(errors.into_iter().map(Diagnostic::from).collect())
+    }
+}
+
+impl<E: Diagnose> From<Error<E>> for Diagnostics {
+    fn from(error: Error<E>) -> Self {
+        Self(vec![error.into()])
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self(vec![diagnostic])
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl std::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, diagnostic) in self.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{diagnostic}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ErrorTrait for Diagnostics {}
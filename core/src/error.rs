@@ -1,10 +1,10 @@
+use crate::Span;
 use std::error::Error as ErrorTrait;
 use thiserror::Error as ThisError;
 
-#[derive(Debug, ThisError)]
+#[derive(Debug, Clone, ThisError)]
 pub struct Error<E: ErrorTrait> {
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
 
     #[source]
     pub source: E,
@@ -1,7 +1,13 @@
+mod diagnostic;
 mod error;
 mod report;
+mod span;
+mod symbol;
 
+pub use diagnostic::{Diagnose, Diagnostic, Diagnostics, Frame, Label, Severity};
 pub use error::Error;
 pub use report::report;
+pub use span::{Location, SourceMap, Span};
+pub use symbol::{BuildIdHasher, IdHasher, Symbol, SymbolMap};
 
 pub type Result<T, E> = core::result::Result<T, Error<E>>;
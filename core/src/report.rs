@@ -1,54 +1,183 @@
-use crate::Error;
+use crate::{Diagnostic, Frame, Label, Severity, SourceMap};
 use color_eyre::owo_colors::OwoColorize;
-use std::error::Error as ErrorTrait;
+use std::io::Write;
 
 /// How many lines before and after the line containing the error
 /// should be displayed
 const LINE_PADDING: usize = 2;
 
+/// How many times a frame repeated consecutively, as in a recursive call,
+/// is displayed before the rest of the repetitions are collapsed
+const MAX_REPEATED_FRAMES: usize = 3;
+
 const SEPARATOR: &str = " | ";
 
-pub fn report<E: ErrorTrait>(source: &str, error: &Error<E>) {
-    let line = error.line + 1;
-    let column = error.column + 1;
+/// Renders the diagnostic, along with the surrounding source code, to the
+/// given output
+///
+/// # Errors
+/// This function will error if writing to `output` fails
+pub fn report(
+    output: &mut dyn Write,
+    source: &str,
+    diagnostic: &Diagnostic,
+) -> std::io::Result<()> {
+    let source_map = SourceMap::new(source);
+    let start = source_map.location(diagnostic.primary.span.start);
+    let line = start.line + 1;
+    let column = start.column + 1;
 
-    eprintln!();
-    eprintln!(
-        "{}: {} at {line}:{column}.",
-        "Error".red().bold(),
-        error.source
-    );
-    eprintln!();
+    let title = format!("{}[{}]", capitalize(diagnostic.severity), diagnostic.code);
+
+    writeln!(output)?;
+    match diagnostic.severity {
+        Severity::Error => write!(output, "{}", title.red().bold())?,
+        Severity::Warning => write!(output, "{}", title.yellow().bold())?,
+    }
+    writeln!(output, ": {} at {line}:{column}.", diagnostic.message)?;
+    writeln!(output)?;
+
+    snippet(
+        output,
+        source,
+        &source_map,
+        &diagnostic.primary,
+        LINE_PADDING,
+    )?;
+
+    for label in &diagnostic.secondary {
+        writeln!(output)?;
+        snippet(output, source, &source_map, label, 0)?;
+    }
+
+    if !diagnostic.trace.is_empty() {
+        writeln!(output)?;
+        trace(output, &source_map, &diagnostic.trace)?;
+    }
+
+    if !diagnostic.notes.is_empty() || !diagnostic.help.is_empty() {
+        writeln!(output)?;
+    }
+
+    for note in &diagnostic.notes {
+        writeln!(output, "{}: {note}", "note".bold())?;
+    }
+
+    for help in &diagnostic.help {
+        writeln!(output, "{}: {help}", "help".cyan().bold())?;
+    }
+
+    writeln!(output)
+}
+
+/// Displays the line `label` starts at and up to `padding` lines around it,
+/// underlining the part of the line covered by the label
+fn snippet(
+    output: &mut dyn Write,
+    source: &str,
+    source_map: &SourceMap,
+    label: &Label,
+    padding: usize,
+) -> std::io::Result<()> {
+    let start = source_map.location(label.span.start);
+    let line = start.line + 1;
+    let column = start.column + 1;
 
-    let offset = line.saturating_sub(LINE_PADDING + 1);
-    let take = line.saturating_add(LINE_PADDING).min(2 * LINE_PADDING + 1);
+    let offset = line.saturating_sub(padding + 1);
+    let take = line.saturating_add(padding).min(2 * padding + 1);
     let chunk = source.lines().skip(offset).take(take);
 
     let align =
         // Length of the error line number
         usize::ilog10(line) as usize + 1
 
-        // Add 1 if one of the next `LINE_PADDING` line numbers is one
+        // Add 1 if one of the next `padding` line numbers is one
         // digit longer than the error line's number.
         // This happens when the last digit of `line` (`line % 10`) is greater
-        // than or equal to 10 - `LINE_PADDING`
-        + usize::saturating_sub(line % 10, 9 - LINE_PADDING).min(1);
+        // than or equal to 10 - `padding`
+        + usize::saturating_sub(line % 10, 9 - padding).min(1);
+
+    // Spans covering several lines are underlined up to the end of the first
+    let line_end = source
+        .get(label.span.start..)
+        .and_then(|rest| rest.find('\n'))
+        .map_or(source.len(), |end| label.span.start + end);
+    let width = label
+        .span
+        .end
+        .min(line_end)
+        .saturating_sub(label.span.start);
+
+    let marker = format!(
+        "{}--- {}",
+        "^".repeat(width.max(1)),
+        label.message.as_deref().unwrap_or("Here")
+    );
 
     for (i, code) in chunk.enumerate().skip_while(|(_, code)| code.is_empty()) {
         let line_indicator = format!("{:align$}{SEPARATOR}", offset + i + 1);
-        eprint!("{}", line_indicator.blue().bold());
+        write!(output, "{}", line_indicator.blue().bold())?;
 
-        if i == usize::min(line - 1, LINE_PADDING) {
-            eprintln!("{}", code.red());
-            eprintln!(
+        if i == usize::min(line - 1, padding) {
+            writeln!(output, "{}", code.red())?;
+            writeln!(
+                output,
                 "{}{}",
                 " ".repeat(SEPARATOR.len() + align + column - 1),
-                "^--- Here".yellow(),
-            );
+                marker.yellow(),
+            )?;
         } else {
-            eprintln!("{code}");
+            writeln!(output, "{code}")?;
         }
     }
 
-    eprintln!()
+    Ok(())
+}
+
+/// Displays the calls in progress, innermost first
+fn trace(output: &mut dyn Write, source_map: &SourceMap, trace: &[Frame]) -> std::io::Result<()> {
+    writeln!(output, "{}", "Stack trace (innermost call first):".bold())?;
+
+    let mut i = 0;
+    while i < trace.len() {
+        let frame = &trace[i];
+        let repetitions = trace[i..]
+            .iter()
+            .take_while(|other| *other == frame)
+            .count();
+
+        let location = source_map.location(frame.call_site.start);
+        let function = frame.function.as_ref().map_or_else(
+            || "anonymous function".into(),
+            |function| format!(r#""{function}""#),
+        );
+
+        for _ in 0..repetitions.min(MAX_REPEATED_FRAMES) {
+            writeln!(
+                output,
+                "  in {function}, called at {}:{}",
+                location.line + 1,
+                location.column + 1
+            )?;
+        }
+
+        if repetitions > MAX_REPEATED_FRAMES {
+            writeln!(
+                output,
+                "  ... the call above is repeated {} more times",
+                repetitions - MAX_REPEATED_FRAMES
+            )?;
+        }
+
+        i += repetitions;
+    }
+
+    Ok(())
+}
+
+fn capitalize(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "Error",
+        Severity::Warning => "Warning",
+    }
 }
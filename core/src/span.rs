@@ -0,0 +1,80 @@
+/// A range of bytes in the source code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    #[must_use]
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// An empty span at `offset`, used for errors about something
+    /// missing from the source, like a semicolon
+    #[must_use]
+    pub const fn at(offset: usize) -> Self {
+        Self::new(offset, offset)
+    }
+
+    /// The smallest span containing both `self` and `other`
+    #[must_use]
+    pub fn to(self, other: Self) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// A zero-based line and byte column in the source code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Converts byte offsets into lines and columns
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    /// Offset of the first byte of every line
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { line_starts }
+    }
+
+    #[must_use]
+    pub fn location(&self, offset: usize) -> Location {
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+
+        Location {
+            line,
+            column: offset - self.line_starts[line],
+        }
+    }
+
+    /// Offset of the first byte of `line`, if it exists
+    #[must_use]
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+}
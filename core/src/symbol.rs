@@ -0,0 +1,161 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    marker::PhantomData,
+};
+
+/// Names every thread knows, in the order of their indices
+const PREDEFINED: [&str; 3] = ["init", "this", "super"];
+
+thread_local! {
+    /// Every symbol created by the thread. Names are leaked, so the table
+    /// grows with the distinct identifiers of the programs the thread runs
+    /// and the globals, natives and foreign classes hosts define. Other
+    /// strings, such as the keys of maps, are never interned
+    static SYMBOLS: RefCell<Interner> = RefCell::new(Interner::new());
+}
+
+struct Interner {
+    names: Vec<&'static str>,
+    indices: HashMap<&'static str, u32>,
+}
+
+impl Interner {
+    fn new() -> Self {
+        let mut interner = Self {
+            names: Vec::new(),
+            indices: HashMap::new(),
+        };
+
+        for name in PREDEFINED {
+            interner.insert(name);
+        }
+
+        interner
+    }
+
+    fn insert(&mut self, name: &'static str) -> u32 {
+        let index = u32::try_from(self.names.len()).expect("Too many symbols");
+
+        self.names.push(name);
+        self.indices.insert(name, index);
+
+        index
+    }
+}
+
+/// An interned name, such as the identifier of a variable or a property.
+/// Symbols are indices in a table of the names of their thread, so they are
+/// compared and hashed as integers. They can't be sent to other threads,
+/// which have their own tables
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32, PhantomData<*const ()>);
+
+impl Symbol {
+    pub const INIT: Self = Self(0, PhantomData);
+    pub const THIS: Self = Self(1, PhantomData);
+    pub const SUPER: Self = Self(2, PhantomData);
+
+    /// Returns the symbol of `name`, creating it if it doesn't exist yet
+    #[must_use]
+    pub fn intern(name: &str) -> Self {
+        SYMBOLS.with_borrow_mut(|symbols| {
+            let index = match symbols.indices.get(name) {
+                Some(&index) => index,
+                None => symbols.insert(Box::leak(name.into())),
+            };
+
+            Self(index, PhantomData)
+        })
+    }
+
+    /// Returns the symbol of `name` if it exists. Names that were never
+    /// interned can't be bound to anything, so looking them up doesn't need
+    /// to intern them
+    #[must_use]
+    pub fn lookup(name: &str) -> Option<Self> {
+        SYMBOLS.with_borrow(|symbols| {
+            symbols
+                .indices
+                .get(name)
+                .map(|&index| Self(index, PhantomData))
+        })
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        SYMBOLS.with_borrow(|symbols| symbols.names[self.0 as usize])
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl From<Symbol> for std::rc::Rc<str> {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().into()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Hasher for keys that are already unique integers, such as symbols and
+/// node ids, which don't need the resistance to collisions of the default
+/// hasher. It mixes the bits of the keys like the `FxHasher` of rustc
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdHasher(u64);
+
+impl IdHasher {
+    const SEED: u64 = 0xf135_7aea_2e62_a9c5;
+}
+
+impl Hasher for IdHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(u64::from(byte));
+        }
+    }
+
+    fn write_u64(&mut self, integer: u64) {
+        self.0 = self.0.wrapping_add(integer).wrapping_mul(Self::SEED);
+    }
+
+    fn write_u32(&mut self, integer: u32) {
+        self.write_u64(u64::from(integer));
+    }
+
+    fn write_usize(&mut self, integer: usize) {
+        self.write_u64(integer as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        // The multiplication leaves the most entropy in the high bits,
+        // while hash tables index their buckets with the low ones
+        self.0.rotate_left(26)
+    }
+}
+
+pub type BuildIdHasher = BuildHasherDefault<IdHasher>;
+
+/// A map keyed by symbols
+pub type SymbolMap<V> = HashMap<Symbol, V, BuildIdHasher>;
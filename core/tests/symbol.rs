@@ -0,0 +1,66 @@
+use std::rc::Rc;
+
+use lox_core::{Symbol, SymbolMap};
+
+#[test]
+fn interning_the_same_name_gives_the_same_symbol() {
+    let first = Symbol::intern("counter");
+    let second = Symbol::intern(&String::from("counter"));
+
+    assert_eq!(first, second);
+    assert_ne!(first, Symbol::intern("Counter"));
+    assert_eq!(Symbol::from("counter"), first);
+}
+
+#[test]
+fn symbols_give_back_their_name() {
+    let symbol = Symbol::intern("value");
+
+    assert_eq!(symbol.as_str(), "value");
+    assert_eq!(symbol.to_string(), "value");
+    assert_eq!(format!("{symbol:?}"), r#""value""#);
+    assert_eq!(Rc::<str>::from(symbol), Rc::from("value"));
+}
+
+#[test]
+fn looking_up_names_does_not_intern_them() {
+    assert_eq!(Symbol::lookup("neverInterned"), None);
+    assert_eq!(Symbol::lookup("neverInterned"), None);
+
+    let symbol = Symbol::intern("interned");
+    assert_eq!(Symbol::lookup("interned"), Some(symbol));
+}
+
+#[test]
+fn predefined_symbols_are_interned() {
+    assert_eq!(Symbol::intern("init"), Symbol::INIT);
+    assert_eq!(Symbol::intern("this"), Symbol::THIS);
+    assert_eq!(Symbol::intern("super"), Symbol::SUPER);
+    assert_eq!(Symbol::SUPER.as_str(), "super");
+}
+
+#[test]
+fn symbols_key_maps() {
+    let mut map = SymbolMap::default();
+    map.insert(Symbol::intern("a"), 1);
+    map.insert(Symbol::intern("b"), 2);
+    map.insert(Symbol::intern("a"), 3);
+
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&Symbol::intern("a")), Some(&3));
+    assert_eq!(map.get(&Symbol::intern("c")), None);
+}
+
+#[test]
+fn symbols_can_be_interned_on_any_thread() {
+    let name = Symbol::intern("shared").as_str();
+    let other = std::thread::spawn(|| Symbol::intern("shared").as_str())
+        .join()
+        .unwrap();
+
+    assert_eq!(name, other);
+    assert_eq!(
+        std::thread::spawn(|| Symbol::INIT.as_str()).join().unwrap(),
+        "init"
+    );
+}
@@ -0,0 +1,174 @@
+use std::{
+    cell::RefCell,
+    io::{BufRead, Write},
+    rc::Rc,
+    time::Instant,
+};
+
+use lox_core::{Result, Symbol};
+
+use crate::{
+    heap::Heap, limits::Limits, CallContext, Environment, ForeignClass, Interpreter, NativeBuilder,
+    RuntimeError, Sandbox, Stdlib, Streams, Value,
+};
+
+/// Default maximum depth of nested calls
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+/// Builder for interpreters that need a non-default configuration
+///
+/// ```ignore
+/// let output = OutputBuffer::default();
+/// let interpreter = InterpreterBuilder::new()
+///     .stdout(output.clone())
+///     .stdin(std::io::Cursor::new("input"))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct InterpreterBuilder {
+    streams: Streams,
+    max_call_depth: usize,
+    limits: Limits,
+    heap: Heap,
+    sandbox: Sandbox,
+
+    /// Natives and foreign classes registered by the host, defined if the
+    /// sandbox allows it
+    natives: Vec<(Rc<str>, Value)>,
+}
+
+impl Default for InterpreterBuilder {
+    fn default() -> Self {
+        Self {
+            streams: Streams::default(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            limits: Limits::default(),
+            heap: Heap::default(),
+            sandbox: Sandbox::default(),
+            natives: Vec::new(),
+        }
+    }
+}
+
+impl InterpreterBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how deep calls can be nested before a stack overflow error is
+    /// raised, defaults to [`DEFAULT_MAX_CALL_DEPTH`]
+    #[must_use]
+    pub const fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Sets how many expressions can be evaluated before
+    /// [`RuntimeError::OutOfFuel`](crate::RuntimeError::OutOfFuel) is raised,
+    /// unlimited by default
+    #[must_use]
+    pub const fn fuel(mut self, fuel: u64) -> Self {
+        self.limits.fuel = Some(fuel);
+        self
+    }
+
+    /// Sets the instant after which
+    /// [`RuntimeError::DeadlineExceeded`](crate::RuntimeError::DeadlineExceeded)
+    /// is raised, unlimited by default
+    #[must_use]
+    pub const fn deadline(mut self, deadline: Instant) -> Self {
+        self.limits.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets how many bytes programs can use before
+    /// [`RuntimeError::OutOfMemory`](crate::RuntimeError::OutOfMemory) is
+    /// raised, unlimited by default
+    #[must_use]
+    pub const fn memory_limit(mut self, limit: usize) -> Self {
+        self.heap.limit = Some(limit);
+        self
+    }
+
+    /// Sets the capabilities granted to programs, defaults to
+    /// [`Sandbox::all`]
+    #[must_use]
+    pub const fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Registers a native function to be defined as a global, unless the
+    /// sandbox denies host natives
+    #[must_use]
+    pub fn native<F>(mut self, native: NativeBuilder, function: F) -> Self
+    where
+        F: Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    {
+        let identifier = Rc::clone(native.identifier());
+        self.natives.push((identifier, native.build(function)));
+        self
+    }
+
+    /// Registers a foreign class to be defined as a global, unless the
+    /// sandbox denies host natives
+    #[must_use]
+    pub fn foreign_class(mut self, class: &Rc<ForeignClass>) -> Self {
+        let identifier = Rc::clone(&class.identifier);
+        self.natives.push((identifier, ForeignClass::value(class)));
+        self
+    }
+
+    /// Sets the stream `print` writes to, defaults to the process' stdout
+    #[must_use]
+    pub fn stdout(mut self, stdout: impl Write + 'static) -> Self {
+        self.streams.stdout = Box::new(stdout);
+        self
+    }
+
+    /// Sets the stream `readLine` reads from, defaults to the process' stdin
+    #[must_use]
+    pub fn stdin(mut self, stdin: impl BufRead + 'static) -> Self {
+        self.streams.stdin = Box::new(stdin);
+        self
+    }
+
+    /// Sets the stream errors are reported to, defaults to the process' stderr
+    #[must_use]
+    pub fn stderr(mut self, stderr: impl Write + 'static) -> Self {
+        self.streams.stderr = Box::new(stderr);
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> Interpreter {
+        let mut environment = Environment::new();
+
+        for (identifier, value) in Value::globals(self.sandbox) {
+            environment.define(Symbol::intern(identifier), Some(value));
+        }
+
+        if self.sandbox.allows_host_natives() {
+            for (identifier, native) in self.natives {
+                environment.define(Symbol::intern(&identifier), Some(native));
+            }
+        }
+
+        let environment = Rc::new(RefCell::new(environment));
+
+        Interpreter {
+            globals: Rc::clone(&environment),
+            environment,
+            locals: Rc::default(),
+            streams: self.streams,
+            call_stack: Vec::new(),
+            trace: None,
+            abort: None,
+            max_call_depth: self.max_call_depth,
+            limits: self.limits,
+            heap: self.heap,
+            sandbox: self.sandbox,
+        }
+    }
+}
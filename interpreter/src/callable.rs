@@ -1,7 +1,9 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
-use crate::{Environment, Value};
-use parser::Statement;
+use crate::{Environment, ForeignClass, Interpreter, LoxInstance, RuntimeError, Value};
+use lox_core::{Error, Frame, Result, Span, Symbol, SymbolMap};
+use parser::{Parameter, Statement};
+use resolver::Locals;
 
 #[derive(Debug, Clone)]
 pub struct Callable {
@@ -9,6 +11,31 @@ pub struct Callable {
     pub kind: CallableKind,
 }
 
+impl Callable {
+    /// The identifier of the function or class, if it has one
+    #[must_use]
+    pub fn identifier(&self) -> Option<Rc<str>> {
+        match self.kind {
+            CallableKind::NativeFunction { ref identifier, .. } => Some(Rc::clone(identifier)),
+            CallableKind::LoxClass(ref class) => Some(Rc::clone(&class.identifier)),
+            CallableKind::LoxFunction { ref identifier, .. } => identifier.clone(),
+            CallableKind::ForeignClass(ref class) => Some(Rc::clone(&class.identifier)),
+        }
+    }
+
+    /// Binds a method to `instance`, which it receives as `this` when called
+    #[must_use]
+    pub fn bind(&self, instance: &Rc<RefCell<LoxInstance>>) -> Self {
+        let mut method = self.clone();
+
+        if let CallableKind::LoxFunction { ref mut this, .. } = method.kind {
+            *this = Some(Rc::clone(instance));
+        }
+
+        method
+    }
+}
+
 impl PartialEq for Callable {
     fn eq(&self, other: &Self) -> bool {
         self.kind == other.kind
@@ -21,25 +48,99 @@ impl std::fmt::Display for Callable {
     }
 }
 
-pub type NativeFunction = Rc<dyn Fn(&[Value]) -> Value>;
+pub type NativeFunction =
+    Rc<dyn Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<Value, RuntimeError>>;
+
+/// Information about the call site of a function, available to native
+/// functions so they can report errors at the right position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallContext {
+    pub span: Span,
+}
+
+impl CallContext {
+    /// Builds an error located at the call site
+    #[must_use]
+    pub const fn error(&self, source: RuntimeError) -> Error<RuntimeError> {
+        Error {
+            span: self.span,
+            source,
+        }
+    }
+
+    /// Extracts a number from an argument
+    ///
+    /// # Errors
+    /// This function will error if `value` is not a number
+    pub const fn number(&self, value: &Value) -> Result<f64, RuntimeError> {
+        match value {
+            Value::Number(number) => Ok(*number),
+            x => Err(self.error(RuntimeError::TypeError {
+                expected: "number",
+                found: x.type_name(),
+            })),
+        }
+    }
+
+    /// Extracts a string from an argument
+    ///
+    /// # Errors
+    /// This function will error if `value` is not a string
+    pub fn string(&self, value: &Value) -> Result<Rc<str>, RuntimeError> {
+        match value {
+            Value::String(string) => Ok(Rc::clone(string)),
+            x => Err(self.error(RuntimeError::TypeError {
+                expected: "string",
+                found: x.type_name(),
+            })),
+        }
+    }
+}
+
+/// A call in progress, recorded to show the stack trace of runtime errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallFrame {
+    /// The identifier of the function, `None` if it is anonymous
+    pub function: Option<Rc<str>>,
+    pub call_site: Span,
+}
+
+impl From<&CallFrame> for Frame {
+    fn from(frame: &CallFrame) -> Self {
+        Self {
+            function: frame.function.as_deref().map(String::from),
+            call_site: frame.call_site,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub enum CallableKind {
-    NativeFunction(NativeFunction),
+    NativeFunction {
+        identifier: Rc<str>,
+        function: NativeFunction,
+    },
     LoxFunction {
         identifier: Option<Rc<str>>,
-        parameters: Rc<[Rc<str>]>,
+        parameters: Rc<[Parameter]>,
         body: Rc<[Statement]>,
         closure: Rc<RefCell<Environment>>,
+
+        /// The local variables of the program the function is declared in
+        locals: Rc<Locals>,
         is_initializer: bool,
+
+        /// The instance a method is bound to, `None` for functions
+        this: Option<Rc<RefCell<LoxInstance>>>,
     },
-    LoxClass(LoxClass),
+    LoxClass(Rc<LoxClass>),
+    ForeignClass(Rc<ForeignClass>),
 }
 
 impl std::fmt::Debug for CallableKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::NativeFunction(_) => write!(f, "<native fn>"),
+            Self::NativeFunction { identifier, .. } => write!(f, "<native fn {identifier}>"),
             Self::LoxFunction {
                 identifier: Some(identifier),
                 ..
@@ -47,7 +148,8 @@ impl std::fmt::Debug for CallableKind {
             Self::LoxFunction {
                 identifier: None, ..
             } => write!(f, "<anonymous fn>"),
-            Self::LoxClass(LoxClass { identifier, .. }) => write!(f, "<class {identifier}>"),
+            Self::LoxClass(class) => write!(f, "{class:?}"),
+            Self::ForeignClass(class) => write!(f, "{class:?}"),
         }
     }
 }
@@ -62,18 +164,22 @@ impl PartialEq for CallableKind {
     #[allow(ambiguous_wide_pointer_comparisons)]
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::NativeFunction(a), Self::NativeFunction(b)) => {
-                let a = a.as_ref() as *const _;
-                let b = b.as_ref() as *const _;
+            (
+                Self::NativeFunction { function: a, .. },
+                Self::NativeFunction { function: b, .. },
+            ) => {
+                let a = std::ptr::from_ref(a.as_ref());
+                let b = std::ptr::from_ref(b.as_ref());
 
                 a == b
             }
             (Self::LoxFunction { body: a, .. }, Self::LoxFunction { body: b, .. }) => {
-                let a = a.as_ref() as *const _;
-                let b = b.as_ref() as *const _;
+                let a = std::ptr::from_ref(a.as_ref());
+                let b = std::ptr::from_ref(b.as_ref());
 
                 a == b
             }
+            (Self::ForeignClass(a), Self::ForeignClass(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -82,14 +188,14 @@ impl PartialEq for CallableKind {
 #[derive(Clone)]
 pub struct LoxClass {
     pub identifier: Rc<str>,
-    pub methods: HashMap<Rc<str>, Callable>,
-    pub super_class: Option<Rc<LoxClass>>,
+    pub methods: SymbolMap<Callable>,
+    pub super_class: Option<Rc<Self>>,
 }
 
 impl LoxClass {
     #[must_use]
-    pub fn find_method(&self, identifier: &Rc<str>) -> Option<Callable> {
-        if let Some(method) = self.methods.get(identifier) {
+    pub fn find_method(&self, identifier: Symbol) -> Option<Callable> {
+        if let Some(method) = self.methods.get(&identifier) {
             return Some(method.clone());
         }
 
@@ -99,4 +205,16 @@ impl LoxClass {
 
         None
     }
+
+    /// Names of the methods of the class and its superclasses
+    pub fn method_names(&self) -> impl Iterator<Item = &Symbol> {
+        std::iter::successors(Some(self), |class| class.super_class.as_deref())
+            .flat_map(|class| class.methods.keys())
+    }
+}
+
+impl std::fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<class {}>", self.identifier)
+    }
 }
@@ -0,0 +1,12 @@
+use crate::Value;
+
+/// How the execution of a statement ended, telling the enclosing loop or
+/// function whether to carry on
+#[derive(Debug, Clone)]
+pub enum Completion {
+    /// The next statement runs
+    Normal,
+    Return(Value),
+    Break,
+    Continue,
+}
@@ -0,0 +1,258 @@
+use std::{collections::HashMap, hash::BuildHasher, rc::Rc};
+
+use crate::{stdlib, ForeignClass, RuntimeError, Value};
+
+thread_local! {
+    /// Class of the objects created from Rust vectors, shared by all of them
+    static LIST_CLASS: Rc<ForeignClass> = stdlib::list_class();
+
+    /// Class of the objects created from Rust maps, shared by all of them
+    static MAP_CLASS: Rc<ForeignClass> = stdlib::map_class();
+}
+
+/// Conversion from a Rust value into a Lox value
+pub trait IntoLox {
+    fn into_lox(self) -> Value;
+}
+
+/// Conversion from a Lox value into a Rust value
+pub trait FromLox: Sized {
+    /// # Errors
+    /// This function will error if `value` is not of the expected type
+    fn from_lox(value: Value) -> Result<Self, RuntimeError>;
+}
+
+const fn type_error(expected: &'static str, found: &Value) -> RuntimeError {
+    RuntimeError::TypeError {
+        expected,
+        found: found.type_name(),
+    }
+}
+
+impl IntoLox for Value {
+    fn into_lox(self) -> Value {
+        self
+    }
+}
+
+impl FromLox for Value {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        Ok(value)
+    }
+}
+
+impl IntoLox for () {
+    fn into_lox(self) -> Value {
+        Value::Nil
+    }
+}
+
+impl FromLox for () {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Nil => Ok(()),
+            x => Err(type_error("nil", &x)),
+        }
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl FromLox for bool {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Boolean(boolean) => Ok(boolean),
+            x => Err(type_error("boolean", &x)),
+        }
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromLox for f64 {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Number(number) => Ok(number),
+            x => Err(type_error("number", &x)),
+        }
+    }
+}
+
+impl IntoLox for f32 {
+    fn into_lox(self) -> Value {
+        Value::Number(self.into())
+    }
+}
+
+impl FromLox for f32 {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        #[allow(clippy::cast_possible_truncation)]
+        f64::from_lox(value).map(|number| number as Self)
+    }
+}
+
+macro_rules! integer_conversions {
+    ($($integer: ty),+ $(,)?) => {
+        $(
+            impl IntoLox for $integer {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn into_lox(self) -> Value {
+                    Value::Number(self as f64)
+                }
+            }
+
+            impl FromLox for $integer {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss,
+                    clippy::cast_precision_loss,
+                    clippy::cast_lossless,
+                )]
+                fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+                    let number = f64::from_lox(value)?;
+
+                    // `MAX + 1` is a power of two, so it is exact even for the
+                    // types whose `MAX` rounds up to it as a float
+                    let end = <$integer>::MAX as f64 + 1.0;
+                    let in_range = (<$integer>::MIN as f64..end).contains(&number);
+
+                    if number.fract() != 0.0 || !in_range {
+                        return Err(RuntimeError::TypeError {
+                            expected: stringify!($integer),
+                            found: "number",
+                        });
+                    }
+
+                    Ok(number as $integer)
+                }
+            }
+        )+
+    };
+}
+
+integer_conversions!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl IntoLox for Rc<str> {
+    fn into_lox(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl FromLox for Rc<str> {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::String(string) => Ok(string),
+            x => Err(type_error("string", &x)),
+        }
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl FromLox for String {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        Rc::<str>::from_lox(value).map(|string| string.as_ref().into())
+    }
+}
+
+impl IntoLox for &str {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl<T: IntoLox> IntoLox for Option<T> {
+    fn into_lox(self) -> Value {
+        self.map_or(Value::Nil, IntoLox::into_lox)
+    }
+}
+
+impl<T: FromLox> FromLox for Option<T> {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Nil => Ok(None),
+            x => T::from_lox(x).map(Some),
+        }
+    }
+}
+
+/// Vectors are converted into instances of a foreign class, whose methods
+/// give access to the elements
+impl<T: IntoLox> IntoLox for Vec<T> {
+    fn into_lox(self) -> Value {
+        let list: Vec<Value> = self.into_iter().map(IntoLox::into_lox).collect();
+
+        LIST_CLASS.with(|class| ForeignClass::instantiate(class, list))
+    }
+}
+
+impl<T: FromLox> FromLox for Vec<T> {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::UserData(user_data)
+                if LIST_CLASS.with(|class| Rc::ptr_eq(&user_data.class, class)) =>
+            {
+                let list = user_data.borrow_mut::<Vec<Value>>().ok_or_else(|| {
+                    RuntimeError::ForeignObjectInUse(Rc::clone(&user_data.class.identifier))
+                })?;
+
+                list.iter().cloned().map(T::from_lox).collect()
+            }
+            x => Err(type_error("list", &x)),
+        }
+    }
+}
+
+/// Maps are converted into instances of a foreign class, whose methods give
+/// access to the entries
+impl<T: IntoLox, S: BuildHasher> IntoLox for HashMap<String, T, S> {
+    fn into_lox(self) -> Value {
+        let map: HashMap<Rc<str>, Value> = self
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into_lox()))
+            .collect();
+
+        MAP_CLASS.with(|class| ForeignClass::instantiate(class, map))
+    }
+}
+
+/// Maps and instances, whose fields are their entries, are converted into
+/// maps. The methods of instances are ignored
+impl<T: FromLox, S: BuildHasher + Default> FromLox for HashMap<String, T, S> {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::UserData(user_data)
+                if MAP_CLASS.with(|class| Rc::ptr_eq(&user_data.class, class)) =>
+            {
+                let map = user_data
+                    .borrow_mut::<HashMap<Rc<str>, Value>>()
+                    .ok_or_else(|| {
+                        RuntimeError::ForeignObjectInUse(Rc::clone(&user_data.class.identifier))
+                    })?;
+
+                map.iter()
+                    .map(|(key, value)| Ok((key.as_ref().into(), T::from_lox(value.clone())?)))
+                    .collect()
+            }
+            Value::Instance(instance) => instance
+                .borrow()
+                .fields
+                .iter()
+                .map(|(key, value)| Ok((key.as_ref().into(), T::from_lox(value.clone())?)))
+                .collect(),
+            x => Err(type_error("map", &x)),
+        }
+    }
+}
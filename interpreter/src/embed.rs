@@ -0,0 +1,102 @@
+use std::rc::Rc;
+
+use lexer::Lexer;
+use lox_core::{Diagnostics, Span, Symbol};
+use parser::{Parser, Statement};
+use resolver::Resolver;
+
+use crate::{CallContext, Interpreter, IntoLox, RuntimeError, Value};
+
+impl Interpreter {
+    /// Runs `source` as a program, returning the value of its last statement
+    /// if that statement is an expression, or `nil` otherwise
+    ///
+    /// # Errors
+    /// This function will return the diagnostics of the first phase
+    /// (scanning and parsing, resolution or execution) that fails
+    pub fn eval(&mut self, source: &str) -> Result<Value, Diagnostics> {
+        let (tokens, mut diagnostics) = Lexer::new(source).scan();
+        let (program, parser_diagnostics) = Parser::new(&tokens).parse();
+        diagnostics.extend(parser_diagnostics);
+
+        if diagnostics.has_errors() {
+            return Err(diagnostics);
+        }
+
+        let mut resolver = Resolver::new();
+        let diagnostics = resolver.resolve(&program);
+
+        if diagnostics.has_errors() {
+            return Err(diagnostics);
+        }
+
+        // Natives can evaluate programs while another one runs, which must
+        // find its own variables once they return
+        let locals = std::mem::replace(&mut self.locals, Rc::new(resolver.locals));
+        let environment = std::mem::replace(&mut self.environment, Rc::clone(&self.globals));
+
+        let result = self.evaluate_program(&program);
+
+        self.environment = environment;
+        self.locals = locals;
+
+        result.map_err(|error| self.runtime_diagnostic(error).into())
+    }
+
+    /// Runs `program`, returning the value of its last statement if that
+    /// statement is an expression
+    fn evaluate_program(&mut self, program: &[Statement]) -> lox_core::Result<Value, RuntimeError> {
+        let mut value = Value::Nil;
+        for statement in program {
+            value = match statement {
+                Statement::Expression(expression) => self.evaluate(expression)?,
+                statement => {
+                    self.execute(statement)?;
+                    Value::Nil
+                }
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the value of the global variable `identifier`, if it is
+    /// declared and has been assigned a value
+    #[must_use]
+    pub fn get_global(&self, identifier: &str) -> Option<Value> {
+        let identifier = Symbol::lookup(identifier)?;
+        self.globals.borrow().get(identifier)
+    }
+
+    /// Defines the global variable `identifier`, overriding its value if it
+    /// already exists
+    pub fn set_global(&mut self, identifier: &str, value: impl IntoLox) {
+        self.globals
+            .borrow_mut()
+            .define(Symbol::intern(identifier), Some(value.into_lox()));
+    }
+
+    /// Calls the global function `identifier` with the given arguments
+    ///
+    /// # Errors
+    /// This function will error if the global doesn't exist, is not callable,
+    /// or if the call itself fails
+    pub fn call_function(
+        &mut self,
+        identifier: &str,
+        args: &[Value],
+    ) -> Result<Value, Diagnostics> {
+        let context = CallContext {
+            span: Span::default(),
+        };
+        let callee = self.get_global(identifier).ok_or_else(|| {
+            context.error(RuntimeError::undeclared_variable(
+                identifier,
+                self.globals.borrow().visible_names(),
+            ))
+        })?;
+
+        self.call_value(callee, args, &context)
+            .map_err(|error| self.runtime_diagnostic(error).into())
+    }
+}
@@ -1,12 +1,22 @@
 use crate::{RuntimeError, Value};
-use lox_core::{Error, Result};
+use lox_core::{Error, Result, Symbol, SymbolMap};
 use parser::Reference;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use resolver::Slot;
+use std::{cell::RefCell, rc::Rc};
 
+/// A scope of variables. The global scope looks its variables up by name,
+/// while local scopes are flat frames addressed by the slots computed by the
+/// resolver
 #[derive(Debug, Default)]
 pub struct Environment {
-    parent: Option<Rc<RefCell<Environment>>>,
-    values: HashMap<Rc<str>, State>,
+    parent: Option<Rc<RefCell<Self>>>,
+    values: SymbolMap<State>,
+
+    /// Variables of a local scope, in the order they were declared
+    slots: Vec<State>,
+
+    /// Names of the slots, only used for error messages
+    names: Vec<Symbol>,
 }
 
 #[derive(Debug, Clone)]
@@ -21,7 +31,9 @@ impl Environment {
     pub fn new() -> Self {
         Self {
             parent: None,
-            values: HashMap::new(),
+            values: SymbolMap::default(),
+            slots: Vec::new(),
+            names: Vec::new(),
         }
     }
 
@@ -29,38 +41,51 @@ impl Environment {
     pub fn spawn_child(parent: &Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
             parent: Some(Rc::clone(parent)),
-            values: HashMap::new(),
+            values: SymbolMap::default(),
+            slots: Vec::new(),
+            names: Vec::new(),
         }))
     }
 
-    /// Creates a new variable in the environment or overrides its
-    /// value if it already exists
-    pub fn define(&mut self, name: &Rc<str>, value: Option<Value>) {
-        self.values.insert(
-            Rc::clone(name),
-            value.map_or(State::Unassigned, State::Assigned),
-        );
+    /// Creates a new variable in the environment. In the global scope, this
+    /// overrides its value if it already exists, while in a local scope the
+    /// variable takes the next slot
+    pub fn define(&mut self, name: Symbol, value: Option<Value>) {
+        let state = value.map_or(State::Unassigned, State::Assigned);
+
+        if self.parent.is_some() {
+            self.slots.push(state);
+            self.names.push(name);
+        } else {
+            self.values.insert(name, state);
+        }
+    }
+
+    /// Returns the value of a global variable, if it has been assigned one
+    #[must_use]
+    pub fn get(&self, identifier: Symbol) -> Option<Value> {
+        match self.values.get(&identifier) {
+            Some(State::Assigned(value)) => Some(value.clone()),
+            _ => None,
+        }
     }
 
-    /// Overrides the value of an existing variable
+    /// Overrides the value of an existing global variable
     ///
     /// # Errors
     /// This function will error if no variable is found with the given `name`
     pub fn assign(&mut self, reference: &Reference, value: Value) -> Result<(), RuntimeError> {
-        if self.values.contains_key(&reference.identifier) {
-            self.values
-                .insert(Rc::clone(&reference.identifier), State::Assigned(value));
+        if let std::collections::hash_map::Entry::Occupied(mut e) =
+            self.values.entry(reference.identifier)
+        {
+            e.insert(State::Assigned(value));
             return Ok(());
         }
 
-        Err(Error {
-            line: reference.line,
-            column: reference.column,
-            source: RuntimeError::UndeclaredVariable(Rc::clone(&reference.identifier)),
-        })
+        Err(self.undeclared(reference))
     }
 
-    /// Returns the value of an existing variable
+    /// Returns the value of an existing global variable
     ///
     /// # Errors
     /// This function will error if no variable is found with the given `name`
@@ -73,79 +98,95 @@ impl Environment {
 
         match state {
             State::Assigned(value) => Ok(value),
-            State::Unassigned => Err(Error {
-                line: reference.line,
-                column: reference.column,
-                source: RuntimeError::UnassignedVariable(Rc::clone(&reference.identifier)),
-            }),
-            State::Undeclared => Err(Error {
-                line: reference.line,
-                column: reference.column,
-                source: RuntimeError::UndeclaredVariable(Rc::clone(&reference.identifier)),
-            }),
+            State::Unassigned => Err(Self::unassigned(reference)),
+            State::Undeclared => Err(self.undeclared(reference)),
         }
     }
 
-    /// Returns the value of an existing variable at a specific enclosing scope
+    /// Returns the value of an existing local variable, in the slot of a
+    /// specific enclosing scope
     ///
     /// # Errors
-    /// This function will error if no variable is found with the given `name`
-    pub fn lookup_at(&self, distance: usize, reference: &Reference) -> Result<Value, RuntimeError> {
-        let state = match distance {
-            0 => self.values[&reference.identifier].clone(),
-            _ => self.ancestor(distance).borrow().values[&reference.identifier].clone(),
+    /// This function will error if the variable has not been assigned a value
+    pub fn lookup_at(&self, slot: Slot, reference: &Reference) -> Result<Value, RuntimeError> {
+        let state = match slot.depth {
+            0 => self.slots[slot.index].clone(),
+            _ => self.ancestor(slot.depth).borrow().slots[slot.index].clone(),
         };
 
         match state {
             State::Assigned(value) => Ok(value),
-            State::Unassigned => Err(Error {
-                line: reference.line,
-                column: reference.column,
-                source: RuntimeError::UnassignedVariable(Rc::clone(&reference.identifier)),
-            }),
+            State::Unassigned => Err(Self::unassigned(reference)),
             State::Undeclared => unreachable!(),
         }
     }
 
-    /// Overrides the value of an existing variable at a specific enclosing scope
-    ///
-    /// # Errors
-    /// This function will error if no variable is found with the given `name`
-    pub fn assign_at(
-        &mut self,
-        distance: usize,
-        reference: &Reference,
-        value: Value,
-    ) -> Result<(), RuntimeError> {
-        if distance == 0 {
-            let values = &mut self.values;
-
-            if values.contains_key(&reference.identifier) {
-                values.insert(Rc::clone(&reference.identifier), State::Assigned(value));
-
-                Ok(())
-            } else {
-                Err(Error {
-                    line: reference.line,
-                    column: reference.column,
-                    source: RuntimeError::UndeclaredVariable(Rc::clone(&reference.identifier)),
-                })
-            }
-        } else {
-            let ancestor = self.ancestor(distance);
-
-            let values = &mut ancestor.borrow_mut().values;
-            if values.contains_key(&reference.identifier) {
-                values.insert(Rc::clone(&reference.identifier), State::Assigned(value));
-
-                Ok(())
-            } else {
-                Err(Error {
-                    line: reference.line,
-                    column: reference.column,
-                    source: RuntimeError::UndeclaredVariable(Rc::clone(&reference.identifier)),
-                })
-            }
+    /// Returns the value of a local variable, in the slot of a specific
+    /// enclosing scope, if it has been assigned one
+    #[must_use]
+    pub fn get_at(&self, slot: Slot) -> Option<Value> {
+        let state = match slot.depth {
+            0 => self.slots[slot.index].clone(),
+            _ => self.ancestor(slot.depth).borrow().slots[slot.index].clone(),
+        };
+
+        match state {
+            State::Assigned(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Overrides the value of an existing local variable, in the slot of a
+    /// specific enclosing scope
+    pub fn assign_at(&mut self, slot: Slot, value: Value) {
+        match slot.depth {
+            0 => self.slots[slot.index] = State::Assigned(value),
+            _ => self.ancestor(slot.depth).borrow_mut().slots[slot.index] = State::Assigned(value),
+        }
+    }
+
+    /// The enclosing scope, `None` for the global scope
+    pub(crate) const fn parent(&self) -> Option<&Rc<RefCell<Self>>> {
+        self.parent.as_ref()
+    }
+
+    /// Every value assigned to a variable of this environment
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Value> {
+        self.values
+            .values()
+            .chain(&self.slots)
+            .filter_map(|state| match state {
+                State::Assigned(value) => Some(value),
+                _ => None,
+            })
+    }
+
+    /// Every name declared in this environment or its ancestors
+    #[must_use]
+    pub fn visible_names(&self) -> Vec<Symbol> {
+        let mut names: Vec<_> = self.values.keys().chain(&self.names).copied().collect();
+
+        if let Some(ref parent) = self.parent {
+            names.extend(parent.borrow().visible_names());
+        }
+
+        names
+    }
+
+    fn unassigned(reference: &Reference) -> Error<RuntimeError> {
+        Error {
+            span: reference.span,
+            source: RuntimeError::UnassignedVariable(reference.identifier.into()),
+        }
+    }
+
+    fn undeclared(&self, reference: &Reference) -> Error<RuntimeError> {
+        Error {
+            span: reference.span,
+            source: RuntimeError::undeclared_variable(
+                reference.identifier.as_str(),
+                self.visible_names(),
+            ),
         }
     }
 
@@ -1,9 +1,10 @@
 use std::rc::Rc;
 
-use crate::Value;
+use crate::suggest;
+use lox_core::Diagnose;
 use thiserror::Error as ErrorTrait;
 
-#[derive(Debug, ErrorTrait)]
+#[derive(Debug, Clone, ErrorTrait)]
 pub enum RuntimeError {
     #[error(r#"Expected expression of type "{expected}", found type "{found}""#)]
     TypeError {
@@ -14,33 +15,151 @@ pub enum RuntimeError {
     #[error("Attempted to divide by zero")]
     DivideByZero,
 
-    #[error(r#"Undeclared variable "{0}""#)]
-    UndeclaredVariable(Rc<str>),
+    #[error(r#"Undeclared variable "{identifier}""#)]
+    UndeclaredVariable {
+        identifier: Rc<str>,
+
+        /// Similarly named variables, in case of a typo
+        suggestions: Vec<Rc<str>>,
+    },
 
     #[error(r#"Attempted to use variable "{0}" before it was assigned a value"#)]
     UnassignedVariable(Rc<str>),
 
-    #[error("Unexpected break statement outside of loop")]
-    Break,
-
-    #[error("Unexpected continue statement outside of loop")]
-    Continue,
-
     #[error(r#"Type "{0}" is not callable"#)]
     TypeIsNotCallable(&'static str),
 
     #[error("Function expected {expected} arguments but got {found}")]
     ImcorrectNumberOfArguments { expected: usize, found: usize },
 
-    #[error("Unexpected return statement outside of function or method")]
-    Return(Value),
-
     #[error(r#"Attempted to access property in value of type "{0}""#)]
     TypeIsNotInstance(&'static str),
 
-    #[error(r#"Attempted to access undefined property "{0}""#)]
-    UndefinedProperty(Rc<str>),
+    #[error(r#"Attempted to access undefined property "{identifier}""#)]
+    UndefinedProperty {
+        identifier: Rc<str>,
+
+        /// Similarly named properties, in case of a typo
+        suggestions: Vec<Rc<str>>,
+    },
 
     #[error("A class can only inherit from another class")]
     SuperClassMustBeAClass,
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(Rc<str>),
+
+    #[error("I/O error: {0}")]
+    Io(Rc<str>),
+
+    #[error(r#"Class "{0}" cannot be instantiated from Lox code"#)]
+    NotConstructible(Rc<str>),
+
+    #[error(r#"Attempted to assign to read-only property "{0}""#)]
+    ReadOnlyProperty(Rc<str>),
+
+    #[error(r#"Attempted to use an instance of "{0}" while it is already in use"#)]
+    ForeignObjectInUse(Rc<str>),
+
+    #[error("Stack overflow, calls cannot be nested more than {0} levels deep")]
+    StackOverflow(usize),
+
+    #[error("Execution ran out of fuel")]
+    OutOfFuel,
+
+    #[error("Execution exceeded its deadline")]
+    DeadlineExceeded,
+
+    #[error("Execution was interrupted")]
+    Interrupted,
+
+    #[error("Execution exceeded its memory limit of {0} bytes")]
+    OutOfMemory(usize),
+}
+
+impl Diagnose for RuntimeError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::TypeError { .. } => "E0401",
+            Self::DivideByZero => "E0402",
+            Self::UndeclaredVariable { .. } => "E0403",
+            Self::UnassignedVariable(_) => "E0404",
+            Self::TypeIsNotCallable(_) => "E0407",
+            Self::ImcorrectNumberOfArguments { .. } => "E0408",
+            Self::TypeIsNotInstance(_) => "E0410",
+            Self::UndefinedProperty { .. } => "E0411",
+            Self::SuperClassMustBeAClass => "E0412",
+            Self::InvalidArgument(_) => "E0413",
+            Self::Io(_) => "E0414",
+            Self::NotConstructible(_) => "E0415",
+            Self::ReadOnlyProperty(_) => "E0416",
+            Self::ForeignObjectInUse(_) => "E0417",
+            Self::StackOverflow(_) => "E0418",
+            Self::OutOfFuel => "E0419",
+            Self::DeadlineExceeded => "E0420",
+            Self::Interrupted => "E0421",
+            Self::OutOfMemory(_) => "E0422",
+        }
+    }
+
+    fn help(&self) -> Vec<String> {
+        match self {
+            Self::UndeclaredVariable { suggestions, .. }
+            | Self::UndefinedProperty { suggestions, .. } => match suggestions.as_slice() {
+                [] => Vec::new(),
+                [suggestion] => vec![format!(r#"Did you mean "{suggestion}"?"#)],
+                suggestions => {
+                    let suggestions: Vec<_> = suggestions
+                        .iter()
+                        .map(|suggestion| format!(r#""{suggestion}""#))
+                        .collect();
+
+                    vec![format!("Did you mean one of {}?", suggestions.join(", "))]
+                }
+            },
+            Self::StackOverflow(_) => {
+                vec!["Check that every recursive function reaches its base case".into()]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl RuntimeError {
+    /// Whether the error was raised by the host to stop the program, rather
+    /// than by the program itself. Native functions can't handle these: one
+    /// raised while a native runs is raised again once it returns
+    #[must_use]
+    pub const fn is_abort(&self) -> bool {
+        matches!(
+            self,
+            Self::OutOfFuel | Self::DeadlineExceeded | Self::Interrupted | Self::OutOfMemory(_)
+        )
+    }
+
+    /// Builds an [`RuntimeError::UndeclaredVariable`], suggesting the
+    /// closest of the `candidates`
+    pub fn undeclared_variable<I, S>(identifier: &str, candidates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::UndeclaredVariable {
+            identifier: identifier.into(),
+            suggestions: suggest::suggestions(identifier, candidates),
+        }
+    }
+
+    /// Builds an [`RuntimeError::UndefinedProperty`], suggesting the
+    /// closest of the `candidates`
+    pub fn undefined_property<I, S>(identifier: &str, candidates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::UndefinedProperty {
+            identifier: identifier.into(),
+            suggestions: suggest::suggestions(identifier, candidates),
+        }
+    }
 }
@@ -0,0 +1,360 @@
+use std::{
+    any::{Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use lox_core::{Error, Result, Span, Symbol, SymbolMap};
+
+use crate::{CallContext, Callable, CallableKind, Interpreter, NativeBuilder, RuntimeError, Value};
+
+type Constructor =
+    Rc<dyn Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<Box<dyn Any>, RuntimeError>>;
+
+type Method =
+    Rc<dyn Fn(&mut Interpreter, &CallContext, &UserData, &[Value]) -> Result<Value, RuntimeError>>;
+
+type Getter = Rc<dyn Fn(&dyn Any) -> Value>;
+
+type Setter = Rc<dyn Fn(&mut dyn Any, &CallContext, Value) -> Result<(), RuntimeError>>;
+
+/// Instances of foreign classes always hold the type their class was built for
+fn downcast_ref<T: 'static>(data: &dyn Any) -> &T {
+    data.downcast_ref()
+        .expect("Foreign class instance holds a value of a different type")
+}
+
+fn downcast_mut<T: 'static>(data: &mut dyn Any) -> &mut T {
+    data.downcast_mut()
+        .expect("Foreign class instance holds a value of a different type")
+}
+
+/// Descriptor of a class whose instances are Rust values
+pub struct ForeignClass {
+    pub identifier: Rc<str>,
+    type_id: TypeId,
+    constructor: Option<(usize, Constructor)>,
+    methods: SymbolMap<(usize, Method)>,
+    fields: SymbolMap<(Getter, Option<Setter>)>,
+}
+
+impl ForeignClass {
+    /// Number of arguments expected by the constructor
+    #[must_use]
+    pub fn arity(&self) -> usize {
+        self.constructor.as_ref().map_or(0, |(arity, _)| *arity)
+    }
+
+    /// Wraps a Rust value in an instance of this class, so it can be handed
+    /// over to Lox code
+    ///
+    /// # Panics
+    /// Panics if `data` is not of the type the class was built for
+    #[must_use]
+    pub fn instantiate<T: 'static>(class: &Rc<Self>, data: T) -> Value {
+        assert_eq!(
+            class.type_id,
+            TypeId::of::<T>(),
+            "Foreign class {} was built for a different type",
+            class.identifier
+        );
+
+        Self::wrap(class, Box::new(data))
+    }
+
+    /// The value of the class itself, calling its constructor when called
+    pub(crate) fn value(class: &Rc<Self>) -> Value {
+        Value::Callable(Callable {
+            arity: class.arity(),
+            kind: CallableKind::ForeignClass(Rc::clone(class)),
+        })
+    }
+
+    fn wrap(class: &Rc<Self>, data: Box<dyn Any>) -> Value {
+        Value::UserData(Rc::new(UserData {
+            class: Rc::clone(class),
+            data: RefCell::new(data),
+        }))
+    }
+
+    pub(crate) fn construct(
+        class: &Rc<Self>,
+        interpreter: &mut Interpreter,
+        context: &CallContext,
+        args: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        let Some((_, ref constructor)) = class.constructor else {
+            return Err(context.error(RuntimeError::NotConstructible(Rc::clone(&class.identifier))));
+        };
+
+        let data = constructor(interpreter, context, args)?;
+
+        Ok(Self::wrap(class, data))
+    }
+}
+
+impl std::fmt::Debug for ForeignClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<class {}>", self.identifier)
+    }
+}
+
+/// An instance of a [`ForeignClass`], holding an opaque Rust value
+pub struct UserData {
+    pub class: Rc<ForeignClass>,
+    data: RefCell<Box<dyn Any>>,
+}
+
+impl UserData {
+    /// Mutably borrows the Rust value, returning `None` if it is not of
+    /// type `T` or is already borrowed
+    #[must_use]
+    pub fn borrow_mut<T: 'static>(&self) -> Option<RefMut<'_, T>> {
+        let data = self.data.try_borrow_mut().ok()?;
+        RefMut::filter_map(data, |data| data.downcast_mut::<T>()).ok()
+    }
+
+    /// Borrows the Rust value, returning `None` if it is not of type `T` or
+    /// is mutably borrowed
+    pub(crate) fn borrow<T: 'static>(&self) -> Option<Ref<'_, T>> {
+        let data = self.data.try_borrow().ok()?;
+        Ref::filter_map(data, |data| data.downcast_ref::<T>()).ok()
+    }
+
+    /// # Errors
+    ///
+    /// This function errors if the property doesn't exist
+    pub fn get(
+        user_data: &Rc<Self>,
+        identifier: Symbol,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        let class = &user_data.class;
+        let context = CallContext { span };
+
+        if let Some((getter, _)) = class.fields.get(&identifier) {
+            let data = user_data.borrow_data(&context)?;
+            return Ok(getter(data.as_ref()));
+        }
+
+        if let Some((arity, method)) = class.methods.get(&identifier) {
+            let method = Rc::clone(method);
+            let user_data = Rc::clone(user_data);
+
+            return Ok(NativeBuilder::new(identifier.as_str()).arity(*arity).build(
+                move |interpreter, context, args| method(interpreter, context, &user_data, args),
+            ));
+        }
+
+        let candidates = class.fields.keys().chain(class.methods.keys());
+        Err(context.error(RuntimeError::undefined_property(
+            identifier.as_str(),
+            candidates,
+        )))
+    }
+
+    /// # Errors
+    ///
+    /// This function errors if the property doesn't exist or is read-only
+    pub fn set(&self, identifier: Symbol, value: Value, span: Span) -> Result<(), RuntimeError> {
+        let context = CallContext { span };
+
+        match self.class.fields.get(&identifier) {
+            Some((_, Some(setter))) => {
+                let mut data = self.borrow_data(&context)?;
+                setter(data.as_mut(), &context, value)
+            }
+            Some((_, None)) => {
+                Err(context.error(RuntimeError::ReadOnlyProperty(identifier.into())))
+            }
+            None => Err(context.error(RuntimeError::undefined_property(
+                identifier.as_str(),
+                self.class.fields.keys(),
+            ))),
+        }
+    }
+
+    fn borrow_data(&self, context: &CallContext) -> Result<RefMut<'_, Box<dyn Any>>, RuntimeError> {
+        self.data.try_borrow_mut().map_err(|_| Error {
+            span: context.span,
+            source: RuntimeError::ForeignObjectInUse(Rc::clone(&self.class.identifier)),
+        })
+    }
+}
+
+impl std::fmt::Debug for UserData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{} instance>", self.class.identifier)
+    }
+}
+
+impl std::fmt::Display for UserData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Builder for classes whose instances wrap a Rust value of type `T`
+///
+/// ```ignore
+/// let class = ForeignClassBuilder::<Counter>::new("Counter")
+///     .constructor(0, |_, _, _| Ok(Counter::default()))
+///     .method("increment", 0, |_, _, counter, _| {
+///         counter.count += 1;
+///         Ok(Value::Nil)
+///     })
+///     .field("count", |counter| Value::Number(counter.count))
+///     .build();
+///
+/// let interpreter = InterpreterBuilder::new().foreign_class(&class).build();
+/// ```
+pub struct ForeignClassBuilder<T> {
+    class: ForeignClass,
+    marker: PhantomData<T>,
+}
+
+impl<T: 'static> ForeignClassBuilder<T> {
+    #[must_use]
+    pub fn new(identifier: &str) -> Self {
+        Self {
+            class: ForeignClass {
+                identifier: identifier.into(),
+                type_id: TypeId::of::<T>(),
+                constructor: None,
+                methods: SymbolMap::default(),
+                fields: SymbolMap::default(),
+            },
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the function called when the class is called from Lox code.
+    /// Classes without a constructor can only be instantiated by the host
+    #[must_use]
+    pub fn constructor<F>(mut self, arity: usize, constructor: F) -> Self
+    where
+        F: Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<T, RuntimeError> + 'static,
+    {
+        self.class.constructor = Some((
+            arity,
+            Rc::new(move |interpreter, context, args| {
+                Ok(Box::new(constructor(interpreter, context, args)?))
+            }),
+        ));
+        self
+    }
+
+    /// Adds a method, which receives the wrapped value mutably
+    #[must_use]
+    pub fn method<F>(mut self, identifier: &str, arity: usize, method: F) -> Self
+    where
+        F: Fn(&mut Interpreter, &CallContext, &mut T, &[Value]) -> Result<Value, RuntimeError>
+            + 'static,
+    {
+        self.class.methods.insert(
+            Symbol::intern(identifier),
+            (
+                arity,
+                Rc::new(move |interpreter, context, user_data, args| {
+                    let mut data = user_data.borrow_data(context)?;
+                    let data = downcast_mut::<T>(data.as_mut());
+
+                    method(interpreter, context, data, args)
+                }),
+            ),
+        );
+        self
+    }
+
+    /// Adds a method like [`Self::method`] that runs `check` first, before
+    /// the wrapped value is borrowed, so the heap can still measure it
+    pub(crate) fn checked_method<C, F>(
+        mut self,
+        identifier: &str,
+        arity: usize,
+        check: C,
+        method: F,
+    ) -> Self
+    where
+        C: Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<(), RuntimeError> + 'static,
+        F: Fn(&CallContext, &mut T, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    {
+        self.class.methods.insert(
+            Symbol::intern(identifier),
+            (
+                arity,
+                Rc::new(move |interpreter, context, user_data, args| {
+                    check(interpreter, context, args)?;
+
+                    let mut data = user_data.borrow_data(context)?;
+                    method(context, downcast_mut::<T>(data.as_mut()), args)
+                }),
+            ),
+        );
+        self
+    }
+
+    /// Adds a read-only field
+    #[must_use]
+    pub fn field<G>(mut self, identifier: &str, getter: G) -> Self
+    where
+        G: Fn(&T) -> Value + 'static,
+    {
+        self.class
+            .fields
+            .insert(Symbol::intern(identifier), (Self::getter(getter), None));
+        self
+    }
+
+    /// Adds a field that can be assigned from Lox code
+    #[must_use]
+    pub fn field_mut<G, S>(mut self, identifier: &str, getter: G, setter: S) -> Self
+    where
+        G: Fn(&T) -> Value + 'static,
+        S: Fn(&mut T, &CallContext, Value) -> Result<(), RuntimeError> + 'static,
+    {
+        let setter: Setter = Rc::new(move |data, context, value| {
+            let data = downcast_mut::<T>(data);
+
+            setter(data, context, value)
+        });
+
+        self.class.fields.insert(
+            Symbol::intern(identifier),
+            (Self::getter(getter), Some(setter)),
+        );
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> Rc<ForeignClass> {
+        Rc::new(self.class)
+    }
+
+    fn getter<G>(getter: G) -> Getter
+    where
+        G: Fn(&T) -> Value + 'static,
+    {
+        Rc::new(move |data| {
+            let data = downcast_ref::<T>(data);
+
+            getter(data)
+        })
+    }
+}
+
+impl Interpreter {
+    /// Defines a foreign class as a global, under its identifier, unless the
+    /// sandbox denies host natives
+    pub fn define_foreign_class(&mut self, class: &Rc<ForeignClass>) {
+        if !self.sandbox.allows_host_natives() {
+            return;
+        }
+
+        self.globals.borrow_mut().define(
+            Symbol::intern(&class.identifier),
+            Some(ForeignClass::value(class)),
+        );
+    }
+}
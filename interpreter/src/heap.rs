@@ -0,0 +1,553 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+use lox_core::{BuildIdHasher, Symbol, SymbolMap};
+use parser::NodeId;
+
+use crate::{
+    Callable, CallableKind, Environment, Interpreter, LoxClass, LoxInstance, RuntimeError, Value,
+};
+
+/// Number of tracked allocations after which the first collection runs
+const INITIAL_THRESHOLD: usize = 1_000;
+
+/// Approximate sizes, in bytes, of what programs allocate
+const ENVIRONMENT_SIZE: usize = size_of::<RefCell<Environment>>();
+const INSTANCE_SIZE: usize = size_of::<RefCell<LoxInstance>>();
+const VALUE_SIZE: usize = size_of::<Value>();
+const FIELD_SIZE: usize = size_of::<(Symbol, Value)>();
+
+/// Fraction of the memory limit allocated between two measurements, once
+/// the live values use most of it
+const LIMIT_MARGIN: usize = 4;
+
+/// Statistics about the objects tracked by the cycle collector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeapStats {
+    /// Environments currently alive
+    pub environments: usize,
+
+    /// Instances currently alive
+    pub instances: usize,
+
+    /// Collections run so far
+    pub collections: usize,
+
+    /// Objects freed by the collector so far, each of them part of a cycle
+    /// that was no longer reachable
+    pub collected: usize,
+
+    /// Approximate number of bytes used by the values of the program
+    pub bytes: usize,
+}
+
+/// Keeps track of the environments and instances created by the interpreter,
+/// so the reference cycles between them can be collected.
+///
+/// Reference counting frees everything else. A collection subtracts the
+/// references tracked objects hold on each other from their reference counts:
+/// the objects left with references are held from elsewhere, such as the
+/// interpreter, a native or the host. Every object reachable from those is
+/// kept, and the others are cleared, which breaks their cycles
+#[derive(Debug)]
+pub struct Heap {
+    objects: Vec<Tracked>,
+
+    /// Tracked allocations since the last collection
+    allocations: usize,
+
+    /// Allocations that trigger the next collection
+    threshold: usize,
+    collections: usize,
+    collected: usize,
+
+    /// Bytes programs can use, `None` if unlimited
+    pub limit: Option<usize>,
+
+    /// Bytes in use when the usage was last measured, plus the bytes
+    /// allocated since
+    bytes: usize,
+
+    /// Bytes in use when the usage was last measured
+    live: usize,
+
+    /// The method last found by each property access, along with the class
+    /// it was found for. Collections clear it, since it holds the classes
+    /// and methods strongly
+    methods: HashMap<NodeId, (Rc<LoxClass>, Callable), BuildIdHasher>,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+            allocations: 0,
+            threshold: INITIAL_THRESHOLD,
+            collections: 0,
+            collected: 0,
+            limit: None,
+            bytes: 0,
+            live: 0,
+            methods: HashMap::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Tracked {
+    Environment(Weak<RefCell<Environment>>),
+    Instance(Weak<RefCell<LoxInstance>>),
+    Class(Weak<LoxClass>),
+}
+
+impl Tracked {
+    fn is_alive(&self) -> bool {
+        match self {
+            Self::Environment(environment) => environment.strong_count() > 0,
+            Self::Instance(instance) => instance.strong_count() > 0,
+            Self::Class(class) => class.strong_count() > 0,
+        }
+    }
+
+    fn upgrade(&self) -> Option<Object> {
+        Some(match self {
+            Self::Environment(environment) => Object::Environment(environment.upgrade()?),
+            Self::Instance(instance) => Object::Instance(instance.upgrade()?),
+            Self::Class(class) => Object::Class(class.upgrade()?),
+        })
+    }
+}
+
+/// An object the collector looks through
+#[derive(Clone)]
+enum Object {
+    Environment(Rc<RefCell<Environment>>),
+    Instance(Rc<RefCell<LoxInstance>>),
+    Class(Rc<LoxClass>),
+}
+
+impl Object {
+    fn address(&self) -> *const () {
+        match self {
+            Self::Environment(environment) => Rc::as_ptr(environment).cast(),
+            Self::Instance(instance) => Rc::as_ptr(instance).cast(),
+            Self::Class(class) => Rc::as_ptr(class).cast(),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Self::Environment(environment) => Rc::strong_count(environment),
+            Self::Instance(instance) => Rc::strong_count(instance),
+            Self::Class(class) => Rc::strong_count(class),
+        }
+    }
+
+    /// The objects this one holds a reference to, `None` if it is mutably
+    /// borrowed
+    fn children(&self) -> Option<Vec<Self>> {
+        let mut children = Vec::new();
+
+        match self {
+            Self::Environment(environment) => {
+                let environment = environment.try_borrow().ok()?;
+
+                if let Some(parent) = environment.parent() {
+                    children.push(Self::Environment(Rc::clone(parent)));
+                }
+
+                for value in environment.values() {
+                    value_children(value, &mut children);
+                }
+            }
+            Self::Instance(instance) => {
+                let instance = instance.try_borrow().ok()?;
+
+                children.push(Self::Class(Rc::clone(&instance.class)));
+                for value in instance.fields.values() {
+                    value_children(value, &mut children);
+                }
+            }
+            Self::Class(class) => class_children(class, &mut children),
+        }
+
+        Some(children)
+    }
+
+    /// Drops the references held by a garbage object
+    fn clear(&self) {
+        match self {
+            Self::Environment(environment) => {
+                if let Ok(mut environment) = environment.try_borrow_mut() {
+                    // Dropped once the borrow is released
+                    let _contents = std::mem::take(&mut *environment);
+                }
+            }
+            Self::Instance(instance) => {
+                if let Ok(mut instance) = instance.try_borrow_mut() {
+                    let _fields = std::mem::take(&mut instance.fields);
+                }
+            }
+            // Classes can't be modified, their cycles go through an
+            // environment, which is cleared
+            Self::Class(_) => (),
+        }
+    }
+}
+
+fn value_children(value: &Value, children: &mut Vec<Object>) {
+    match value {
+        Value::Instance(instance) => children.push(Object::Instance(Rc::clone(instance))),
+        Value::Callable(Callable { kind, .. }) => callable_children(kind, children),
+        _ => (),
+    }
+}
+
+fn callable_children(kind: &CallableKind, children: &mut Vec<Object>) {
+    match kind {
+        CallableKind::LoxFunction { closure, this, .. } => {
+            children.push(Object::Environment(Rc::clone(closure)));
+
+            if let Some(this) = this {
+                children.push(Object::Instance(Rc::clone(this)));
+            }
+        }
+        CallableKind::LoxClass(class) => children.push(Object::Class(Rc::clone(class))),
+        CallableKind::NativeFunction { .. } | CallableKind::ForeignClass(_) => (),
+    }
+}
+
+fn class_children(class: &LoxClass, children: &mut Vec<Object>) {
+    for method in class.methods.values() {
+        callable_children(&method.kind, children);
+    }
+
+    if let Some(ref super_class) = class.super_class {
+        children.push(Object::Class(Rc::clone(super_class)));
+    }
+}
+
+impl Heap {
+    /// Creates a child environment of `parent`
+    pub fn environment(&mut self, parent: &Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        let environment = Environment::spawn_child(parent);
+
+        self.objects
+            .push(Tracked::Environment(Rc::downgrade(&environment)));
+        self.allocations += 1;
+        self.charge(ENVIRONMENT_SIZE);
+
+        environment
+    }
+
+    /// Creates an instance of `class` without any field
+    pub fn instance(&mut self, class: Rc<LoxClass>) -> Rc<RefCell<LoxInstance>> {
+        let instance = Rc::new(RefCell::new(LoxInstance {
+            class,
+            fields: SymbolMap::default(),
+        }));
+
+        self.objects
+            .push(Tracked::Instance(Rc::downgrade(&instance)));
+        self.allocations += 1;
+        self.charge(INSTANCE_SIZE);
+
+        instance
+    }
+
+    /// The method cached for the property access `id`, if it was found for
+    /// `class`
+    pub fn cached_method(&self, id: NodeId, class: &Rc<LoxClass>) -> Option<&Callable> {
+        self.methods
+            .get(&id)
+            .filter(|(cached, _)| Rc::ptr_eq(cached, class))
+            .map(|(_, method)| method)
+    }
+
+    /// Caches the method found for the property access `id` on `class`
+    pub fn cache_method(&mut self, id: NodeId, class: &Rc<LoxClass>, method: Callable) {
+        self.methods.insert(id, (Rc::clone(class), method));
+    }
+
+    /// Tracks a new class
+    pub fn class(&mut self, class: LoxClass) -> Rc<LoxClass> {
+        let class = Rc::new(class);
+
+        self.objects.push(Tracked::Class(Rc::downgrade(&class)));
+        self.allocations += 1;
+
+        class
+    }
+
+    /// Counts `bytes` towards the memory usage
+    pub const fn charge(&mut self, bytes: usize) {
+        self.bytes = self.bytes.saturating_add(bytes);
+    }
+
+    /// Counts the bytes of a new field towards the memory usage
+    pub const fn charge_field(&mut self) {
+        self.charge(FIELD_SIZE);
+    }
+
+    /// Counts the bytes of an element stored in a list towards the memory
+    /// usage, including the string it refers to
+    pub fn charge_element(&mut self, value: &Value) {
+        let string = match value {
+            Value::String(string) => string.len(),
+            _ => 0,
+        };
+
+        self.charge(FIELD_SIZE + string);
+    }
+
+    /// Fails if the memory usage exceeds the limit. Since freed values are
+    /// not subtracted from it, the usage is measured again once it reaches
+    /// the limit, after collecting garbage. When the live values are close to
+    /// the limit, at least a quarter of it is allocated before the next
+    /// measurement, so programs near the limit don't collect on every
+    /// allocation
+    ///
+    /// # Errors
+    /// This function will error if the values reachable from `globals` or a
+    /// tracked object use more memory than the limit
+    pub fn check_limit(&mut self, globals: &Rc<RefCell<Environment>>) -> Result<(), RuntimeError> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+
+        if self.bytes <= limit.max(self.live.saturating_add(limit / LIMIT_MARGIN)) {
+            return Ok(());
+        }
+
+        self.collect();
+        self.bytes = self.measure(globals);
+        self.live = self.bytes;
+
+        if self.bytes > limit {
+            return Err(RuntimeError::OutOfMemory(limit));
+        }
+
+        Ok(())
+    }
+
+    /// Adds up the sizes of the values reachable from `globals` or a tracked
+    /// object. Strings, lists and maps referenced several times are counted
+    /// once
+    fn measure(&self, globals: &Rc<RefCell<Environment>>) -> usize {
+        let mut measure = Measure::default();
+
+        if let Ok(globals) = globals.try_borrow() {
+            measure.environment(&globals);
+        }
+
+        for object in &self.objects {
+            match object.upgrade() {
+                Some(Object::Environment(environment)) => {
+                    if let Ok(environment) = environment.try_borrow() {
+                        measure.environment(&environment);
+                    }
+                }
+                Some(Object::Instance(instance)) => {
+                    if let Ok(instance) = instance.try_borrow() {
+                        measure.bytes += INSTANCE_SIZE;
+
+                        for value in instance.fields.values() {
+                            measure.bytes += FIELD_SIZE - VALUE_SIZE;
+                            measure.value(value);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        measure.bytes
+    }
+
+    /// Runs a collection if enough objects were allocated since the last one
+    pub fn maybe_collect(&mut self) {
+        if self.allocations >= self.threshold {
+            self.collect();
+        }
+    }
+
+    /// Frees the tracked objects that are only reachable from each other,
+    /// returning how many were freed. Nothing is freed while one of them is
+    /// mutably borrowed
+    pub fn collect(&mut self) -> usize {
+        self.allocations = 0;
+        self.methods.clear();
+        self.objects.retain(Tracked::is_alive);
+
+        let objects: Vec<_> = self.objects.iter().filter_map(Tracked::upgrade).collect();
+        let index: HashMap<_, _> = objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| (object.address(), i))
+            .collect();
+
+        // The reference held by `objects` is left out
+        let mut external: Vec<_> = objects
+            .iter()
+            .map(|object| object.strong_count() - 1)
+            .collect();
+
+        let mut children = Vec::with_capacity(objects.len());
+        for object in &objects {
+            let Some(object_children) = object.children() else {
+                return 0;
+            };
+
+            for child in &object_children {
+                if let Some(&i) = index.get(&child.address()) {
+                    external[i] -= 1;
+                }
+            }
+
+            children.push(object_children);
+        }
+
+        let mut reachable = vec![false; objects.len()];
+        let mut visited = HashSet::new();
+        let mut stack: Vec<_> = objects
+            .iter()
+            .zip(&external)
+            .filter(|(_, &count)| count > 0)
+            .map(|(object, _)| object.clone())
+            .collect();
+
+        while let Some(object) = stack.pop() {
+            if let Some(&i) = index.get(&object.address()) {
+                if !reachable[i] {
+                    reachable[i] = true;
+                    stack.append(&mut children[i]);
+                }
+            } else if visited.insert(object.address()) {
+                let Some(mut object_children) = object.children() else {
+                    return 0;
+                };
+
+                stack.append(&mut object_children);
+            }
+        }
+
+        drop(children);
+
+        let garbage: Vec<_> = objects
+            .into_iter()
+            .zip(reachable)
+            .filter_map(|(object, reachable)| (!reachable).then_some(object))
+            .collect();
+
+        for object in &garbage {
+            object.clear();
+        }
+
+        let collected = garbage.len();
+        drop(garbage);
+
+        self.objects.retain(Tracked::is_alive);
+        self.threshold = INITIAL_THRESHOLD.max(2 * self.objects.len());
+        self.collections += 1;
+        self.collected += collected;
+
+        collected
+    }
+
+    fn stats(&self, globals: &Rc<RefCell<Environment>>) -> HeapStats {
+        let (environments, instances) =
+            self.objects
+                .iter()
+                .fold((0, 0), |(environments, instances), object| match object {
+                    Tracked::Environment(_) if object.is_alive() => (environments + 1, instances),
+                    Tracked::Instance(_) if object.is_alive() => (environments, instances + 1),
+                    _ => (environments, instances),
+                });
+
+        HeapStats {
+            environments,
+            instances,
+            collections: self.collections,
+            collected: self.collected,
+            bytes: self.measure(globals),
+        }
+    }
+}
+
+/// The bytes counted so far, along with the strings, lists and maps they
+/// include
+#[derive(Default)]
+struct Measure {
+    bytes: usize,
+    seen: HashSet<*const ()>,
+}
+
+impl Measure {
+    fn environment(&mut self, environment: &Environment) {
+        self.bytes += ENVIRONMENT_SIZE;
+
+        for value in environment.values() {
+            self.value(value);
+        }
+    }
+
+    /// Counts a value, along with the string, list or map it refers to.
+    /// Instances and closures are tracked, so they are counted on their own
+    fn value(&mut self, value: &Value) {
+        self.bytes += VALUE_SIZE;
+
+        match value {
+            Value::String(string) if self.seen.insert(Rc::as_ptr(string).cast()) => {
+                self.bytes += string.len();
+            }
+            Value::UserData(user_data) if self.seen.insert(Rc::as_ptr(user_data).cast()) => {
+                // Lists and maps are only borrowed by the host or while they
+                // are modified, after their new elements were charged
+                if let Some(list) = user_data.borrow::<Vec<Value>>() {
+                    for element in list.iter() {
+                        self.bytes += FIELD_SIZE - VALUE_SIZE;
+                        self.value(element);
+                    }
+                } else if let Some(map) = user_data.borrow::<HashMap<Rc<str>, Value>>() {
+                    for (key, value) in map.iter() {
+                        self.bytes += FIELD_SIZE - VALUE_SIZE + key.len();
+                        self.value(value);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Interpreter {
+    /// Statistics about the environments and instances created by programs
+    #[must_use]
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.stats(&self.globals)
+    }
+
+    /// Frees the environments and instances that are only reachable from
+    /// each other, returning how many were freed. Collections also run on
+    /// their own as programs allocate
+    pub fn collect_garbage(&mut self) -> usize {
+        self.heap.collect()
+    }
+
+    /// Bytes programs can use before [`RuntimeError::OutOfMemory`] is raised,
+    /// `None` if unlimited
+    #[must_use]
+    pub const fn memory_limit(&self) -> Option<usize> {
+        self.heap.limit
+    }
+
+    /// Sets how many bytes programs can use before they are stopped, `None`
+    /// to run without limit. The usage is an estimate, based on the values
+    /// the program holds
+    pub const fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.heap.limit = limit;
+    }
+}
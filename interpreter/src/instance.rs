@@ -1,12 +1,12 @@
-use lox_core::{Error, Result};
+use lox_core::{Error, Result, Span, Symbol, SymbolMap};
+use std::cell::RefCell;
 use std::rc::Rc;
-use std::{cell::RefCell, collections::HashMap};
 
-use crate::{Callable, CallableKind, Environment, LoxClass, RuntimeError, Value};
+use crate::{LoxClass, RuntimeError, Value};
 
 pub struct LoxInstance {
-    pub class: LoxClass,
-    pub fields: HashMap<Rc<str>, Value>,
+    pub class: Rc<LoxClass>,
+    pub fields: SymbolMap<Value>,
 }
 
 impl LoxInstance {
@@ -15,52 +15,36 @@ impl LoxInstance {
     /// This function errors if the property doesn't exist
     pub fn get(
         instance: &Rc<RefCell<Self>>,
-        identifier: &Rc<str>,
-        line: usize,
-        column: usize,
+        identifier: Symbol,
+        span: Span,
     ) -> Result<Value, RuntimeError> {
-        if let Some(value) = instance.borrow().fields.get(identifier) {
+        if let Some(value) = instance.borrow().fields.get(&identifier) {
             return Ok(value.clone());
         }
 
         if let Some(method) = instance.borrow().class.find_method(identifier) {
-            let bound_method = match method.kind {
-                CallableKind::LoxFunction {
-                    ref parameters,
-                    ref body,
-                    ref closure,
-                    ref identifier,
-                    is_initializer,
-                } => CallableKind::LoxFunction {
-                    identifier: identifier.clone(),
-                    parameters: Rc::clone(parameters),
-                    body: Rc::clone(body),
-                    closure: {
-                        let env = Environment::spawn_child(closure);
-                        env.borrow_mut()
-                            .define(&"this".into(), Some(Value::Instance(Rc::clone(instance))));
-                        env
-                    },
-                    is_initializer,
-                },
-                _ => unreachable!(),
-            };
-
-            return Ok(Value::Callable(Callable {
-                arity: method.arity,
-                kind: bound_method,
-            }));
+            return Ok(Value::Callable(method.bind(instance)));
         }
 
-        Err(Error {
-            line,
-            column,
-            source: RuntimeError::UndefinedProperty(Rc::clone(identifier)),
-        })
+        Err(instance.borrow().undefined_property(identifier, span))
+    }
+
+    /// The error raised when accessing a property that is neither a field
+    /// nor a method of the instance
+    pub(crate) fn undefined_property(&self, identifier: Symbol, span: Span) -> Error<RuntimeError> {
+        let fields = self.fields.keys();
+
+        Error {
+            span,
+            source: RuntimeError::undefined_property(
+                identifier.as_str(),
+                fields.chain(self.class.method_names()),
+            ),
+        }
     }
 
-    pub fn set(&mut self, identifier: &Rc<str>, value: Value) {
-        self.fields.insert(Rc::clone(identifier), value);
+    pub fn set(&mut self, identifier: Symbol, value: Value) {
+        self.fields.insert(identifier, value);
     }
 }
 
@@ -1,92 +1,126 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    io::{BufRead, Write},
     rc::Rc,
-    time::{SystemTime, UNIX_EPOCH},
 };
 
-use lox_core::{report, Error, Result};
+use lox_core::{report, Diagnostic, Diagnostics, Error, Frame, Result, Span, Symbol, SymbolMap};
 use parser::{
     BinaryOperator, BinaryOperatorKind, Expression, Function, LogicalOperator, LogicalOperatorKind,
-    Reference, Statement, UnaryOperatorKind,
+    NodeId, Reference, Statement, UnaryOperatorKind,
 };
+use resolver::{Locals, Slot};
 
-use crate::{Callable, CallableKind, Environment, LoxClass, LoxInstance, RuntimeError, Value};
+use crate::{
+    completion::Completion, heap::Heap, limits::Limits, suggest, CallContext, CallFrame, Callable,
+    CallableKind, Environment, ForeignClass, InterpreterBuilder, LoxClass, LoxInstance,
+    RuntimeError, Sandbox, Stdlib, Streams, UserData, Value, NUMBER_METHODS,
+};
+
+/// Stack space left at which the stack is grown before calling a function
+const STACK_RED_ZONE: usize = 128 * 1024;
+
+/// Size of every new segment of the stack
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Interpreter {
     pub environment: Rc<RefCell<Environment>>,
     pub globals: Rc<RefCell<Environment>>,
-    pub locals: HashMap<Reference, usize>,
+    /// The local variables of the program being run. Functions keep the ones
+    /// of the program they are declared in
+    pub locals: Rc<Locals>,
+    pub(crate) streams: Streams,
+
+    /// The calls in progress, outermost first
+    pub(crate) call_stack: Vec<CallFrame>,
+
+    /// The call stack at the point the error being propagated was raised
+    pub(crate) trace: Option<Vec<CallFrame>>,
+
+    /// The error stopping the program, if one was raised while the innermost
+    /// native function runs
+    pub(crate) abort: Option<Error<RuntimeError>>,
+
+    /// Calls nested deeper than this raise a stack overflow error
+    pub(crate) max_call_depth: usize,
+    pub(crate) limits: Limits,
+
+    /// The capabilities the interpreter was built with
+    pub(crate) sandbox: Sandbox,
+    pub(crate) heap: Heap,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     #[must_use]
     pub fn new() -> Self {
-        let mut environment = Environment::new();
-
-        environment.define(
-            &"clock".into(),
-            Some(Value::Callable(Callable {
-                arity: 0,
-                kind: CallableKind::NativeFunction(Rc::new(|_| {
-                    let now = SystemTime::now();
-                    let elapsed = now.duration_since(UNIX_EPOCH).unwrap_or_default();
-
-                    Value::Number(1_000.0 * elapsed.as_secs_f64())
-                })),
-            })),
-        );
-
-        environment.define(
-            &"print".into(),
-            Some(Value::Callable(Callable {
-                arity: 1,
-                kind: CallableKind::NativeFunction(Rc::new(|args| {
-                    println!("{}", args[0]);
-                    Value::Nil
-                })),
-            })),
-        );
-
-        environment.define(
-            &"readLine".into(),
-            Some(Value::Callable(Callable {
-                arity: 0,
-                kind: CallableKind::NativeFunction(Rc::new(|_| {
-                    let stdin = std::io::stdin();
-                    let mut buffer = String::new();
-                    _ = stdin.read_line(&mut buffer);
-
-                    Value::String(buffer.trim_end_matches(['\r', '\n']).into())
-                })),
-            })),
-        );
-
-        let environment = Rc::new(RefCell::new(environment));
-
-        Self {
-            globals: Rc::clone(&environment),
-            environment,
-            locals: HashMap::new(),
-        }
+        InterpreterBuilder::new().build()
+    }
+
+    /// The stream `print` writes to
+    pub fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.streams.stdout
+    }
+
+    /// The stream `readLine` reads from
+    pub fn stdin(&mut self) -> &mut dyn BufRead {
+        &mut self.streams.stdin
+    }
+
+    /// The stream errors are reported to
+    pub fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.streams.stderr
     }
 
-    pub fn resolve_locals(&mut self, locals: HashMap<Reference, usize>) {
-        self.locals.extend(locals);
+    /// Sets the local variables of the next program to run, replacing the
+    /// ones of the previous program
+    pub fn resolve_locals(&mut self, locals: Locals) {
+        self.locals = Rc::new(locals);
     }
 
-    pub fn interpret(&mut self, source: &str, program: &[Statement]) {
+    /// Executes the program, stopping at the first runtime error
+    pub fn interpret(&mut self, program: &[Statement]) -> Diagnostics {
         for statement in program {
             if let Err(error) = self.execute(statement) {
-                report(source, &error);
-                break;
+                return self.runtime_diagnostic(error).into();
             }
         }
+
+        Diagnostics::new()
+    }
+
+    /// The calls in progress, outermost first
+    #[must_use]
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    /// Turns an error that reached the top level into a diagnostic, along
+    /// with the stack trace recorded while it was propagated
+    pub(crate) fn runtime_diagnostic(&mut self, error: Error<RuntimeError>) -> Diagnostic {
+        let trace = self.trace.take().unwrap_or_default();
+
+        Diagnostic::from(error).with_trace(trace.iter().rev().map(Frame::from).collect())
+    }
+
+    /// Renders the diagnostics to the interpreter's stderr
+    pub fn report(&mut self, source: &str, diagnostics: &Diagnostics) {
+        for diagnostic in diagnostics {
+            // Failing to write to stderr leaves nowhere else to report to
+            _ = report(&mut self.streams.stderr, source, diagnostic);
+        }
     }
 
     #[allow(clippy::too_many_lines)]
-    fn execute(&mut self, statement: &Statement) -> Result<(), RuntimeError> {
+    pub(crate) fn execute(&mut self, statement: &Statement) -> Result<Completion, RuntimeError> {
+        self.heap.maybe_collect();
+
         match statement {
             Statement::Expression(expression) => {
                 self.evaluate(expression)?;
@@ -97,18 +131,18 @@ impl Interpreter {
                 ..
             } => {
                 let value = initializer.as_ref().map(|x| self.evaluate(x)).transpose()?;
-                self.environment.borrow_mut().define(identifier, value);
+                self.environment.borrow_mut().define(*identifier, value);
             }
-            Statement::Block(statements) => self.execute_block(statements)?,
+            Statement::Block(statements) => return self.execute_block(statements),
             Statement::If {
                 condition,
                 then_branch,
                 else_branch,
             } => {
                 if self.evaluate(condition)?.is_truthy() {
-                    self.execute(then_branch)?;
+                    return self.execute(then_branch);
                 } else if let Some(else_branch) = else_branch {
-                    self.execute(else_branch)?;
+                    return self.execute(else_branch);
                 }
             }
             Statement::For {
@@ -117,27 +151,14 @@ impl Interpreter {
                 body: statement,
             } => {
                 while self.evaluate(condition)?.is_truthy() {
-                    match self.execute(statement) {
-                        Ok(()) => {
-                            if let Some(ref increment) = increment {
-                                self.evaluate(increment)?;
-                            }
-                        }
-                        Err(Error {
-                            source: RuntimeError::Break,
-                            ..
-                        }) => break,
-                        Err(Error {
-                            source: RuntimeError::Continue,
-                            ..
-                        }) => {
+                    match self.execute(statement)? {
+                        Completion::Normal | Completion::Continue => {
                             if let Some(ref increment) = increment {
                                 self.evaluate(increment)?;
                             }
-
-                            continue;
                         }
-                        Err(e) => return Err(e),
+                        Completion::Break => break,
+                        completion @ Completion::Return(_) => return Ok(completion),
                     }
                 }
             }
@@ -146,34 +167,15 @@ impl Interpreter {
                 body: statement,
             } => {
                 while self.evaluate(condition)?.is_truthy() {
-                    match self.execute(statement) {
-                        Ok(()) => (),
-                        Err(Error {
-                            source: RuntimeError::Break,
-                            ..
-                        }) => break,
-                        Err(Error {
-                            source: RuntimeError::Continue,
-                            ..
-                        }) => continue,
-                        Err(e) => return Err(e),
+                    match self.execute(statement)? {
+                        Completion::Normal | Completion::Continue => (),
+                        Completion::Break => break,
+                        completion @ Completion::Return(_) => return Ok(completion),
                     }
                 }
             }
-            Statement::Break { line, column } => {
-                return Err(Error {
-                    line: *line,
-                    column: *column,
-                    source: RuntimeError::Break,
-                })
-            }
-            Statement::Continue { line, column } => {
-                return Err(Error {
-                    line: *line,
-                    column: *column,
-                    source: RuntimeError::Continue,
-                })
-            }
+            Statement::Break { .. } => return Ok(Completion::Break),
+            Statement::Continue { .. } => return Ok(Completion::Continue),
             Statement::Function(Function {
                 identifier,
                 parameters,
@@ -181,33 +183,27 @@ impl Interpreter {
                 ..
             }) => {
                 self.environment.borrow_mut().define(
-                    identifier,
+                    *identifier,
                     Some(Value::Callable(Callable {
                         arity: parameters.len(),
                         kind: CallableKind::LoxFunction {
-                            identifier: Some(Rc::clone(identifier)),
+                            identifier: Some((*identifier).into()),
                             parameters: Rc::clone(parameters),
                             body: Rc::clone(body),
                             closure: Rc::clone(&self.environment),
+                            locals: Rc::clone(&self.locals),
                             is_initializer: false,
+                            this: None,
                         },
                     })),
                 );
             }
-            Statement::Return {
-                line,
-                column,
-                expression,
-            } => {
-                return Err(Error {
-                    line: *line,
-                    column: *column,
-                    source: RuntimeError::Return(
-                        expression
-                            .as_ref()
-                            .map_or(Ok(Value::Nil), |x| self.evaluate(x))?,
-                    ),
-                })
+            Statement::Return { expression, .. } => {
+                let value = expression
+                    .as_ref()
+                    .map_or(Ok(Value::Nil), |x| self.evaluate(x))?;
+
+                return Ok(Completion::Return(value));
             }
             Statement::Class {
                 identifier,
@@ -215,15 +211,14 @@ impl Interpreter {
                 super_class: super_reference,
                 ..
             } => {
-                let mut methods_map = HashMap::new();
+                let mut methods_map = SymbolMap::default();
 
-                let super_class: Option<Rc<_>> = super_reference
+                let super_class = super_reference
                     .as_ref()
                     .map(|x| self.evaluate(x))
                     .transpose()?
                     .map(|x| {
-                        let Some(Expression::Variable(Reference { line, column, .. })) =
-                            super_reference
+                        let Some(Expression::Variable(Reference { span, .. })) = super_reference
                         else {
                             unreachable!()
                         };
@@ -232,53 +227,52 @@ impl Interpreter {
                             Value::Callable(Callable {
                                 kind: CallableKind::LoxClass(super_class),
                                 ..
-                            }) => Ok(super_class.into()),
+                            }) => Ok(super_class),
                             _ => Err(Error {
-                                line: *line,
-                                column: *column,
+                                span: *span,
                                 source: RuntimeError::SuperClassMustBeAClass,
                             }),
                         }
                     })
                     .transpose()?;
 
-                self.environment.borrow_mut().define(identifier, None);
-
                 let current = Rc::clone(&self.environment);
                 if let Some(ref super_class) = super_class {
-                    self.environment = Environment::spawn_child(&self.environment);
+                    self.environment = self.heap.environment(&self.environment);
                     self.environment.borrow_mut().define(
-                        &"super".into(),
+                        Symbol::SUPER,
                         Some(Value::Callable(Callable {
                             arity: 0,
-                            kind: CallableKind::LoxClass(super_class.as_ref().clone()),
+                            kind: CallableKind::LoxClass(Rc::clone(super_class)),
                         })),
                     );
                 }
 
                 for method in methods.iter() {
                     methods_map.insert(
-                        Rc::clone(&method.identifier),
+                        method.identifier,
                         Callable {
                             arity: method.parameters.len(),
                             kind: CallableKind::LoxFunction {
-                                identifier: Some(Rc::clone(&method.identifier)),
+                                identifier: Some(method.identifier.into()),
                                 parameters: Rc::clone(&method.parameters),
                                 body: Rc::clone(&method.body),
                                 closure: Rc::clone(&self.environment),
-                                is_initializer: method.identifier.as_ref() == "init",
+                                locals: Rc::clone(&self.locals),
+                                is_initializer: method.identifier == Symbol::INIT,
+                                this: None,
                             },
                         },
                     );
                 }
 
                 let class = Value::Callable(Callable {
-                    arity: methods_map.get("init").map_or(0, |x| x.arity),
-                    kind: CallableKind::LoxClass(LoxClass {
-                        identifier: Rc::clone(identifier),
+                    arity: methods_map.get(&Symbol::INIT).map_or(0, |x| x.arity),
+                    kind: CallableKind::LoxClass(self.heap.class(LoxClass {
+                        identifier: (*identifier).into(),
                         super_class,
                         methods: methods_map,
-                    }),
+                    })),
                 });
 
                 if super_reference.is_some() {
@@ -287,30 +281,41 @@ impl Interpreter {
 
                 self.environment
                     .borrow_mut()
-                    .define(identifier, Some(class));
+                    .define(*identifier, Some(class));
             }
-        };
+        }
 
-        Ok(())
+        Ok(Completion::Normal)
     }
 
-    fn execute_block(&mut self, statements: &[Statement]) -> Result<(), RuntimeError> {
+    fn execute_block(&mut self, statements: &[Statement]) -> Result<Completion, RuntimeError> {
         let current = Rc::clone(&self.environment);
 
-        self.environment = Environment::spawn_child(&current);
+        self.environment = self.heap.environment(&current);
         for statement in statements {
-            if let Err(error) = self.execute(statement) {
-                self.environment = current;
-                return Err(error);
+            match self.execute(statement) {
+                Ok(Completion::Normal) => (),
+                result => {
+                    self.environment = current;
+                    return result;
+                }
             }
         }
         self.environment = current;
 
-        Ok(())
+        Ok(Completion::Normal)
     }
 
     #[allow(clippy::too_many_lines)]
-    fn evaluate(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+    pub(crate) fn evaluate(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+        self.limits
+            .tick()
+            .and_then(|()| self.heap.check_limit(&self.globals))
+            .map_err(|source| Error {
+                span: expression.span(),
+                source,
+            })?;
+
         Ok(match expression {
             Expression::Ternary {
                 condition,
@@ -338,8 +343,7 @@ impl Interpreter {
                         Value::Number(number) => Value::Number(-number),
                         x => {
                             return Err(Error {
-                                line: operator.line,
-                                column: operator.column,
+                                span: operator.span,
                                 source: RuntimeError::TypeError {
                                     expected: "number",
                                     found: x.type_name(),
@@ -350,54 +354,62 @@ impl Interpreter {
                     UnaryOperatorKind::Bang => Value::Boolean(!value.is_truthy()),
                 }
             }
-            Expression::GroupingExpression(expression) => self.evaluate(expression)?,
-            Expression::Literal(literal) => literal.clone().into(),
+            Expression::GroupingExpression { expression, .. } => self.evaluate(expression)?,
+            Expression::Literal { value, .. } => value.clone().into(),
             Expression::Variable(reference) => self.lookup_variable(reference)?,
             Expression::Assignment { reference, value } => {
                 let value = self.evaluate(value)?;
 
-                if let Some(&distance) = self.locals.get(reference) {
-                    self.environment
-                        .borrow_mut()
-                        .assign_at(distance, reference, value.clone())?;
+                if let Some(&slot) = self.locals.get(&reference.id) {
+                    self.environment.borrow_mut().assign_at(slot, value.clone());
                 } else {
-                    self.globals.borrow_mut().assign(reference, value.clone())?;
+                    self.globals
+                        .borrow_mut()
+                        .assign(reference, value.clone())
+                        .map_err(|error| self.suggest_visible(error))?;
                 }
 
                 value
             }
-            Expression::Call {
-                callee,
-                args,
-                line,
-                column,
-            } => self.evaluate_call(callee, args, *line, *column)?,
-            Expression::AnonymousFunction { parameters, body } => Value::Callable(Callable {
+            Expression::Call { callee, args, span } => self.evaluate_call(callee, args, *span)?,
+            Expression::AnonymousFunction {
+                parameters, body, ..
+            } => Value::Callable(Callable {
                 arity: parameters.len(),
                 kind: CallableKind::LoxFunction {
                     identifier: None,
                     parameters: Rc::clone(parameters),
                     body: Rc::clone(body),
                     closure: Rc::clone(&self.environment),
+                    locals: Rc::clone(&self.locals),
                     is_initializer: false,
+                    this: None,
                 },
             }),
             Expression::Get {
-                line,
-                column,
+                span,
                 object,
                 identifier,
+                id,
             } => {
                 let object = self.evaluate(object)?;
 
                 match object {
                     Value::Instance(instance) => {
-                        LoxInstance::get(&instance, identifier, *line, *column)?
+                        self.get_property(&instance, *identifier, *id, *span)?
                     }
+                    Value::Number(number) => Value::number_method(number, identifier.as_str())
+                        .ok_or_else(|| Error {
+                            span: *span,
+                            source: RuntimeError::undefined_property(
+                                identifier.as_str(),
+                                NUMBER_METHODS,
+                            ),
+                        })?,
+                    Value::UserData(user_data) => UserData::get(&user_data, *identifier, *span)?,
                     x => {
                         return Err(Error {
-                            line: *line,
-                            column: *column,
+                            span: *span,
                             source: RuntimeError::TypeIsNotInstance(x.type_name()),
                         })
                     }
@@ -407,60 +419,61 @@ impl Interpreter {
                 object,
                 identifier,
                 value,
-                line,
-                column,
+                span,
             } => {
                 let mut object = self.evaluate(object)?;
                 let value = self.evaluate(value)?;
 
                 match object {
                     Value::Instance(ref mut instance) => {
-                        instance.borrow_mut().set(identifier, value.clone());
+                        if !instance.borrow().fields.contains_key(identifier) {
+                            self.heap.charge_field();
+                            self.heap
+                                .check_limit(&self.globals)
+                                .map_err(|source| Error {
+                                    span: *span,
+                                    source,
+                                })?;
+                        }
+
+                        instance.borrow_mut().set(*identifier, value.clone());
+                    }
+                    Value::UserData(ref user_data) => {
+                        user_data.set(*identifier, value.clone(), *span)?;
                     }
                     x => {
                         return Err(Error {
-                            line: *line,
-                            column: *column,
+                            span: *span,
                             source: RuntimeError::TypeIsNotInstance(x.type_name()),
                         })
                     }
-                };
+                }
 
                 value
             }
-            Expression::This { line, column } => {
+            Expression::This { span, id } => {
                 let reference = Reference {
-                    line: *line,
-                    column: *column,
-                    identifier: "this".into(),
+                    span: *span,
+                    identifier: Symbol::THIS,
+                    id: *id,
                 };
                 self.lookup_variable(&reference)?
             }
-            Expression::Super {
-                line,
-                column,
-                method,
-            } => {
+            Expression::Super { span, method, id } => {
                 let super_reference = Reference {
-                    identifier: "super".into(),
-                    line: *line,
-                    column: *column,
-                };
-
-                let this_reference = Reference {
-                    identifier: "this".into(),
-                    line: 0,
-                    column: 0,
+                    identifier: Symbol::SUPER,
+                    span: *span,
+                    id: *id,
                 };
 
-                let Some(&distance) = self.locals.get(&super_reference) else {
+                let Some(&slot) = self.locals.get(id) else {
                     unreachable!()
                 };
 
                 let super_class = self
                     .environment
                     .borrow()
-                    .lookup_at(distance, &super_reference)?;
+                    .lookup_at(slot, &super_reference)?;
 
                 let Value::Callable(Callable {
                     kind: CallableKind::LoxClass(super_class),
@@ -470,51 +483,61 @@ impl Interpreter {
                     unreachable!()
                 };
 
-                let object = self
-                    .environment
-                    .borrow()
-                    .lookup_at(distance - 1, &this_reference)?;
+                // `this` is declared in the scope right inside the one of `super`
+                let object = self.environment.borrow().get_at(Slot {
+                    depth: slot.depth - 1,
+                    index: 0,
+                });
 
-                let Value::Instance(object) = object else {
+                let Some(Value::Instance(object)) = object else {
                     unreachable!()
                 };
 
-                let method = super_class.find_method(method).ok_or_else(|| Error {
-                    line: *line,
-                    column: *column,
-                    source: RuntimeError::UndefinedProperty(Rc::clone(method)),
+                let method = super_class.find_method(*method).ok_or_else(|| Error {
+                    span: *span,
+                    source: RuntimeError::undefined_property(
+                        method.as_str(),
+                        super_class.method_names(),
+                    ),
                 })?;
 
-                let bound_method = match method.kind {
-                    CallableKind::LoxFunction {
-                        ref parameters,
-                        ref body,
-                        ref closure,
-                        ref identifier,
-                        is_initializer,
-                    } => CallableKind::LoxFunction {
-                        identifier: identifier.clone(),
-                        parameters: Rc::clone(parameters),
-                        body: Rc::clone(body),
-                        closure: {
-                            let env = Environment::spawn_child(closure);
-                            env.borrow_mut()
-                                .define(&"this".into(), Some(Value::Instance(Rc::clone(&object))));
-                            env
-                        },
-                        is_initializer,
-                    },
-                    _ => unreachable!(),
-                };
-
-                Value::Callable(Callable {
-                    arity: method.arity,
-                    kind: bound_method,
-                })
+                Value::Callable(method.bind(&object))
             }
         })
     }
 
+    /// Looks up a field or a method of an instance. The method found is
+    /// cached for the property access, along with the class it was found
+    /// for, so the next access on an instance of the same class doesn't look
+    /// it up again
+    fn get_property(
+        &mut self,
+        instance: &Rc<RefCell<LoxInstance>>,
+        identifier: Symbol,
+        id: NodeId,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        let object = instance.borrow();
+
+        if let Some(value) = object.fields.get(&identifier) {
+            return Ok(value.clone());
+        }
+
+        if let Some(method) = self.heap.cached_method(id, &object.class) {
+            return Ok(Value::Callable(method.bind(instance)));
+        }
+
+        let method = object
+            .class
+            .find_method(identifier)
+            .ok_or_else(|| object.undefined_property(identifier, span))?;
+
+        let bound_method = method.bind(instance);
+        self.heap.cache_method(id, &object.class, method);
+
+        Ok(Value::Callable(bound_method))
+    }
+
     fn evaluate_ternary_expression(
         &mut self,
         condition: &Expression,
@@ -545,13 +568,12 @@ impl Interpreter {
             | BinaryOperatorKind::GreaterEqual
             | BinaryOperatorKind::LessThan
             | BinaryOperatorKind::LessEqual => Self::evaluate_comparison(left, operator, right)?,
-            BinaryOperatorKind::Plus => Self::evaluate_plus_operation(left, operator, right)?,
+            BinaryOperatorKind::Plus => self.evaluate_plus_operation(left, operator, right)?,
             BinaryOperatorKind::Minus => match (left, right) {
                 (Value::Number(a), Value::Number(b)) => Value::Number(a - b),
                 (Value::Number(_), x) | (x, _) => {
                     return Err(Error {
-                        line: operator.line,
-                        column: operator.column,
+                        span: operator.span,
                         source: RuntimeError::TypeError {
                             expected: "number",
                             found: x.type_name(),
@@ -563,8 +585,7 @@ impl Interpreter {
                 (Value::Number(a), Value::Number(b)) => Value::Number(a * b),
                 (Value::Number(_), x) | (x, _) => {
                     return Err(Error {
-                        line: operator.line,
-                        column: operator.column,
+                        span: operator.span,
                         source: RuntimeError::TypeError {
                             expected: "number",
                             found: x.type_name(),
@@ -576,8 +597,7 @@ impl Interpreter {
                 (Value::Number(a), Value::Number(b)) => Value::Number(a / b),
                 (Value::Number(_), x) | (x, _) => {
                     return Err(Error {
-                        line: operator.line,
-                        column: operator.column,
+                        span: operator.span,
                         source: RuntimeError::TypeError {
                             expected: "number",
                             found: x.type_name(),
@@ -624,8 +644,7 @@ impl Interpreter {
             },
             (a, b) => {
                 return Err(Error {
-                    line: operator.line,
-                    column: operator.column,
+                    span: operator.span,
                     source: RuntimeError::TypeError {
                         expected: a.type_name(),
                         found: b.type_name(),
@@ -636,6 +655,7 @@ impl Interpreter {
     }
 
     fn evaluate_plus_operation(
+        &mut self,
         left: Value,
         operator: &BinaryOperator,
         right: Value,
@@ -643,12 +663,11 @@ impl Interpreter {
         Ok(match (left, right) {
             (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
             (a @ Value::String(_), b) | (a, b @ Value::String(_)) => {
-                Self::concatenate_strings(&a, &b)
+                self.concatenate_strings(&a, &b, operator)?
             }
             (Value::Number(_), x) => {
                 return Err(Error {
-                    line: operator.line,
-                    column: operator.column,
+                    span: operator.span,
                     source: RuntimeError::TypeError {
                         expected: "number",
                         found: x.type_name(),
@@ -657,8 +676,7 @@ impl Interpreter {
             }
             (x, _) => {
                 return Err(Error {
-                    line: operator.line,
-                    column: operator.column,
+                    span: operator.span,
                     source: RuntimeError::TypeError {
                         // The error will read
                         // Expected expression of type "number" or
@@ -671,7 +689,12 @@ impl Interpreter {
         })
     }
 
-    fn concatenate_strings(left: &Value, right: &Value) -> Value {
+    fn concatenate_strings(
+        &mut self,
+        left: &Value,
+        right: &Value,
+        operator: &BinaryOperator,
+    ) -> Result<Value, RuntimeError> {
         let a = match left {
             Value::Number(value) => &value.to_string(),
             Value::Boolean(true) => "true",
@@ -680,6 +703,7 @@ impl Interpreter {
             Value::String(ref x) => x.as_ref(),
             Value::Callable(Callable { kind, .. }) => &kind.to_string(),
             Value::Instance(instance) => &instance.borrow().to_string(),
+            Value::UserData(_) => &left.to_string(),
         };
 
         let b = match right {
@@ -690,13 +714,23 @@ impl Interpreter {
             Value::String(ref x) => x.as_ref(),
             Value::Callable(Callable { kind, .. }) => &kind.to_string(),
             Value::Instance(instance) => &instance.borrow().to_string(),
+            Value::UserData(_) => &right.to_string(),
         };
 
+        // The string is only allocated if it fits in the memory limit
+        self.heap.charge(a.len() + b.len());
+        self.heap
+            .check_limit(&self.globals)
+            .map_err(|source| Error {
+                span: operator.span,
+                source,
+            })?;
+
         let mut string = String::with_capacity(a.len() + b.len());
         string.push_str(a);
         string.push_str(b);
 
-        Value::String(string.into())
+        Ok(Value::String(string.into()))
     }
 
     fn evaluate_logical_expression(
@@ -726,19 +760,37 @@ impl Interpreter {
     }
 
     fn lookup_variable(&self, reference: &Reference) -> Result<Value, RuntimeError> {
-        if let Some(&distance) = self.locals.get(reference) {
-            self.environment.borrow().lookup_at(distance, reference)
+        if let Some(&slot) = self.locals.get(&reference.id) {
+            self.environment.borrow().lookup_at(slot, reference)
         } else {
-            self.globals.borrow().lookup(reference)
+            self.globals
+                .borrow()
+                .lookup(reference)
+                .map_err(|error| self.suggest_visible(error))
         }
     }
 
+    /// Globals are looked up directly, so the suggestions of an undeclared
+    /// variable error only include global names. This replaces them with the
+    /// names visible from the current scope
+    fn suggest_visible(&self, mut error: Error<RuntimeError>) -> Error<RuntimeError> {
+        if let RuntimeError::UndeclaredVariable {
+            ref identifier,
+            ref mut suggestions,
+        } = error.source
+        {
+            let names = self.environment.borrow().visible_names();
+            *suggestions = suggest::suggestions(identifier, names);
+        }
+
+        error
+    }
+
     fn evaluate_call(
         &mut self,
         callee: &Expression,
         args: &[Expression],
-        line: usize,
-        column: usize,
+        span: Span,
     ) -> Result<Value, RuntimeError> {
         let callee = self.evaluate(callee)?;
         let mut arg_values = vec![];
@@ -747,122 +799,147 @@ impl Interpreter {
             arg_values.push(self.evaluate(arg)?);
         }
 
+        self.call_value(callee, &arg_values, &CallContext { span })
+    }
+
+    /// Calls `callee` with the given arguments, as if the call had been made
+    /// from Lox code at the position described by `context`
+    ///
+    /// # Errors
+    /// This function will error if `callee` is not callable, if the number of
+    /// arguments doesn't match its arity or if the call itself fails
+    pub fn call_value(
+        &mut self,
+        callee: Value,
+        args: &[Value],
+        context: &CallContext,
+    ) -> Result<Value, RuntimeError> {
         match callee {
+            Value::Callable(_) if self.call_stack.len() >= self.max_call_depth => {
+                Err(context.error(RuntimeError::StackOverflow(self.max_call_depth)))
+            }
             Value::Callable(function) if args.len() == function.arity => {
-                Ok(self.call(function, &arg_values)?)
+                self.call_stack.push(CallFrame {
+                    function: function.identifier(),
+                    call_site: context.span,
+                });
+
+                // Lox calls recurse natively, so the stack is grown on demand
+                // to let them go as deep as `max_call_depth` allows
+                let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+                    self.call(function, args, context)
+                });
+
+                // Errors are propagated up to the top level, unless a native
+                // function handles them, in which case it returns normally
+                match result {
+                    Ok(_) => self.trace = None,
+                    Err(ref error) => {
+                        if self.trace.is_none() {
+                            self.trace = Some(self.call_stack.clone());
+                        }
+
+                        if error.source.is_abort() {
+                            self.abort = Some(error.clone());
+                        }
+                    }
+                }
+
+                self.call_stack.pop();
+                result
             }
-            Value::Callable(Callable { arity, .. }) => Err(Error {
-                line,
-                column,
-                source: RuntimeError::ImcorrectNumberOfArguments {
+            Value::Callable(Callable { arity, .. }) => {
+                Err(context.error(RuntimeError::ImcorrectNumberOfArguments {
                     expected: arity,
                     found: args.len(),
-                },
-            }),
-            x => Err(Error {
-                line,
-                column,
-                source: RuntimeError::TypeIsNotCallable(x.type_name()),
-            }),
+                }))
+            }
+            x => Err(context.error(RuntimeError::TypeIsNotCallable(x.type_name()))),
         }
     }
 
-    fn call(&mut self, function: Callable, args: &[Value]) -> Result<Value, RuntimeError> {
+    /// Runs a native function or a foreign constructor, which can call back
+    /// into Lox. If an error stopping the program is raised meanwhile, it is
+    /// raised again even if the host code handled it
+    fn call_host<F>(&mut self, call: F) -> Result<Value, RuntimeError>
+    where
+        F: FnOnce(&mut Self) -> Result<Value, RuntimeError>,
+    {
+        let outer = self.abort.take();
+        let result = call(self);
+
+        std::mem::replace(&mut self.abort, outer).map_or(result, Err)
+    }
+
+    fn call(
+        &mut self,
+        function: Callable,
+        args: &[Value],
+        context: &CallContext,
+    ) -> Result<Value, RuntimeError> {
         Ok(match function.kind {
-            CallableKind::NativeFunction(function) => function(args),
+            CallableKind::NativeFunction { function, .. } => {
+                self.call_host(|interpreter| function(interpreter, context, args))?
+            }
             CallableKind::LoxFunction {
                 parameters,
                 body,
                 closure,
+                locals,
                 is_initializer,
+                this,
                 ..
             } => {
                 let current = Rc::clone(&self.environment);
+                let current_locals = std::mem::replace(&mut self.locals, locals);
+
+                self.environment = self.heap.environment(&closure);
 
-                self.environment = Environment::spawn_child(&closure);
+                // Methods find `this` in their first slot
+                if let Some(ref this) = this {
+                    self.environment
+                        .borrow_mut()
+                        .define(Symbol::THIS, Some(Value::Instance(Rc::clone(this))));
+                }
 
                 for (param, arg) in parameters.iter().zip(args) {
                     self.environment
                         .borrow_mut()
-                        .define(param, Some(arg.clone()));
+                        .define(param.identifier, Some(arg.clone()));
                 }
 
+                let mut completion = Ok(Completion::Normal);
                 for statement in body.iter() {
-                    match self.execute(statement) {
-                        Ok(()) => (),
-                        Err(error) => {
-                            self.environment = current;
-
-                            match error.source {
-                                RuntimeError::Return(_) if is_initializer => {
-                                    let reference = Reference {
-                                        identifier: "this".into(),
-                                        line: 0,
-                                        column: 0,
-                                    };
-
-                                    return closure.borrow().lookup_at(0, &reference);
-                                }
-                                RuntimeError::Return(value) => return Ok(value),
-                                _ => return Err(error),
-                            }
-                        }
+                    completion = self.execute(statement);
+
+                    if !matches!(completion, Ok(Completion::Normal)) {
+                        break;
                     }
                 }
 
                 self.environment = current;
+                self.locals = current_locals;
 
-                if is_initializer {
-                    let reference = Reference {
-                        identifier: "this".into(),
-                        line: 0,
-                        column: 0,
-                    };
-
-                    closure.borrow().lookup_at(0, &reference)?
-                } else {
-                    Value::Nil
+                match completion? {
+                    _ if is_initializer => Value::Instance(this.unwrap_or_else(|| unreachable!())),
+                    Completion::Return(value) => value,
+                    _ => Value::Nil,
                 }
             }
+            CallableKind::ForeignClass(class) => self.call_host(|interpreter| {
+                ForeignClass::construct(&class, interpreter, context, args)
+            })?,
             CallableKind::LoxClass(class) => {
-                let initializer = class.methods.get("init").cloned();
-                let instance = Rc::new(RefCell::new(LoxInstance {
-                    class,
-                    fields: HashMap::new(),
-                }));
+                let initializer = class.methods.get(&Symbol::INIT).cloned();
+                let instance = self.heap.instance(class);
 
                 let Some(initializer) = initializer else {
                     return Ok(Value::Instance(instance));
                 };
 
-                let initializer = Callable {
-                    arity: initializer.arity,
-                    kind: match initializer.kind {
-                        CallableKind::LoxFunction {
-                            ref parameters,
-                            ref body,
-                            ref closure,
-                            ref identifier,
-                            is_initializer,
-                        } => CallableKind::LoxFunction {
-                            identifier: identifier.clone(),
-                            parameters: Rc::clone(parameters),
-                            body: Rc::clone(body),
-                            closure: {
-                                let env = Environment::spawn_child(closure);
-                                env.borrow_mut().define(
-                                    &"this".into(),
-                                    Some(Value::Instance(Rc::clone(&instance))),
-                                );
-                                env
-                            },
-                            is_initializer,
-                        },
-                        _ => unreachable!(),
-                    },
-                };
+                let initializer = initializer.bind(&instance);
 
-                self.call(initializer, args)?
+                self.call(initializer, args, context)?
             }
         })
     }
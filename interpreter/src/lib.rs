@@ -1,16 +1,37 @@
 #![deny(clippy::pedantic, clippy::nursery)]
 #![allow(clippy::module_name_repetitions)]
 
+mod builder;
 mod callable;
+mod completion;
+mod convert;
+mod embed;
 mod environment;
 mod error;
+mod foreign;
+mod heap;
 mod instance;
 mod interpreter;
+mod limits;
+mod native;
+mod sandbox;
+mod stdlib;
+mod streams;
+mod suggest;
 mod value;
 
-pub use callable::{Callable, CallableKind, LoxClass};
+pub use builder::{InterpreterBuilder, DEFAULT_MAX_CALL_DEPTH};
+pub use callable::{CallContext, CallFrame, Callable, CallableKind, LoxClass, NativeFunction};
+pub use convert::{FromLox, IntoLox};
 pub use environment::Environment;
 pub use error::RuntimeError;
+pub use foreign::{ForeignClass, ForeignClassBuilder, UserData};
+pub use heap::HeapStats;
 pub use instance::LoxInstance;
 pub use interpreter::Interpreter;
+pub use limits::{InterruptHandle, Limits};
+pub use native::NativeBuilder;
+pub use sandbox::Sandbox;
+pub use stdlib::{Stdlib, NUMBER_METHODS};
+pub use streams::{OutputBuffer, Streams};
 pub use value::Value;
@@ -0,0 +1,121 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use crate::{Interpreter, RuntimeError};
+
+/// Number of evaluations between two checks of the deadline, since reading
+/// the clock is much slower than evaluating most expressions
+const DEADLINE_CHECK_INTERVAL: u32 = 1024;
+
+/// Bounds on how long a program can run. The interpreter checks them every
+/// time an expression is evaluated, the virtual machine at every call and
+/// every jump back to the start of a loop
+#[derive(Debug, Default)]
+pub struct Limits {
+    /// Evaluations left before the program is stopped, `None` if unlimited
+    pub fuel: Option<u64>,
+    pub deadline: Option<Instant>,
+    pub interrupted: Arc<AtomicBool>,
+
+    /// Evaluations since the deadline was last checked
+    pub ticks: u32,
+}
+
+impl Limits {
+    /// Charges one evaluation
+    ///
+    /// # Errors
+    /// This function will error if any of the limits was reached
+    pub fn tick(&mut self) -> Result<(), RuntimeError> {
+        if let Some(ref mut fuel) = self.fuel {
+            if *fuel == 0 {
+                return Err(RuntimeError::OutOfFuel);
+            }
+
+            *fuel -= 1;
+        }
+
+        // The flag is cleared so the interpreter can be used again afterwards
+        if self.interrupted.swap(false, Ordering::Relaxed) {
+            return Err(RuntimeError::Interrupted);
+        }
+
+        self.ticks += 1;
+        if self.ticks == DEADLINE_CHECK_INTERVAL {
+            self.ticks = 0;
+
+            if self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Err(RuntimeError::DeadlineExceeded);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A handle raising [`RuntimeError::Interrupted`] at the next check
+    #[must_use]
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            interrupted: Arc::clone(&self.interrupted),
+        }
+    }
+}
+
+/// Stops a running interpreter or virtual machine from another thread
+///
+/// ```ignore
+/// let handle = interpreter.interrupt_handle();
+/// std::thread::spawn(move || {
+///     std::thread::sleep(Duration::from_secs(1));
+///     handle.interrupt();
+/// });
+///
+/// interpreter.eval("while (true) {}")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct InterruptHandle {
+    interrupted: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    /// Makes the program raise [`RuntimeError::Interrupted`] the next time
+    /// its limits are checked
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Interpreter {
+    /// Evaluations left before [`RuntimeError::OutOfFuel`] is raised, `None`
+    /// if unlimited
+    #[must_use]
+    pub const fn fuel(&self) -> Option<u64> {
+        self.limits.fuel
+    }
+
+    /// Sets how many expressions can be evaluated before the program is
+    /// stopped, `None` to run without limit
+    pub const fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.limits.fuel = fuel;
+    }
+
+    /// Sets the instant after which [`RuntimeError::DeadlineExceeded`] is
+    /// raised, `None` to run without limit
+    pub const fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.limits.deadline = deadline;
+    }
+
+    /// A handle to stop the interpreter from another thread
+    #[must_use]
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.limits.interrupt_handle()
+    }
+}
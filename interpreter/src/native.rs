@@ -0,0 +1,65 @@
+use std::rc::Rc;
+
+use lox_core::{Result, Symbol};
+
+use crate::{CallContext, Callable, CallableKind, Environment, Interpreter, RuntimeError, Value};
+
+/// Builder for functions implemented in Rust and exposed to Lox code
+///
+/// ```ignore
+/// NativeBuilder::new("double")
+///     .arity(1)
+///     .define(&mut environment, |_, context, args| {
+///         Ok(Value::Number(2.0 * context.number(&args[0])?))
+///     });
+/// ```
+#[derive(Debug, Clone)]
+pub struct NativeBuilder {
+    identifier: Rc<str>,
+    arity: usize,
+}
+
+impl NativeBuilder {
+    #[must_use]
+    pub fn new(identifier: &str) -> Self {
+        Self {
+            identifier: identifier.into(),
+            arity: 0,
+        }
+    }
+
+    /// Sets the number of arguments the function expects, defaults to 0
+    #[must_use]
+    pub const fn arity(mut self, arity: usize) -> Self {
+        self.arity = arity;
+        self
+    }
+
+    pub(crate) const fn identifier(&self) -> &Rc<str> {
+        &self.identifier
+    }
+
+    /// Creates a callable value from the native function
+    #[must_use]
+    pub fn build<F>(self, function: F) -> Value
+    where
+        F: Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    {
+        Value::Callable(Callable {
+            arity: self.arity,
+            kind: CallableKind::NativeFunction {
+                identifier: self.identifier,
+                function: Rc::new(function),
+            },
+        })
+    }
+
+    /// Defines the native function in `environment` under its identifier
+    pub fn define<F>(self, environment: &mut Environment, function: F)
+    where
+        F: Fn(&mut Interpreter, &CallContext, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    {
+        let identifier = Symbol::intern(&self.identifier);
+        environment.define(identifier, Some(self.build(function)));
+    }
+}
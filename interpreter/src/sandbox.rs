@@ -0,0 +1,106 @@
+/// The capabilities granted to programs, deciding which natives are defined
+/// when the interpreter is built
+///
+/// ```ignore
+/// // Deterministic programs that can only print
+/// let interpreter = InterpreterBuilder::new()
+///     .sandbox(Sandbox::pure().stdout(true))
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Sandbox {
+    clock: bool,
+    stdin: bool,
+    stdout: bool,
+    host_natives: bool,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl Sandbox {
+    /// Grants every capability, the default
+    #[must_use]
+    pub const fn all() -> Self {
+        Self {
+            clock: true,
+            stdin: true,
+            stdout: true,
+            host_natives: true,
+        }
+    }
+
+    /// Grants no capability, leaving only natives without side effects that
+    /// always give the same result, like those of `Math`
+    #[must_use]
+    pub const fn pure() -> Self {
+        Self {
+            clock: false,
+            stdin: false,
+            stdout: false,
+            host_natives: false,
+        }
+    }
+
+    /// Whether `clock` is defined, letting programs read the system time
+    #[must_use]
+    pub const fn clock(mut self, clock: bool) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether `readLine` is defined, letting programs read the interpreter's
+    /// stdin
+    #[must_use]
+    pub const fn stdin(mut self, stdin: bool) -> Self {
+        self.stdin = stdin;
+        self
+    }
+
+    /// Whether `print` is defined, letting programs write to the
+    /// interpreter's stdout
+    #[must_use]
+    pub const fn stdout(mut self, stdout: bool) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Whether the natives and foreign classes registered with
+    /// [`InterpreterBuilder::native`](crate::InterpreterBuilder::native),
+    /// [`InterpreterBuilder::foreign_class`](crate::InterpreterBuilder::foreign_class)
+    /// or [`Interpreter::define_foreign_class`](crate::Interpreter::define_foreign_class)
+    /// are defined
+    #[must_use]
+    pub const fn host_natives(mut self, host_natives: bool) -> Self {
+        self.host_natives = host_natives;
+        self
+    }
+
+    /// Whether programs can read the system time
+    #[must_use]
+    pub const fn allows_clock(self) -> bool {
+        self.clock
+    }
+
+    /// Whether programs can read from stdin
+    #[must_use]
+    pub const fn allows_stdin(self) -> bool {
+        self.stdin
+    }
+
+    /// Whether programs can write to stdout
+    #[must_use]
+    pub const fn allows_stdout(self) -> bool {
+        self.stdout
+    }
+
+    /// Whether the natives registered by the host are defined
+    #[must_use]
+    pub const fn allows_host_natives(self) -> bool {
+        self.host_natives
+    }
+}
@@ -0,0 +1,399 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{BufRead, Write},
+    ops::RangeInclusive,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use lox_core::{Error, Result, SymbolMap};
+
+use crate::{
+    CallContext, ForeignClass, ForeignClassBuilder, Interpreter, IntoLox, LoxClass, LoxInstance,
+    NativeBuilder, RuntimeError, Sandbox, Value,
+};
+
+/// Digits used when formatting numbers in a radix other than 10
+const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Maximum number of fractional digits emitted by `toString(radix)`
+const MAX_FRACTIONAL_DIGITS: usize = 20;
+
+/// Maximum number of digits accepted by `toFixed(digits)`
+const MAX_FIXED_DIGITS: f64 = 100.0;
+
+/// Names of the methods of numbers
+pub const NUMBER_METHODS: [&str; 3] = ["toFixed", "toString", "isNaN"];
+
+/// The standard library, written once for the values of every backend.
+/// Backends implement the required methods, and get their globals and the
+/// methods of numbers from the provided ones
+pub trait Stdlib: Clone + std::fmt::Display + Sized + 'static {
+    /// What natives receive when they are called
+    type Host;
+
+    fn stdout(host: &mut Self::Host) -> &mut dyn Write;
+    fn stdin(host: &mut Self::Host) -> &mut dyn BufRead;
+
+    fn nil() -> Self;
+    fn boolean(boolean: bool) -> Self;
+    fn number(number: f64) -> Self;
+    fn string(string: Rc<str>) -> Self;
+
+    /// The number held by the value, if it is one
+    fn as_number(&self) -> Option<f64>;
+    fn type_name(&self) -> &'static str;
+
+    /// Wraps a function implemented in Rust
+    fn native<F>(identifier: &str, arity: usize, function: F) -> Self
+    where
+        F: Fn(&mut Self::Host, &CallContext, &[Self]) -> Result<Self, RuntimeError> + 'static;
+
+    /// Creates an object holding the given fields
+    fn object(identifier: &str, fields: Vec<(&str, Self)>) -> Self;
+
+    /// The `clock`, `print` and `readLine` globals, as far as the sandbox
+    /// allows, and the `Math` global, an object holding the numeric
+    /// functions and constants
+    #[must_use]
+    fn globals(sandbox: Sandbox) -> Vec<(&'static str, Self)> {
+        let mut globals = Vec::new();
+
+        if sandbox.allows_clock() {
+            let clock = Self::native("clock", 0, |_, _, _| {
+                let now = SystemTime::now();
+                let elapsed = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+                Ok(Self::number(1_000.0 * elapsed.as_secs_f64()))
+            });
+            globals.push(("clock", clock));
+        }
+
+        if sandbox.allows_stdout() {
+            let print = Self::native("print", 1, |host, context, args| {
+                writeln!(Self::stdout(host), "{}", args[0])
+                    .map_err(|error| io_error(context, &error))?;
+                Ok(Self::nil())
+            });
+            globals.push(("print", print));
+        }
+
+        if sandbox.allows_stdin() {
+            let read_line = Self::native("readLine", 0, |host, context, _| {
+                let mut buffer = String::new();
+                Self::stdin(host)
+                    .read_line(&mut buffer)
+                    .map_err(|error| io_error(context, &error))?;
+
+                Ok(Self::string(buffer.trim_end_matches(['\r', '\n']).into()))
+            });
+            globals.push(("readLine", read_line));
+        }
+
+        let math = Self::object(
+            "Math",
+            vec![
+                ("floor", unary("floor", f64::floor)),
+                ("ceil", unary("ceil", f64::ceil)),
+                ("round", unary("round", f64::round)),
+                ("abs", unary("abs", f64::abs)),
+                ("sqrt", unary("sqrt", f64::sqrt)),
+                ("sin", unary("sin", f64::sin)),
+                ("cos", unary("cos", f64::cos)),
+                ("tan", unary("tan", f64::tan)),
+                ("log", unary("log", f64::ln)),
+                ("pow", binary("pow", f64::powf)),
+                ("min", binary("min", f64::min)),
+                ("max", binary("max", f64::max)),
+                ("PI", Self::number(std::f64::consts::PI)),
+                ("E", Self::number(std::f64::consts::E)),
+            ],
+        );
+        globals.push(("Math", math));
+
+        globals
+    }
+
+    /// Returns the method `identifier` bound to `number`, if it exists
+    #[must_use]
+    fn number_method(number: f64, identifier: &str) -> Option<Self> {
+        Some(match identifier {
+            "toFixed" => Self::native("toFixed", 1, move |_, context, args| {
+                let digits = integer_in_range(context, &args[0], 0.0..=MAX_FIXED_DIGITS)?;
+                Ok(Self::string(format!("{number:.digits$}").into()))
+            }),
+            "toString" => Self::native("toString", 1, move |_, context, args| {
+                let radix = integer_in_range(context, &args[0], 2.0..=36.0)?;
+                Ok(Self::string(to_radix_string(number, radix).into()))
+            }),
+            "isNaN" => Self::native("isNaN", 0, move |_, _, _| {
+                Ok(Self::boolean(number.is_nan()))
+            }),
+            _ => return None,
+        })
+    }
+}
+
+impl Stdlib for Value {
+    type Host = Interpreter;
+
+    fn stdout(host: &mut Interpreter) -> &mut dyn Write {
+        host.stdout()
+    }
+
+    fn stdin(host: &mut Interpreter) -> &mut dyn BufRead {
+        host.stdin()
+    }
+
+    fn nil() -> Self {
+        Self::Nil
+    }
+
+    fn boolean(boolean: bool) -> Self {
+        Self::Boolean(boolean)
+    }
+
+    fn number(number: f64) -> Self {
+        Self::Number(number)
+    }
+
+    fn string(string: Rc<str>) -> Self {
+        Self::String(string)
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match *self {
+            Self::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.type_name()
+    }
+
+    fn native<F>(identifier: &str, arity: usize, function: F) -> Self
+    where
+        F: Fn(&mut Interpreter, &CallContext, &[Self]) -> Result<Self, RuntimeError> + 'static,
+    {
+        NativeBuilder::new(identifier).arity(arity).build(function)
+    }
+
+    fn object(identifier: &str, fields: Vec<(&str, Self)>) -> Self {
+        let fields: SymbolMap<Self> = fields
+            .into_iter()
+            .map(|(identifier, value)| (identifier.into(), value))
+            .collect();
+
+        Self::Instance(Rc::new(RefCell::new(LoxInstance {
+            class: Rc::new(LoxClass {
+                identifier: identifier.into(),
+                methods: SymbolMap::default(),
+                super_class: None,
+            }),
+            fields,
+        })))
+    }
+}
+
+/// Wraps a unary `f64` function
+fn unary<V: Stdlib>(identifier: &str, function: fn(f64) -> f64) -> V {
+    V::native(identifier, 1, move |_, context, args| {
+        Ok(V::number(function(number(context, &args[0])?)))
+    })
+}
+
+/// Wraps a binary `f64` function
+fn binary<V: Stdlib>(identifier: &str, function: fn(f64, f64) -> f64) -> V {
+    V::native(identifier, 2, move |_, context, args| {
+        let a = number(context, &args[0])?;
+        let b = number(context, &args[1])?;
+
+        Ok(V::number(function(a, b)))
+    })
+}
+
+/// Extracts a number from an argument
+fn number<V: Stdlib>(context: &CallContext, value: &V) -> Result<f64, RuntimeError> {
+    value.as_number().ok_or_else(|| {
+        context.error(RuntimeError::TypeError {
+            expected: "number",
+            found: value.type_name(),
+        })
+    })
+}
+
+/// Extracts an integer in the `range` from an argument
+fn integer_in_range<V: Stdlib>(
+    context: &CallContext,
+    value: &V,
+    range: RangeInclusive<f64>,
+) -> Result<usize, RuntimeError> {
+    let number = number(context, value)?;
+
+    if number.fract() != 0.0 || !range.contains(&number) {
+        return Err(context.error(RuntimeError::InvalidArgument(
+            format!(
+                "expected an integer between {} and {}, found {number}",
+                range.start(),
+                range.end()
+            )
+            .into(),
+        )));
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Ok(number as usize)
+}
+
+fn io_error(context: &CallContext, error: &std::io::Error) -> Error<RuntimeError> {
+    context.error(RuntimeError::Io(error.to_string().into()))
+}
+
+/// Builds the class of the objects Rust vectors are converted into. They
+/// can only be created by the host
+#[must_use]
+pub fn list_class() -> Rc<ForeignClass> {
+    ForeignClassBuilder::<Vec<Value>>::new("List")
+        .field("length", |list| list.len().into_lox())
+        .method("get", 1, |_, context, list, args| {
+            let index = list_index(context, &args[0], list.len())?;
+
+            Ok(list[index].clone())
+        })
+        .checked_method(
+            "set",
+            2,
+            |interpreter, context, args| charge_element(interpreter, context, &args[1]),
+            |context, list, args| {
+                let index = list_index(context, &args[0], list.len())?;
+                list[index] = args[1].clone();
+
+                Ok(args[1].clone())
+            },
+        )
+        .checked_method(
+            "push",
+            1,
+            |interpreter, context, args| charge_element(interpreter, context, &args[0]),
+            |_, list, args| {
+                list.push(args[0].clone());
+
+                Ok(Value::Nil)
+            },
+        )
+        .build()
+}
+
+/// Builds the class of the objects Rust maps are converted into. Their keys
+/// are strings, which unlike the names of fields are not interned, so hosts
+/// can convert maps with any keys. They can only be created by the host
+#[must_use]
+pub fn map_class() -> Rc<ForeignClass> {
+    ForeignClassBuilder::<HashMap<Rc<str>, Value>>::new("Map")
+        .field("length", |map| map.len().into_lox())
+        .method("get", 1, |_, context, map, args| {
+            let key = context.string(&args[0])?;
+
+            Ok(map.get(&key).cloned().unwrap_or(Value::Nil))
+        })
+        .method("has", 1, |_, context, map, args| {
+            let key = context.string(&args[0])?;
+
+            Ok(Value::Boolean(map.contains_key(&key)))
+        })
+        .checked_method(
+            "set",
+            2,
+            |interpreter, context, args| {
+                interpreter.heap.charge(context.string(&args[0])?.len());
+                charge_element(interpreter, context, &args[1])
+            },
+            |context, map, args| {
+                map.insert(context.string(&args[0])?, args[1].clone());
+
+                Ok(args[1].clone())
+            },
+        )
+        .build()
+}
+
+/// Counts an element stored in a list or a map towards the memory limit, failing if
+/// it doesn't fit
+fn charge_element(
+    interpreter: &mut Interpreter,
+    context: &CallContext,
+    value: &Value,
+) -> Result<(), RuntimeError> {
+    interpreter.heap.charge_element(value);
+    interpreter
+        .heap
+        .check_limit(&interpreter.globals)
+        .map_err(|source| context.error(source))
+}
+
+/// Extracts a valid index into a list of length `length` from an argument
+fn list_index(context: &CallContext, value: &Value, length: usize) -> Result<usize, RuntimeError> {
+    if length == 0 {
+        return Err(context.error(RuntimeError::InvalidArgument(
+            "cannot index into an empty list".into(),
+        )));
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let last = (length - 1) as f64;
+
+    integer_in_range(context, value, 0.0..=last)
+}
+
+/// Formats `number` in the given `radix`, which must be in the range `2..=36`
+///
+/// # Panics
+/// This function panics if `radix` is out of range
+#[must_use]
+pub fn to_radix_string(number: f64, radix: usize) -> String {
+    if radix == 10 || !number.is_finite() {
+        return Value::Number(number).to_string();
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let radix_f64 = radix as f64;
+    let mut integer = number.abs().trunc();
+    let mut fraction = number.abs().fract();
+
+    let mut digits = vec![];
+    loop {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        digits.push(DIGITS[(integer % radix_f64) as usize]);
+        integer = (integer / radix_f64).trunc();
+
+        if integer == 0.0 {
+            break;
+        }
+    }
+
+    if number.is_sign_negative() && number != 0.0 {
+        digits.push(b'-');
+    }
+
+    digits.reverse();
+
+    if fraction > 0.0 {
+        digits.push(b'.');
+
+        for _ in 0..MAX_FRACTIONAL_DIGITS {
+            fraction *= radix_f64;
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            digits.push(DIGITS[fraction.trunc() as usize]);
+            fraction = fraction.fract();
+
+            if fraction == 0.0 {
+                break;
+            }
+        }
+    }
+
+    String::from_utf8(digits).expect("Radix digits are valid ASCII")
+}
@@ -0,0 +1,99 @@
+use std::{
+    cell::RefCell,
+    io::{BufRead, Read, StdinLock, Write},
+    rc::Rc,
+};
+
+/// The input and output handles used by a program: `print` writes to
+/// `stdout`, `readLine` reads from `stdin` and errors are reported to
+/// `stderr`
+pub struct Streams {
+    pub stdout: Box<dyn Write>,
+    pub stdin: Box<dyn BufRead>,
+    pub stderr: Box<dyn Write>,
+}
+
+impl Default for Streams {
+    fn default() -> Self {
+        Self {
+            stdout: Box::new(std::io::stdout()),
+            stdin: Box::new(Stdin::default()),
+            stderr: Box::new(std::io::stderr()),
+        }
+    }
+}
+
+impl std::fmt::Debug for Streams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Streams").finish_non_exhaustive()
+    }
+}
+
+/// The stdin of the process, locked only while it is read so that several
+/// interpreters can share it. Input is buffered by the standard library, so
+/// nothing read ahead is lost between them
+#[derive(Default)]
+struct Stdin {
+    /// Held from the time the buffer is filled until it is consumed
+    lock: Option<StdinLock<'static>>,
+}
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.lock
+            .take()
+            .unwrap_or_else(|| std::io::stdin().lock())
+            .read(buf)
+    }
+}
+
+impl BufRead for Stdin {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.lock
+            .get_or_insert_with(|| std::io::stdin().lock())
+            .fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.lock
+            .take()
+            .unwrap_or_else(|| std::io::stdin().lock())
+            .consume(amount);
+    }
+}
+
+/// An in-memory output stream whose contents can be inspected after the
+/// interpreter writes to it
+///
+/// ```ignore
+/// let output = OutputBuffer::default();
+/// let mut interpreter = InterpreterBuilder::new().stdout(output.clone()).build();
+///
+/// interpreter.eval(r#"print("Hello");"#)?;
+/// assert_eq!(output.contents(), "Hello\n");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    /// Returns everything written so far, replacing invalid UTF-8
+    #[must_use]
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+
+    /// Discards everything written so far
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
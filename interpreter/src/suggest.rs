@@ -0,0 +1,67 @@
+use std::rc::Rc;
+
+/// Maximum number of suggestions attached to an error
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the candidates closest to `identifier`, ignoring the ones too
+/// different from it to be a typo
+pub fn suggestions<I, S>(identifier: &str, candidates: I) -> Vec<Rc<str>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    // Allow one edit for every three characters
+    let threshold = (identifier.chars().count() / 3).max(1);
+
+    let mut suggestions: Vec<(usize, Rc<str>)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let candidate = candidate.as_ref();
+            let distance = edit_distance(identifier, candidate);
+
+            (distance <= threshold && candidate != identifier).then(|| (distance, candidate.into()))
+        })
+        .collect();
+
+    suggestions.sort();
+    suggestions.dedup();
+
+    suggestions
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Number of insertions, deletions, substitutions and transpositions of
+/// adjacent characters needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Only the last three rows of the table are needed
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
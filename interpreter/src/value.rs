@@ -2,7 +2,7 @@ use std::{cell::RefCell, rc::Rc};
 
 use parser::Literal;
 
-use crate::{Callable, LoxInstance};
+use crate::{Callable, LoxInstance, UserData};
 
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -12,6 +12,7 @@ pub enum Value {
     Nil,
     Callable(Callable),
     Instance(Rc<RefCell<LoxInstance>>),
+    UserData(Rc<UserData>),
 }
 
 impl From<Literal> for Value {
@@ -34,7 +35,7 @@ impl Value {
             Self::Boolean(_) => "boolean",
             Self::Nil => "nil",
             Self::Callable(_) => "function",
-            Self::Instance(_) => "object",
+            Self::Instance(_) | Self::UserData(_) => "object",
         }
     }
 
@@ -56,6 +57,7 @@ impl std::fmt::Display for Value {
             Self::Nil => write!(f, "nil"),
             Self::Callable(function) => write!(f, "{function}"),
             Self::Instance(instance) => write!(f, "{}", instance.borrow()),
+            Self::UserData(user_data) => write!(f, "{user_data}"),
         }
     }
 }
@@ -63,17 +65,20 @@ impl std::fmt::Display for Value {
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::String(a), Self::String(b)) => a == b,
+            // Literals evaluate to the strings the lexer shares between equal
+            // ones, only strings built while running need their text compared
+            (Self::String(a), Self::String(b)) => Rc::ptr_eq(a, b) || a == b,
             (Self::Number(a), Self::Number(b)) => a == b,
             (Self::Boolean(a), Self::Boolean(b)) => a == b,
             (Self::Nil, Self::Nil) => true,
             (Self::Callable(a), Self::Callable(b)) => a == b,
             (Self::Instance(a), Self::Instance(b)) => {
-                let a = a.as_ref() as *const _;
-                let b = b.as_ref() as *const _;
+                let a = std::ptr::from_ref(a.as_ref());
+                let b = std::ptr::from_ref(b.as_ref());
 
                 a == b
             }
+            (Self::UserData(a), Self::UserData(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
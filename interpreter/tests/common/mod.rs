@@ -0,0 +1,28 @@
+// Each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use interpreter::{Interpreter, InterpreterBuilder, OutputBuffer};
+use lox_core::Diagnostic;
+
+/// Everything `source` prints
+pub fn output(source: &str) -> String {
+    let output = OutputBuffer::default();
+    let mut interpreter = InterpreterBuilder::new().stdout(output.clone()).build();
+
+    interpreter.eval(source).unwrap();
+    output.contents()
+}
+
+/// The error raised by running `source`
+pub fn error(source: &str) -> Diagnostic {
+    let diagnostics = Interpreter::new().eval(source).unwrap_err();
+
+    diagnostics.iter().next().unwrap().clone()
+}
+
+/// The code of the error raised by running `source` with `interpreter`
+pub fn error_code(interpreter: &mut Interpreter, source: &str) -> &'static str {
+    let diagnostics = interpreter.eval(source).unwrap_err();
+
+    diagnostics.iter().next().unwrap().code
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::output;
+use interpreter::{Interpreter, Value};
+
+#[test]
+fn return_leaves_nested_loops() {
+    let source = r"
+        fun find(target) {
+            for (var i = 0; i < 10; i = i + 1) {
+                var j = 0;
+                while (true) {
+                    if (i * j == target) return i + j;
+                    if (j == i) break;
+                    j = j + 1;
+                }
+            }
+            return nil;
+        }
+        print(find(12));
+        print(find(11));
+    ";
+
+    assert_eq!(output(source), "7\nnil\n");
+}
+
+#[test]
+fn break_leaves_only_the_innermost_loop() {
+    let source = r"
+        for (var i = 0; i < 3; i = i + 1) {
+            while (true) break;
+            print(i);
+        }
+    ";
+
+    assert_eq!(output(source), "0\n1\n2\n");
+}
+
+#[test]
+fn continue_runs_the_increment_of_for_loops() {
+    let source = r"
+        for (var i = 0; i < 5; i = i + 1) {
+            if (i == 1 or i == 3) continue;
+            print(i);
+        }
+    ";
+
+    assert_eq!(output(source), "0\n2\n4\n");
+}
+
+#[test]
+fn continue_checks_the_condition_of_while_loops() {
+    let source = r"
+        var i = 0;
+        while (i < 4) {
+            i = i + 1;
+            if (i == 2) continue;
+            print(i);
+        }
+    ";
+
+    assert_eq!(output(source), "1\n3\n4\n");
+}
+
+#[test]
+fn returning_from_a_function_called_by_the_host_is_not_an_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval("fun early(x) { while (true) { return x; } }")
+        .unwrap();
+
+    assert_eq!(
+        interpreter.call_function("early", &[Value::Number(1.0)]),
+        Ok(Value::Number(1.0))
+    );
+}
+
+#[test]
+fn initializers_return_the_instance_early() {
+    let source = r"
+        class Point {
+            init(x) {
+                this.x = x;
+                if (x > 0) return;
+                this.x = 0;
+            }
+        }
+        print(Point(3).x);
+        print(Point(0 - 3).x);
+    ";
+
+    assert_eq!(output(source), "3\n0\n");
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use interpreter::{FromLox, Interpreter, IntoLox, RuntimeError, Value};
+use lox_core::Symbol;
+
+#[test]
+fn eval_returns_the_last_expression() {
+    let mut interpreter = Interpreter::new();
+
+    assert_eq!(interpreter.eval("1 + 2;"), Ok(Value::Number(3.0)));
+    assert_eq!(interpreter.eval("var x = 1;"), Ok(Value::Nil));
+}
+
+#[test]
+fn globals_persist_between_evals() {
+    let mut interpreter = Interpreter::new();
+
+    interpreter.set_global("base", 40);
+    interpreter.eval("var answer = base + 2;").unwrap();
+
+    assert_eq!(interpreter.get_global("answer"), Some(Value::Number(42.0)));
+    assert_eq!(interpreter.get_global("missing"), None);
+}
+
+#[test]
+fn functions_defined_in_earlier_evals_can_be_called() {
+    let mut interpreter = Interpreter::new();
+
+    interpreter
+        .eval("fun greet(name) { var greeting = \"Hello, \"; return greeting + name; }")
+        .unwrap();
+    interpreter
+        .eval("var unrelated = 1; { var shadow = 2; }")
+        .unwrap();
+
+    let greeting = interpreter.call_function("greet", &["Lox".into_lox()]);
+
+    assert_eq!(greeting, Ok(Value::String("Hello, Lox".into())));
+    assert_eq!(
+        interpreter.eval("greet(\"you\");"),
+        Ok("Hello, you".into_lox())
+    );
+}
+
+#[test]
+fn calling_a_missing_function_fails() {
+    let diagnostics = Interpreter::new().call_function("nope", &[]).unwrap_err();
+
+    assert_eq!(diagnostics.iter().next().unwrap().code, "E0403");
+}
+
+#[test]
+fn integers_must_be_in_range() {
+    assert_eq!(u8::from_lox(Value::Number(255.0)).ok(), Some(255));
+    assert!(u8::from_lox(Value::Number(256.0)).is_err());
+    assert!(u8::from_lox(Value::Number(-1.0)).is_err());
+    assert!(i32::from_lox(Value::Number(1.5)).is_err());
+    assert!(i64::from_lox(Value::Number(9_223_372_036_854_775_808.0)).is_err());
+    assert_eq!(
+        i64::from_lox(Value::Number(-9_223_372_036_854_775_808.0)).ok(),
+        Some(i64::MIN)
+    );
+}
+
+#[test]
+fn vectors_round_trip_through_lists() {
+    let mut interpreter = Interpreter::new();
+
+    interpreter.set_global("list", vec![1, 2, 3]);
+    interpreter
+        .eval("list.push(list.get(0) + list.length);")
+        .unwrap();
+
+    let list = Vec::<u32>::from_lox(interpreter.get_global("list").unwrap());
+
+    assert_eq!(list.ok(), Some(vec![1, 2, 3, 4]));
+}
+
+#[test]
+fn maps_round_trip_through_map_objects() {
+    let mut interpreter = Interpreter::new();
+
+    interpreter.set_global("map", HashMap::from([("a".to_string(), 1.0)]));
+    interpreter
+        .eval(r#"map.set("b", map.get("a") + map.length);"#)
+        .unwrap();
+
+    let map = HashMap::<String, f64>::from_lox(interpreter.get_global("map").unwrap()).unwrap();
+
+    assert_eq!(map, HashMap::from([("a".into(), 1.0), ("b".into(), 2.0)]));
+}
+
+#[test]
+fn instances_convert_into_maps_of_their_fields() {
+    let mut interpreter = Interpreter::new();
+
+    let point = interpreter
+        .eval("class Point { norm() {} } var point = Point(); point.x = 1; point.y = 2; point;")
+        .unwrap();
+    let point = HashMap::<String, f64>::from_lox(point).unwrap();
+
+    assert_eq!(point, HashMap::from([("x".into(), 1.0), ("y".into(), 2.0)]));
+}
+
+#[test]
+fn host_strings_are_not_interned() {
+    let mut interpreter = Interpreter::new();
+    let key = "a key that is never an identifier";
+
+    interpreter.set_global("map", HashMap::from([(key.to_string(), 1.0)]));
+    assert_eq!(interpreter.get_global("neverDeclaredGlobal"), None);
+
+    assert_eq!(Symbol::lookup(key), None);
+    assert_eq!(Symbol::lookup("neverDeclaredGlobal"), None);
+}
+
+#[test]
+fn mismatched_types_fail_to_convert() {
+    let error = String::from_lox(Value::Number(1.0)).unwrap_err();
+
+    assert!(matches!(
+        error,
+        RuntimeError::TypeError {
+            expected: "string",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn variables_at_the_same_position_in_other_evals_resolve_separately() {
+    let mut interpreter = Interpreter::new();
+
+    // Both uses of `a` are at the same line and column, in scopes of
+    // different depths
+    interpreter
+        .eval("fun f() { var a = 1; fun g() {  return a; } return g; }")
+        .unwrap();
+    interpreter
+        .eval("fun h() { var a = 2; fun g() {{ return a; }} return g; }")
+        .unwrap();
+
+    assert_eq!(interpreter.eval("f()();"), Ok(Value::Number(1.0)));
+    assert_eq!(interpreter.eval("h()();"), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn this_and_super_resolve_in_nested_methods() {
+    let source = r#"
+        class Base { name() { return "base"; } }
+        class Derived < Base {
+            name() { return "derived"; }
+            both() {
+                fun inner() { return this.name() + " " + super.name(); }
+                return inner();
+            }
+        }
+        Derived().both();
+    "#;
+
+    assert_eq!(
+        Interpreter::new().eval(source),
+        Ok(Value::String("derived base".into()))
+    );
+}
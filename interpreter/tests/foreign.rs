@@ -0,0 +1,102 @@
+//! Foreign classes are only supported by the interpreter, the virtual
+//! machine has no equivalent
+
+mod common;
+
+use std::rc::Rc;
+
+use common::error_code;
+use interpreter::{ForeignClass, ForeignClassBuilder, InterpreterBuilder, IntoLox, Value};
+
+struct Counter {
+    count: u32,
+    step: u32,
+}
+
+fn counter_class() -> Rc<ForeignClass> {
+    ForeignClassBuilder::<Counter>::new("Counter")
+        .constructor(1, |_, context, args| {
+            Ok(Counter {
+                count: 0,
+                step: context.number(&args[0])? as u32,
+            })
+        })
+        .method("increment", 0, |_, _, counter, _| {
+            counter.count += counter.step;
+            Ok(Value::Nil)
+        })
+        .field("count", |counter| counter.count.into_lox())
+        .field_mut(
+            "step",
+            |counter| counter.step.into_lox(),
+            |counter, context, value| {
+                counter.step = context.number(&value)? as u32;
+                Ok(())
+            },
+        )
+        .build()
+}
+
+#[test]
+fn foreign_classes_are_constructed_from_lox() {
+    let mut interpreter = InterpreterBuilder::new()
+        .foreign_class(&counter_class())
+        .build();
+
+    let result = interpreter.eval(
+        r"
+        var counter = Counter(2);
+        counter.increment();
+        counter.step = 5;
+        counter.increment();
+        counter.count;
+        ",
+    );
+
+    assert_eq!(result, Ok(Value::Number(7.0)));
+    assert_eq!(
+        interpreter.eval("counter;").unwrap().to_string(),
+        "<Counter instance>"
+    );
+}
+
+#[test]
+fn foreign_objects_are_shared_with_the_host() {
+    let class = counter_class();
+    let mut interpreter = InterpreterBuilder::new().build();
+
+    let counter = ForeignClass::instantiate(&class, Counter { count: 1, step: 1 });
+    interpreter.set_global("counter", counter.clone());
+    interpreter.eval("counter.increment();").unwrap();
+
+    let Value::UserData(user_data) = counter else {
+        panic!("foreign objects are user data");
+    };
+
+    assert_eq!(user_data.borrow_mut::<Counter>().unwrap().count, 2);
+    assert!(user_data.borrow_mut::<String>().is_none());
+}
+
+#[test]
+fn foreign_properties_are_checked() {
+    let mut interpreter = InterpreterBuilder::new()
+        .foreign_class(&counter_class())
+        .build();
+
+    interpreter.eval("var counter = Counter(1);").unwrap();
+
+    assert_eq!(error_code(&mut interpreter, "counter.count = 3;"), "E0416");
+    assert_eq!(error_code(&mut interpreter, "counter.missing;"), "E0411");
+    assert_eq!(
+        error_code(&mut interpreter, "counter.increment(1);"),
+        "E0408"
+    );
+}
+
+#[test]
+fn classes_without_constructor_are_not_constructible() {
+    let class = ForeignClassBuilder::<Counter>::new("Handle").build();
+    let mut interpreter = InterpreterBuilder::new().foreign_class(&class).build();
+
+    assert_eq!(error_code(&mut interpreter, "Handle();"), "E0415");
+}
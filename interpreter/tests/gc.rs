@@ -0,0 +1,104 @@
+//! Cycles are only collected by the interpreter, the virtual machine never
+//! frees objects referring to each other
+
+use interpreter::{HeapStats, Interpreter, Value};
+
+/// The environments and instances alive
+const fn alive(stats: HeapStats) -> (usize, usize) {
+    (stats.environments, stats.instances)
+}
+
+#[test]
+fn recursive_local_functions_are_collected() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval(
+            r"
+            fun count(n) {
+                fun go(n) { if (n == 0) return 0; return 1 + go(n - 1); }
+                return go(n);
+            }
+            for (var i = 0; i < 10; i = i + 1) count(3);
+            ",
+        )
+        .unwrap();
+
+    interpreter.collect_garbage();
+    let stats = interpreter.heap_stats();
+
+    assert_eq!(alive(stats), (0, 0));
+    assert!(stats.collected >= 10);
+}
+
+#[test]
+fn instances_referencing_each_other_are_collected() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval(
+            r"
+            class Node {}
+            fun link() {
+                var a = Node();
+                var b = Node();
+                a.next = b;
+                b.next = a;
+            }
+            link();
+            ",
+        )
+        .unwrap();
+
+    interpreter.collect_garbage();
+
+    assert_eq!(alive(interpreter.heap_stats()), (0, 0));
+}
+
+#[test]
+fn reachable_cycles_are_kept() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval(
+            r"
+            class Node {}
+            var a = Node();
+            a.self = a;
+            a.value = 42;
+            fun counter() {
+                var count = 0;
+                fun increment() { count = count + 1; return count; }
+                return increment;
+            }
+            var increment = counter();
+            increment();
+            ",
+        )
+        .unwrap();
+
+    assert_eq!(interpreter.collect_garbage(), 0);
+    assert_eq!(interpreter.eval("a.self.value;"), Ok(Value::Number(42.0)));
+    assert_eq!(interpreter.eval("increment();"), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn values_held_by_the_host_are_kept() {
+    let mut interpreter = Interpreter::new();
+    let closure = interpreter
+        .eval(
+            r"
+            fun make() {
+                var value = 1;
+                fun get() { return value; }
+                return get;
+            }
+            make();
+            ",
+        )
+        .unwrap();
+
+    interpreter.collect_garbage();
+
+    assert_eq!(alive(interpreter.heap_stats()).0, 1);
+
+    interpreter.set_global("held", closure);
+    assert_eq!(interpreter.eval("held();"), Ok(Value::Number(1.0)));
+}
@@ -0,0 +1,175 @@
+//! Memory limits and natives are only supported by the interpreter. Fuel,
+//! deadlines and interrupts are also covered for the virtual machine in
+//! `vm/tests/limits.rs`
+
+mod common;
+
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::error_code;
+use interpreter::{Interpreter, InterpreterBuilder, NativeBuilder, Value};
+
+/// Defines `attempt`, a native calling the function it is given and
+/// returning whether the call succeeded
+fn with_attempt(builder: InterpreterBuilder) -> InterpreterBuilder {
+    builder.native(
+        NativeBuilder::new("attempt").arity(1),
+        |interpreter, context, args| {
+            let result = interpreter.call_value(args[0].clone(), &[], context);
+
+            Ok(Value::Boolean(result.is_ok()))
+        },
+    )
+}
+
+#[test]
+fn running_out_of_fuel_stops_the_program() {
+    let mut interpreter = InterpreterBuilder::new().fuel(1_000).build();
+
+    assert_eq!(error_code(&mut interpreter, "while (true) {}"), "E0419");
+    assert_eq!(interpreter.fuel(), Some(0));
+
+    interpreter.set_fuel(Some(1_000));
+    assert_eq!(interpreter.eval("1 + 2;"), Ok(Value::Number(3.0)));
+    assert!(interpreter.fuel().unwrap() < 1_000);
+}
+
+#[test]
+fn passing_the_deadline_stops_the_program() {
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let mut interpreter = InterpreterBuilder::new().deadline(deadline).build();
+
+    assert_eq!(error_code(&mut interpreter, "while (true) {}"), "E0420");
+
+    interpreter.set_deadline(None);
+    assert_eq!(interpreter.eval("1 + 2;"), Ok(Value::Number(3.0)));
+}
+
+#[test]
+fn interrupting_stops_the_program() {
+    let mut interpreter = Interpreter::new();
+    let handle = interpreter.interrupt_handle();
+
+    let interrupter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+
+    assert_eq!(error_code(&mut interpreter, "while (true) {}"), "E0421");
+    interrupter.join().unwrap();
+
+    assert_eq!(interpreter.eval("1 + 2;"), Ok(Value::Number(3.0)));
+}
+
+#[test]
+fn natives_cannot_handle_aborts() {
+    let mut interpreter = with_attempt(InterpreterBuilder::new().fuel(1_000)).build();
+
+    let source = "fun spin() { while (true) {} }\nattempt(spin);";
+
+    assert_eq!(error_code(&mut interpreter, source), "E0419");
+}
+
+#[test]
+fn natives_can_handle_other_errors() {
+    let mut interpreter = with_attempt(InterpreterBuilder::new()).build();
+
+    let source = "fun fail() { return nil + 1; }\nattempt(fail);";
+
+    assert_eq!(interpreter.eval(source), Ok(Value::Boolean(false)));
+}
+
+#[test]
+fn growing_strings_past_the_memory_limit_stops_the_program() {
+    let mut interpreter = InterpreterBuilder::new().memory_limit(1 << 20).build();
+
+    let source = r#"var s = "x"; while (true) s = s + s;"#;
+
+    assert_eq!(error_code(&mut interpreter, source), "E0422");
+    assert_eq!(interpreter.memory_limit(), Some(1 << 20));
+}
+
+#[test]
+fn adding_fields_past_the_memory_limit_stops_the_program() {
+    let mut interpreter = InterpreterBuilder::new().memory_limit(1 << 16).build();
+
+    let source = r"
+        class Bag {}
+        var previous = nil;
+        while (true) {
+            var bag = Bag();
+            bag.previous = previous;
+            bag.padding = 1;
+            previous = bag;
+        }
+    ";
+
+    assert_eq!(error_code(&mut interpreter, source), "E0422");
+}
+
+#[test]
+fn garbage_under_the_memory_limit_is_not_counted() {
+    let mut interpreter = InterpreterBuilder::new().memory_limit(1 << 16).build();
+
+    let source = r#"
+        class Node {}
+        for (var i = 0; i < 20000; i = i + 1) {
+            var a = Node();
+            a.self = a;
+            a.name = "node " + i;
+        }
+        "done";
+    "#;
+
+    assert_eq!(interpreter.eval(source), Ok(Value::String("done".into())));
+    assert!(interpreter.heap_stats().bytes < 1 << 16);
+}
+
+#[test]
+fn lists_cannot_grow_past_the_memory_limit() {
+    let mut interpreter = InterpreterBuilder::new().memory_limit(100_000).build();
+    interpreter.set_global("list", Vec::<f64>::new());
+
+    let source = r#"for (var i = 0; i < 200000; i = i + 1) list.push("item " + i);"#;
+
+    assert_eq!(error_code(&mut interpreter, source), "E0422");
+    assert!(interpreter.heap_stats().bytes > 50_000);
+}
+
+#[test]
+fn maps_cannot_grow_past_the_memory_limit() {
+    let mut interpreter = InterpreterBuilder::new().memory_limit(100_000).build();
+    interpreter.set_global("map", HashMap::<String, f64>::new());
+
+    let source = r#"for (var i = 0; i < 200000; i = i + 1) map.set("key " + i, i);"#;
+
+    assert_eq!(error_code(&mut interpreter, source), "E0422");
+}
+
+#[test]
+fn lists_reused_under_the_memory_limit_are_not_counted_twice() {
+    let mut interpreter = InterpreterBuilder::new().memory_limit(100_000).build();
+    interpreter.set_global("list", vec![0.0; 100]);
+
+    let source = r#"
+        for (var i = 0; i < 20000; i = i + 1) list.set(i - 100 * Math.floor(i / 100), "item");
+        var alias = list;
+        list.length;
+    "#;
+
+    assert_eq!(interpreter.eval(source), Ok(Value::Number(100.0)));
+}
+
+#[test]
+fn natives_cannot_handle_running_out_of_memory() {
+    let mut interpreter = with_attempt(InterpreterBuilder::new().memory_limit(1 << 16)).build();
+
+    let source = r#"fun grow() { var s = "x"; while (true) s = s + s; }
+attempt(grow);"#;
+
+    assert_eq!(error_code(&mut interpreter, source), "E0422");
+}
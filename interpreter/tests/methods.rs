@@ -0,0 +1,74 @@
+mod common;
+
+use common::output;
+use interpreter::Interpreter;
+
+#[test]
+fn call_sites_dispatch_on_the_class_of_each_receiver() {
+    let source = r#"
+        class Cat { speak() { return "meow"; } }
+        class Dog { speak() { return "woof"; } }
+        class Puppy < Dog { speak() { return "yip " + super.speak(); } }
+        fun speak(animal) { return animal.speak(); }
+        var animals = "";
+        for (var i = 0; i < 6; i = i + 1) {
+            var animal = i == 0 or i == 3 ? Cat() : i == 1 or i == 4 ? Dog() : Puppy();
+            animals = animals + speak(animal) + ",";
+        }
+        print(animals);
+    "#;
+
+    assert_eq!(output(source), "meow,woof,yip woof,meow,woof,yip woof,\n");
+}
+
+#[test]
+fn fields_shadow_cached_methods() {
+    let source = r#"
+        class Greeter { greet() { return "method"; } }
+        fun greet(greeter) { return greeter.greet(); }
+        var greeter = Greeter();
+        print(greet(greeter));
+        greeter.greet = fun () { return "field"; };
+        print(greet(greeter));
+        print(greet(Greeter()));
+    "#;
+
+    assert_eq!(output(source), "method\nfield\nmethod\n");
+}
+
+#[test]
+fn bound_methods_keep_their_receiver() {
+    let source = r"
+        class Counter {
+            init(count) { this.count = count; }
+            get() { return this.count; }
+        }
+        var first = Counter(1).get;
+        var second = Counter(2).get;
+        print(first() + second() * 10);
+    ";
+
+    assert_eq!(output(source), "21\n");
+}
+
+#[test]
+fn classes_only_used_by_cached_calls_are_collected() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval(
+            r"
+            fun run() {
+                class Local { get() { return 1; } }
+                var local = Local();
+                return local.get();
+            }
+            run();
+            ",
+        )
+        .unwrap();
+
+    interpreter.collect_garbage();
+    let stats = interpreter.heap_stats();
+
+    assert_eq!((stats.environments, stats.instances), (0, 0));
+}
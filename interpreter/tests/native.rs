@@ -0,0 +1,117 @@
+//! Host natives are only supported by the interpreter, the virtual machine
+//! only has the core natives
+
+use std::{cell::Cell, rc::Rc};
+
+use interpreter::{FromLox, InterpreterBuilder, NativeBuilder, RuntimeError, Value};
+use lox_core::Span;
+
+#[test]
+fn natives_receive_their_arguments() {
+    let mut interpreter = InterpreterBuilder::new()
+        .native(NativeBuilder::new("double").arity(1), |_, context, args| {
+            Ok(Value::Number(2.0 * context.number(&args[0])?))
+        })
+        .build();
+
+    assert_eq!(interpreter.eval("double(21);"), Ok(Value::Number(42.0)));
+}
+
+#[test]
+fn natives_see_their_call_site() {
+    let call_site = Rc::new(Cell::new(Span::default()));
+    let seen = Rc::clone(&call_site);
+
+    let mut interpreter = InterpreterBuilder::new()
+        .native(NativeBuilder::new("where"), move |_, context, _| {
+            seen.set(context.span);
+            Ok(Value::Nil)
+        })
+        .build();
+
+    interpreter.eval("var x = 1;\nwhere();").unwrap();
+
+    assert_eq!(call_site.get(), Span::new(11, 18));
+}
+
+#[test]
+fn failing_natives_stop_the_program() {
+    let mut interpreter = InterpreterBuilder::new()
+        .native(NativeBuilder::new("fail"), |_, context, _| {
+            Err(context.error(RuntimeError::InvalidArgument("always fails".into())))
+        })
+        .build();
+
+    let diagnostics = interpreter
+        .eval("var before = 1;\nfail();\nvar after = 2;")
+        .unwrap_err();
+    let diagnostic = diagnostics.iter().next().unwrap();
+
+    assert_eq!(diagnostic.code, "E0413");
+    assert_eq!(diagnostic.primary.span, Span::new(16, 22));
+    assert_eq!(interpreter.get_global("before"), Some(Value::Number(1.0)));
+    assert_eq!(interpreter.get_global("after"), None);
+}
+
+#[test]
+fn natives_check_their_arity() {
+    let mut interpreter = InterpreterBuilder::new()
+        .native(NativeBuilder::new("one").arity(1), |_, _, args| {
+            Ok(args[0].clone())
+        })
+        .build();
+
+    let diagnostics = interpreter.eval("one(1, 2);").unwrap_err();
+
+    assert_eq!(diagnostics.iter().next().unwrap().code, "E0408");
+}
+
+#[test]
+fn natives_call_back_into_lox() {
+    let mut interpreter = InterpreterBuilder::new()
+        .native(
+            NativeBuilder::new("apply").arity(2),
+            |interpreter, context, args| {
+                interpreter.call_value(args[0].clone(), &args[1..], context)
+            },
+        )
+        .build();
+
+    let result = interpreter.eval("fun square(x) { return x * x; }\napply(square, 7);");
+
+    assert_eq!(result, Ok(Value::Number(49.0)));
+}
+
+#[test]
+fn natives_can_eval_programs_while_another_runs() {
+    let mut interpreter = InterpreterBuilder::new()
+        .native(
+            NativeBuilder::new("load").arity(1),
+            |interpreter, context, args| {
+                let source =
+                    String::from_lox(args[0].clone()).map_err(|error| context.error(error))?;
+                interpreter
+                    .eval(&source)
+                    .map_err(|_| context.error(RuntimeError::InvalidArgument("load failed".into())))
+            },
+        )
+        .build();
+
+    interpreter
+        .eval(
+            r#"
+var inner;
+var after;
+{
+    var a = 1;
+    inner = load("{ var q = 5; q; } var loaded = 2; loaded + 3;");
+    after = a;
+}
+"#,
+        )
+        .unwrap();
+
+    assert_eq!(interpreter.get_global("inner"), Some(Value::Number(5.0)));
+    assert_eq!(interpreter.get_global("after"), Some(Value::Number(1.0)));
+    assert_eq!(interpreter.get_global("loaded"), Some(Value::Number(2.0)));
+}
@@ -0,0 +1,43 @@
+use interpreter::{Interpreter, InterpreterBuilder, Value};
+
+const RECURSE: &str = "fun depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }";
+
+#[test]
+fn recursion_beyond_the_maximum_depth_overflows() {
+    let mut interpreter = InterpreterBuilder::new().max_call_depth(100).build();
+    interpreter.eval(RECURSE).unwrap();
+
+    assert_eq!(interpreter.eval("depth(99);"), Ok(Value::Number(99.0)));
+
+    let diagnostics = interpreter.eval("depth(100);").unwrap_err();
+    let diagnostic = diagnostics.iter().next().unwrap();
+
+    assert_eq!(diagnostic.code, "E0418");
+    assert_eq!(diagnostic.trace.len(), 100);
+}
+
+#[test]
+fn unbounded_recursion_is_reported() {
+    let diagnostics = Interpreter::new()
+        .eval("fun forever() { return forever(); }\nforever();")
+        .unwrap_err();
+
+    assert_eq!(diagnostics.iter().next().unwrap().code, "E0418");
+}
+
+#[test]
+fn deep_recursion_grows_the_stack() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(RECURSE).unwrap();
+
+    assert_eq!(interpreter.eval("depth(9000);"), Ok(Value::Number(9000.0)));
+}
+
+#[test]
+fn the_interpreter_is_usable_after_an_overflow() {
+    let mut interpreter = InterpreterBuilder::new().max_call_depth(10).build();
+    interpreter.eval(RECURSE).unwrap();
+
+    assert!(interpreter.eval("depth(20);").is_err());
+    assert_eq!(interpreter.eval("depth(5);"), Ok(Value::Number(5.0)));
+}
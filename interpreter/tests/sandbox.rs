@@ -0,0 +1,72 @@
+use std::io::Cursor;
+
+use interpreter::{
+    ForeignClassBuilder, Interpreter, InterpreterBuilder, NativeBuilder, OutputBuffer, Sandbox,
+    Value,
+};
+
+/// Whether `identifier` is defined as a global of `interpreter`
+fn defines(interpreter: &Interpreter, identifier: &str) -> bool {
+    interpreter.get_global(identifier).is_some()
+}
+
+#[test]
+fn all_capabilities_are_granted_by_default() {
+    let interpreter = Interpreter::new();
+
+    for identifier in ["clock", "print", "readLine", "Math"] {
+        assert!(defines(&interpreter, identifier), "{identifier}");
+    }
+}
+
+#[test]
+fn pure_programs_have_no_io() {
+    let interpreter = InterpreterBuilder::new().sandbox(Sandbox::pure()).build();
+
+    for identifier in ["clock", "print", "readLine"] {
+        assert!(!defines(&interpreter, identifier), "{identifier}");
+    }
+    assert!(defines(&interpreter, "Math"));
+}
+
+#[test]
+fn capabilities_are_granted_one_by_one() {
+    let output = OutputBuffer::default();
+    let mut interpreter = InterpreterBuilder::new()
+        .sandbox(Sandbox::pure().stdout(true))
+        .stdout(output.clone())
+        .stdin(Cursor::new("ignored"))
+        .build();
+
+    interpreter.eval("print(Math.max(1, 2));").unwrap();
+
+    assert_eq!(output.contents(), "2\n");
+    assert!(!defines(&interpreter, "readLine"));
+
+    let diagnostics = interpreter.eval("readLine();").unwrap_err();
+    assert_eq!(diagnostics.iter().next().unwrap().code, "E0403");
+}
+
+#[test]
+fn host_natives_can_be_denied() {
+    let class = ForeignClassBuilder::<()>::new("Empty").build();
+    let builder = || {
+        InterpreterBuilder::new()
+            .native(NativeBuilder::new("answer"), |_, _, _| {
+                Ok(Value::Number(42.0))
+            })
+            .foreign_class(&class)
+    };
+
+    let allowed = builder().build();
+    let mut denied = builder()
+        .sandbox(Sandbox::all().host_natives(false))
+        .build();
+    denied.define_foreign_class(&class);
+
+    assert!(defines(&allowed, "answer"));
+    assert!(defines(&allowed, "Empty"));
+    assert!(!defines(&denied, "answer"));
+    assert!(!defines(&denied, "Empty"));
+    assert!(defines(&denied, "print"));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn nested_blocks_resolve_the_innermost_binding() {
+    let source = r"
+        var a = 1;
+        {
+            var a = 2;
+            {
+                var a = 3;
+                print(a);
+            }
+            print(a);
+        }
+        print(a);
+    ";
+
+    assert_eq!(output(source), "3\n2\n1\n");
+}
+
+#[test]
+fn closures_capture_variables_of_enclosing_blocks() {
+    let source = r"
+        fun counter() {
+            var count = 0;
+            {
+                var step = 2;
+                fun increment() {
+                    count = count + step;
+                    return count;
+                }
+                return increment;
+            }
+        }
+        var first = counter();
+        var second = counter();
+        first();
+        print(first());
+        print(second());
+    ";
+
+    assert_eq!(output(source), "4\n2\n");
+}
+
+#[test]
+fn closures_see_later_assignments() {
+    let source = r"
+        {
+            var value = 1;
+            fun show() { print(value); }
+            value = 2;
+            show();
+        }
+    ";
+
+    assert_eq!(output(source), "2\n");
+}
+
+#[test]
+fn closures_keep_resolving_globals_by_name() {
+    let source = r"
+        fun show() { print(later); }
+        var later = 1;
+        show();
+        later = 2;
+        show();
+    ";
+
+    assert_eq!(output(source), "1\n2\n");
+}
+
+#[test]
+fn loop_bodies_get_a_scope_per_iteration() {
+    let source = r"
+        var closures = nil;
+        for (var i = 0; i < 3; i = i + 1) {
+            var copy = i;
+            fun show() { print(copy); }
+            if (i == 1) closures = show;
+        }
+        closures();
+    ";
+
+    assert_eq!(output(source), "1\n");
+}
+
+#[test]
+fn parameters_and_locals_share_the_function_scope() {
+    let source = r"
+        fun f(a, b) {
+            var c = a + b;
+            {
+                var d = c * 2;
+                b = d;
+            }
+            return a + b + c;
+        }
+        print(f(1, 2));
+    ";
+
+    assert_eq!(output(source), "10\n");
+}
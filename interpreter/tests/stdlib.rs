@@ -0,0 +1,45 @@
+mod common;
+
+use common::error;
+use interpreter::{Interpreter, Value};
+
+fn eval(source: &str) -> Value {
+    Interpreter::new().eval(source).expect("program should run")
+}
+
+#[test]
+fn math_functions() {
+    assert_eq!(eval("Math.floor(2.7);"), Value::Number(2.0));
+    assert_eq!(eval("Math.ceil(2.1);"), Value::Number(3.0));
+    assert_eq!(eval("Math.round(2.5);"), Value::Number(3.0));
+    assert_eq!(eval("Math.abs(0 - 4);"), Value::Number(4.0));
+    assert_eq!(eval("Math.sqrt(16);"), Value::Number(4.0));
+    assert_eq!(eval("Math.pow(2, 10);"), Value::Number(1024.0));
+    assert_eq!(eval("Math.min(3, 1);"), Value::Number(1.0));
+    assert_eq!(eval("Math.max(3, 1);"), Value::Number(3.0));
+    assert_eq!(eval("Math.log(1);"), Value::Number(0.0));
+}
+
+#[test]
+fn math_constants() {
+    assert_eq!(eval("Math.PI;"), Value::Number(std::f64::consts::PI));
+    assert_eq!(eval("Math.E;"), Value::Number(std::f64::consts::E));
+}
+
+#[test]
+fn number_formatting() {
+    assert_eq!(eval("(3.14159).toFixed(2);"), Value::String("3.14".into()));
+    assert_eq!(eval("(255).toString(16);"), Value::String("ff".into()));
+    assert_eq!(eval("(0 - 10).toString(2);"), Value::String("-1010".into()));
+    assert_eq!(eval("(0.5).toString(2);"), Value::String("0.1".into()));
+    assert_eq!(eval("(0 / 0).isNaN();"), Value::Boolean(true));
+    assert_eq!(eval("(1).isNaN();"), Value::Boolean(false));
+}
+
+#[test]
+fn invalid_arguments() {
+    assert_eq!(error(r#"Math.sqrt("four");"#).code, "E0401");
+    assert_eq!(error("(1).toString(37);").code, "E0413");
+    assert_eq!(error("(1).toFixed(1.5);").code, "E0413");
+    assert_eq!(error("(1).toFixed(0 - 1);").code, "E0413");
+}
@@ -0,0 +1,59 @@
+use std::io::Cursor;
+
+use interpreter::{Interpreter, InterpreterBuilder, OutputBuffer, Value};
+
+#[test]
+fn print_writes_to_the_configured_stdout() {
+    let output = OutputBuffer::default();
+    let mut interpreter = InterpreterBuilder::new().stdout(output.clone()).build();
+
+    interpreter
+        .eval(r#"print("Hello"); print(1 + 2);"#)
+        .unwrap();
+
+    assert_eq!(output.contents(), "Hello\n3\n");
+
+    output.clear();
+    interpreter.eval("print(nil);").unwrap();
+
+    assert_eq!(output.contents(), "nil\n");
+}
+
+#[test]
+fn read_line_reads_from_the_configured_stdin() {
+    let mut interpreter = InterpreterBuilder::new()
+        .stdin(Cursor::new("first\r\nsecond\n"))
+        .build();
+
+    assert_eq!(
+        interpreter.eval("readLine() + readLine();"),
+        Ok(Value::String("firstsecond".into()))
+    );
+    assert_eq!(
+        interpreter.eval("readLine();"),
+        Ok(Value::String("".into()))
+    );
+}
+
+#[test]
+fn errors_are_reported_to_the_configured_stderr() {
+    let errors = OutputBuffer::default();
+    let mut interpreter = InterpreterBuilder::new().stderr(errors.clone()).build();
+
+    let source = "print(missing);";
+    let diagnostics = interpreter.eval(source).unwrap_err();
+    interpreter.report(source, &diagnostics);
+
+    assert!(errors
+        .contents()
+        .contains("Undeclared variable \"missing\""));
+}
+
+#[test]
+fn interpreters_share_the_default_stdin() {
+    let mut first = Interpreter::new();
+    let mut second = Interpreter::new();
+
+    assert_eq!(first.eval("1;"), Ok(Value::Number(1.0)));
+    assert_eq!(second.eval("2;"), Ok(Value::Number(2.0)));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::error;
+use interpreter::Interpreter;
+
+#[test]
+fn misspelled_globals_are_suggested() {
+    let error = error("var counter = 1; counter + countr;");
+
+    assert_eq!(error.code, "E0403");
+    assert_eq!(error.help, [r#"Did you mean "counter"?"#]);
+}
+
+#[test]
+fn misspelled_locals_are_suggested() {
+    let error = error("{ var first = 1; first + frist; }");
+
+    assert_eq!(error.help, [r#"Did you mean "first"?"#]);
+}
+
+#[test]
+fn inherited_methods_and_fields_are_suggested() {
+    let source = r"
+        class Shape { area() { return 0; } }
+        class Square < Shape {}
+        var square = Square();
+        square.width = 1;
+    ";
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(source).unwrap();
+
+    let method = interpreter.eval("square.aera;").unwrap_err();
+    let field = interpreter.eval("square.widht;").unwrap_err();
+
+    let method = method.iter().next().unwrap();
+    assert_eq!(method.code, "E0411");
+    assert_eq!(method.help, [r#"Did you mean "area"?"#]);
+    assert_eq!(
+        field.iter().next().unwrap().help,
+        [r#"Did you mean "width"?"#]
+    );
+}
+
+#[test]
+fn unrelated_names_are_not_suggested() {
+    let error = error("var apple = 1; apple + zebra;");
+
+    assert_eq!(error.code, "E0403");
+    assert!(error.help.is_empty());
+}
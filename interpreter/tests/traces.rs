@@ -0,0 +1,77 @@
+mod common;
+
+use common::error;
+use interpreter::Interpreter;
+use lox_core::Frame;
+
+/// The names of the functions of `trace` and the code calling them
+fn calls<'a>(source: &'a str, trace: &[Frame]) -> Vec<(String, &'a str)> {
+    trace
+        .iter()
+        .map(|frame| {
+            let name = frame.function.clone().unwrap_or_default();
+
+            (name, &source[frame.call_site.start..frame.call_site.end])
+        })
+        .collect()
+}
+
+#[test]
+fn errors_at_the_top_level_have_no_trace() {
+    assert!(error("nil + 1;").trace.is_empty());
+}
+
+#[test]
+fn nested_calls_are_traced_innermost_first() {
+    let source = r"
+        fun inner() { return nil + 1; }
+        fun outer() { return inner(); }
+        class Runner { run() { return outer(); } }
+        Runner().run();
+    ";
+    let error = error(source);
+
+    assert_eq!(error.code, "E0401");
+    assert_eq!(
+        calls(source, &error.trace),
+        [
+            ("inner".into(), "inner()"),
+            ("outer".into(), "outer()"),
+            ("run".into(), "Runner().run()"),
+        ]
+    );
+}
+
+#[test]
+fn recursive_calls_are_all_traced() {
+    let source = r"
+        fun countdown(n) {
+            if (n == 0) return nil + 1;
+            return countdown(n - 1);
+        }
+        countdown(3);
+    ";
+    let error = error(source);
+
+    assert_eq!(error.trace.len(), 4);
+    assert!(error
+        .trace
+        .iter()
+        .all(|frame| frame.function.as_deref() == Some("countdown")));
+    assert_eq!(
+        &source[error.trace[3].call_site.start..error.trace[3].call_site.end],
+        "countdown(3)"
+    );
+}
+
+#[test]
+fn calls_from_the_host_are_traced() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("fun fail() { return nil + 1; }").unwrap();
+
+    let diagnostics = interpreter.call_function("fail", &[]).unwrap_err();
+    let error = diagnostics.iter().next().unwrap();
+
+    assert_eq!(error.trace.len(), 1);
+    assert_eq!(error.trace[0].function.as_deref(), Some("fail"));
+}
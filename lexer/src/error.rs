@@ -1,3 +1,4 @@
+use lox_core::Diagnose;
 use thiserror::Error as ThisError;
 
 #[derive(Debug, ThisError)]
@@ -8,3 +9,12 @@ pub enum LexerError {
     #[error(r#"Unexpected character "{0}""#)]
     UnexpectedCharacter(char),
 }
+
+impl Diagnose for LexerError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::UnterminatedString => "E0101",
+            Self::UnexpectedCharacter(_) => "E0102",
+        }
+    }
+}
@@ -1,18 +1,19 @@
-use std::{iter::Peekable, str::Bytes};
+use std::{collections::HashMap, iter::Peekable, rc::Rc, str::Bytes};
 
 use crate::{LexerError, Token, TokenKind};
-use lox_core::{report, Error, Result};
+use lox_core::{Diagnostics, Error, Result, Span};
 
 #[derive(Debug)]
 pub struct Lexer<'a> {
     source: &'a str,
     bytes: Peekable<Bytes<'a>>,
 
-    line: usize,
-    column: usize,
-
     current: usize,
     lexeme_start: usize,
+
+    /// Equal string literals of the source share one allocation, so the
+    /// backends can often compare them by address instead of by text
+    literals: HashMap<&'a str, Rc<str>>,
 }
 
 impl<'a> Lexer<'a> {
@@ -21,40 +22,35 @@ impl<'a> Lexer<'a> {
         Self {
             source,
             bytes: source.bytes().peekable(),
-            line: 0,
-            column: 0,
             current: 0,
             lexeme_start: 0,
+            literals: HashMap::new(),
         }
     }
 
+    /// Scans the source code. Unexpected characters and unterminated
+    /// strings are skipped, so every error in the source is found
     #[must_use]
-    pub fn scan(mut self) -> Vec<Token> {
+    pub fn scan(mut self) -> (Vec<Token>, Diagnostics) {
         let mut output = vec![];
-        let mut has_error = false;
+        let mut diagnostics = Diagnostics::new();
 
         while self.peek().is_some() {
             self.lexeme_start = self.current;
 
-            output.push(match self.scan_token() {
-                Ok(Some(token)) if !has_error => token,
-                Ok(_) => continue,
-                Err(err) => {
-                    report(self.source, &err);
-                    output.clear();
-                    has_error = true;
-                    continue;
-                }
-            });
+            match self.scan_token() {
+                Ok(Some(token)) => output.push(token),
+                Ok(None) => (),
+                Err(err) => diagnostics.push(err),
+            }
         }
 
         output.push(Token {
-            line: self.line,
-            column: self.column,
+            span: Span::at(self.current),
             kind: TokenKind::Eof,
         });
 
-        output
+        (output, diagnostics)
     }
 
     fn scan_token(&mut self) -> Result<Option<Token>, LexerError> {
@@ -63,8 +59,7 @@ impl<'a> Lexer<'a> {
         Ok(Some(match character {
             token @ (b'(' | b')' | b'[' | b']' | b'{' | b'}' | b';' | b',' | b'.' | b'-' | b'+'
             | b'?' | b':' | b'*') => Token {
-                line: self.line,
-                column: self.column - 1,
+                span: self.lexeme_span(),
                 kind: match token {
                     b'(' => TokenKind::LeftParen,
                     b')' => TokenKind::RightParen,
@@ -87,8 +82,7 @@ impl<'a> Lexer<'a> {
                 let is_followed_by_equal = self.match_next(b'=');
 
                 Token {
-                    line: self.line,
-                    column: self.column - 1,
+                    span: self.lexeme_span(),
                     kind: match character {
                         b'<' if is_followed_by_equal => TokenKind::LessEqual,
                         b'<' => TokenKind::LessThan,
@@ -114,24 +108,17 @@ impl<'a> Lexer<'a> {
                 }
 
                 Token {
-                    line: self.line,
-                    column: self.column - 1,
+                    span: self.lexeme_span(),
                     kind: TokenKind::Slash,
                 }
             }
             b'"' => self.scan_string_literal()?,
             b'0'..=b'9' => self.scan_number_literal(),
             b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.scan_identifier(),
-            b' ' | b'\t' | b'\r' => return Ok(None),
-            b'\n' => {
-                self.line += 1;
-                self.column = 0;
-                return Ok(None);
-            }
+            b' ' | b'\t' | b'\r' | b'\n' => return Ok(None),
             x => {
                 return Err(Error {
-                    line: self.line,
-                    column: self.column - 1,
+                    span: self.lexeme_span(),
                     source: LexerError::UnexpectedCharacter(x.into()),
                 });
             }
@@ -142,12 +129,6 @@ impl<'a> Lexer<'a> {
         while self.peek().is_some_and(|x| x != b'\n') {
             self.next();
         }
-
-        // Only increase line count if not at EOF
-        if self.peek().is_some() {
-            self.column = 0;
-            self.line += 1;
-        }
     }
 
     fn scan_block_comment(&mut self) {
@@ -161,10 +142,7 @@ impl<'a> Lexer<'a> {
 
             match self.check_block_comment_boundary() {
                 0 => {
-                    if self.next() == b'\n' {
-                        self.column = 0;
-                        self.line += 1;
-                    }
+                    self.next();
                 }
                 depth_change => {
                     self.next();
@@ -185,27 +163,15 @@ impl<'a> Lexer<'a> {
     }
 
     fn scan_string_literal(&mut self) -> Result<Token, LexerError> {
-        let line = self.line;
-        let column = self.column - 1;
-
-        while let Some(c) = self.peek() {
-            if c == b'"' {
-                break;
-            }
-
-            if c == b'\n' {
-                self.line += 1;
-                self.column = 0;
-            }
-
+        while self.peek().is_some_and(|c| c != b'"') {
             self.next();
         }
 
         // Hit EOF without terminating string
         if self.peek().is_none() {
             return Err(Error {
-                line,
-                column,
+                // Point at the opening double quotes
+                span: Span::new(self.lexeme_start, self.lexeme_start + 1),
                 source: LexerError::UnterminatedString,
             });
         }
@@ -213,18 +179,20 @@ impl<'a> Lexer<'a> {
         // Consume the closing double quotes
         self.next();
 
-        let value = &self.source[self.lexeme_start + 1..self.current - 1];
+        let literal = &self.source[self.lexeme_start + 1..self.current - 1];
+        let value = self
+            .literals
+            .entry(literal)
+            .or_insert_with(|| literal.into());
+        let kind = TokenKind::String(Rc::clone(value));
+
         Ok(Token {
-            line,
-            column,
-            kind: TokenKind::String(value.into()),
+            span: self.lexeme_span(),
+            kind,
         })
     }
 
     fn scan_number_literal(&mut self) -> Token {
-        let line = self.line;
-        let column = self.column - 1;
-
         while let Some(b'0'..=b'9' | b'_') = self.peek() {
             self.next();
         }
@@ -241,8 +209,7 @@ impl<'a> Lexer<'a> {
         }
 
         Token {
-            line,
-            column,
+            span: self.lexeme_span(),
             kind: TokenKind::Number {
                 value: self.source[self.lexeme_start..self.current]
                     .replace('_', "")
@@ -254,9 +221,6 @@ impl<'a> Lexer<'a> {
     }
 
     fn scan_identifier(&mut self) -> Token {
-        let line = self.line;
-        let column = self.column - 1;
-
         while let Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_') = self.peek() {
             self.next();
         }
@@ -264,8 +228,7 @@ impl<'a> Lexer<'a> {
         let text = &self.source[self.lexeme_start..self.current];
 
         Token {
-            line,
-            column,
+            span: self.lexeme_span(),
             kind: match text {
                 "if" => TokenKind::If,
                 "else" => TokenKind::Else,
@@ -305,11 +268,15 @@ impl<'a> Lexer<'a> {
     fn next(&mut self) -> u8 {
         let c = self.bytes.next();
         self.current += 1;
-        self.column += 1;
 
         c.expect("Unexpected EOF")
     }
 
+    /// The span of the lexeme being scanned
+    const fn lexeme_span(&self) -> Span {
+        Span::new(self.lexeme_start, self.current)
+    }
+
     fn peek(&mut self) -> Option<u8> {
         self.bytes.peek().copied()
     }
@@ -1,8 +1,8 @@
+use lox_core::{Span, Symbol};
 use std::rc::Rc;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
     pub kind: TokenKind,
 }
 
@@ -16,16 +16,15 @@ impl std::ops::Deref for Token {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
-    Identifier(Rc<str>),
+    Identifier(Symbol),
     String(Rc<str>),
     Number {
         /// The actual floating point value
         value: f64,
 
         /// The number lexeme as written in the code,
-        /// used to know precisely the length of the
-        /// lexeme, as it is possible it doesn't match
-        /// `value.to_string().len()`
+        /// as it is possible it doesn't match
+        /// `value.to_string()`
         lexeme: Rc<str>,
     },
 
@@ -81,53 +80,6 @@ pub enum TokenKind {
     Eof,
 }
 
-impl TokenKind {
-    #[must_use]
-    pub const fn is_empty(&self) -> bool {
-        matches!(self, Self::Eof)
-    }
-
-    #[must_use]
-    pub fn len(&self) -> usize {
-        match self {
-            Self::Identifier(ref x) => x.len(),
-            Self::String(ref x) => x.len() + 2,
-            Self::Number { ref lexeme, .. } => lexeme.len(),
-            Self::LeftParen
-            | Self::RightParen
-            | Self::LeftBracket
-            | Self::RightBracket
-            | Self::LeftCurly
-            | Self::RightCurly
-            | Self::Comma
-            | Self::Dot
-            | Self::Semicolon
-            | Self::QuestionMark
-            | Self::Colon
-            | Self::Plus
-            | Self::Minus
-            | Self::Slash
-            | Self::Star
-            | Self::Bang
-            | Self::GreaterThan
-            | Self::LessThan
-            | Self::Equals => 1,
-            Self::BangEqual
-            | Self::GreaterEqual
-            | Self::LessEqual
-            | Self::DoubleEquals
-            | Self::If
-            | Self::Or => 2,
-            Self::And | Self::Fun | Self::Nil | Self::Var | Self::For => 3,
-            Self::True | Self::This | Self::Else => 4,
-            Self::Break | Self::False | Self::While | Self::Class | Self::Super => 5,
-            Self::Return => 6,
-            Self::Continue => 8,
-            Self::Eof => 0,
-        }
-    }
-}
-
 impl std::fmt::Display for TokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
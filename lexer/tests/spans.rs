@@ -0,0 +1,35 @@
+use lexer::{Lexer, TokenKind};
+use lox_core::{SourceMap, Span};
+
+#[test]
+fn tokens_span_their_bytes() {
+    let source = "var cafe = \"é\";\n// cömment\nprint(cafe);";
+    let (tokens, diagnostics) = Lexer::new(source).scan();
+
+    assert!(diagnostics.is_empty());
+
+    let spans: Vec<_> = tokens
+        .iter()
+        .map(|token| &source[token.span.start..token.span.end])
+        .collect();
+
+    assert_eq!(
+        spans,
+        ["var", "cafe", "=", "\"é\"", ";", "print", "(", "cafe", ")", ";", ""]
+    );
+    assert_eq!(tokens.last().unwrap().kind, TokenKind::Eof);
+    assert_eq!(tokens.last().unwrap().span, Span::at(source.len()));
+}
+
+#[test]
+fn source_maps_locate_offsets() {
+    let source = "var a;\nprint(a);\n";
+    let map = SourceMap::new(source);
+
+    let print = source.find("print").unwrap();
+    let location = map.location(print + 6);
+
+    assert_eq!((location.line, location.column), (1, 6));
+    assert_eq!(map.line_start(1), Some(print));
+    assert_eq!(map.line_start(3), None);
+}
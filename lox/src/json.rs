@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use lox_core::{Diagnostic, Label, SourceMap};
+use serde_json::{json, Value};
+
+/// Converts a diagnostic to a JSON object. Lines and columns are one-based,
+/// while offsets are zero-based byte offsets into the source
+pub fn diagnostic(file: Option<&Path>, source_map: &SourceMap, diagnostic: &Diagnostic) -> Value {
+    let mut object = label(source_map, &diagnostic.primary);
+
+    object["file"] = json!(file.map(|file| file.display().to_string()));
+    object["code"] = json!(diagnostic.code);
+    object["message"] = json!(diagnostic.message);
+    object["severity"] = json!(diagnostic.severity.to_string());
+    object["secondary"] = diagnostic
+        .secondary
+        .iter()
+        .map(|secondary| label(source_map, secondary))
+        .collect();
+    object["notes"] = json!(diagnostic.notes);
+    object["help"] = json!(diagnostic.help);
+    object["trace"] = diagnostic
+        .trace
+        .iter()
+        .map(|frame| {
+            json!({
+                "function": frame.function,
+                "start": position(source_map, frame.call_site.start),
+                "end": position(source_map, frame.call_site.end),
+            })
+        })
+        .collect();
+
+    object
+}
+
+fn label(source_map: &SourceMap, label: &Label) -> Value {
+    json!({
+        "start": position(source_map, label.span.start),
+        "end": position(source_map, label.span.end),
+        "label": label.message,
+    })
+}
+
+fn position(source_map: &SourceMap, offset: usize) -> Value {
+    let location = source_map.location(offset);
+
+    json!({
+        "line": location.line + 1,
+        "column": location.column + 1,
+        "offset": offset,
+    })
+}
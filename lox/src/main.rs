@@ -1,72 +1,298 @@
-use clap::Parser as Clap;
-use color_eyre::Result;
-use std::{io::Write, path::Path};
+mod json;
 
-use interpreter::Interpreter;
+use clap::{Parser as Clap, ValueEnum};
+use color_eyre::{eyre::bail, Result};
+use std::{
+    io::{BufRead, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use interpreter::{Interpreter, InterpreterBuilder, Sandbox, DEFAULT_MAX_CALL_DEPTH};
 use lexer::Lexer;
-use parser::Parser;
-use resolver::Resolver;
+use lox_core::{Diagnostics, SourceMap};
+use optimizer::{OptimizationLevel, Optimizer};
+use parser::{Parser, Statement};
+use resolver::{Locals, Resolver};
+use vm::{Vm, VmBuilder};
 
 #[derive(Clap)]
 struct Args {
     #[arg(short, long)]
     pub source: Option<Box<Path>>,
+
+    /// How errors are printed to stderr
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    pub error_format: ErrorFormat,
+
+    /// What runs the program
+    #[arg(long, value_enum, default_value_t = Backend::Interpreter)]
+    pub backend: Backend,
+
+    /// How deep calls can be nested before a stack overflow error
+    #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+    pub max_call_depth: usize,
+
+    /// How many expressions can be evaluated before the program is stopped.
+    /// The vm and jit backends count calls and loop iterations instead
+    #[arg(long)]
+    pub fuel: Option<u64>,
+
+    /// How many seconds the program can run before it is stopped
+    #[arg(long)]
+    pub timeout: Option<f64>,
+
+    /// How many bytes the program can use before it is stopped. Only
+    /// supported by the interpreter backend
+    #[arg(long)]
+    pub memory_limit: Option<usize>,
+
+    /// Which natives programs have access to
+    #[arg(long, value_enum, default_value_t = SandboxPreset::All)]
+    pub sandbox: SandboxPreset,
+
+    /// How much the program is optimized before it runs
+    #[arg(short = 'O', value_enum, default_value_t = OptimizationPreset::None)]
+    pub optimization: OptimizationPreset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ErrorFormat {
+    /// Coloured text along with the source code
+    Human,
+
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Backend {
+    /// Walks the syntax tree
+    Interpreter,
+
+    /// Compiles to bytecode run by a virtual machine
+    Vm,
+
+    /// Like the virtual machine, also compiling functions that are called
+    /// often and only do arithmetic to machine code
+    Jit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SandboxPreset {
+    /// Every native, including those doing I/O
+    All,
+
+    /// Only natives without side effects
+    Pure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OptimizationPreset {
+    /// No optimization
+    #[value(name = "0")]
+    None,
+
+    /// Computes operations on literals ahead of time
+    #[value(name = "1")]
+    Fold,
+
+    /// Also removes branches and loops that can never run
+    #[value(name = "2")]
+    Full,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    let sandbox = match args.sandbox {
+        SandboxPreset::All => Sandbox::all(),
+        SandboxPreset::Pure => Sandbox::pure(),
+    };
+
+    let optimizer = Optimizer::new(match args.optimization {
+        OptimizationPreset::None => OptimizationLevel::None,
+        OptimizationPreset::Fold => OptimizationLevel::Fold,
+        OptimizationPreset::Full => OptimizationLevel::Full,
+    });
+
+    let mut engine = match args.backend {
+        Backend::Interpreter => {
+            let mut builder = InterpreterBuilder::new()
+                .max_call_depth(args.max_call_depth)
+                .sandbox(sandbox);
+
+            if let Some(fuel) = args.fuel {
+                builder = builder.fuel(fuel);
+            }
+
+            if let Some(timeout) = args.timeout {
+                builder = builder.deadline(Instant::now() + Duration::from_secs_f64(timeout));
+            }
+
+            if let Some(memory_limit) = args.memory_limit {
+                builder = builder.memory_limit(memory_limit);
+            }
+
+            Engine::Interpreter(builder.build())
+        }
+        Backend::Vm | Backend::Jit => {
+            if args.memory_limit.is_some() {
+                bail!("--memory-limit is only supported by the interpreter backend");
+            }
+
+            let mut builder = VmBuilder::new()
+                .max_call_depth(args.max_call_depth)
+                .sandbox(sandbox)
+                .jit(args.backend == Backend::Jit);
+
+            if let Some(fuel) = args.fuel {
+                builder = builder.fuel(fuel);
+            }
+
+            if let Some(timeout) = args.timeout {
+                builder = builder.deadline(Instant::now() + Duration::from_secs_f64(timeout));
+            }
+
+            Engine::Vm(builder.build())
+        }
+    };
+
     match args.source {
-        Some(ref path) => run_file(path)?,
-        None => run_prompt()?,
+        Some(ref path) => run_file(&mut engine, optimizer, path, args.error_format)?,
+        None => run_prompt(&mut engine, optimizer, args.error_format)?,
     };
 
     Ok(())
 }
 
-fn run_file(path: &Path) -> Result<()> {
-    let source = std::fs::read_to_string(path)?;
+/// The backend running programs
+enum Engine {
+    Interpreter(Interpreter),
+    Vm(Vm),
+}
 
-    let mut interpreter = Interpreter::new();
+impl Engine {
+    fn resolve_locals(&mut self, locals: Locals) {
+        // The compiler resolves variables itself
+        if let Self::Interpreter(interpreter) = self {
+            interpreter.resolve_locals(locals);
+        }
+    }
 
-    run(&mut interpreter, &source)?;
-    Ok(())
+    fn interpret(&mut self, program: &[Statement]) -> Diagnostics {
+        match self {
+            Self::Interpreter(interpreter) => interpreter.interpret(program),
+            Self::Vm(vm) => vm.interpret(program),
+        }
+    }
+
+    fn report(&mut self, source: &str, diagnostics: &Diagnostics) {
+        match self {
+            Self::Interpreter(interpreter) => interpreter.report(source, diagnostics),
+            Self::Vm(vm) => vm.report(source, diagnostics),
+        }
+    }
+
+    fn stderr(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Interpreter(interpreter) => interpreter.stderr(),
+            Self::Vm(vm) => vm.stderr(),
+        }
+    }
+
+    fn stdin(&mut self) -> &mut dyn BufRead {
+        match self {
+            Self::Interpreter(interpreter) => interpreter.stdin(),
+            Self::Vm(vm) => vm.stdin(),
+        }
+    }
 }
 
-fn run_prompt() -> Result<()> {
-    let mut interpreter = Interpreter::new();
+fn run_file(
+    engine: &mut Engine,
+    optimizer: Optimizer,
+    path: &Path,
+    format: ErrorFormat,
+) -> Result<()> {
+    let source = std::fs::read_to_string(path)?;
 
+    run(engine, optimizer, &source, Some(path), format)?;
+    Ok(())
+}
+
+fn run_prompt(engine: &mut Engine, optimizer: Optimizer, format: ErrorFormat) -> Result<()> {
     let mut stdout = std::io::stdout();
-    let stdin = std::io::stdin();
     let mut buffer = String::new();
 
     loop {
         _ = stdout.write_all(b"> ");
         _ = stdout.flush();
         buffer.clear();
-        stdin.read_line(&mut buffer)?;
+        // The engine holds the lock on stdin, and shares it with `readLine`
+        engine.stdin().read_line(&mut buffer)?;
 
         if buffer.trim().is_empty() {
             return Ok(());
         }
 
-        _ = run(&mut interpreter, &buffer);
+        _ = run(engine, optimizer, &buffer, None, format);
     }
 }
 
-fn run(interpreter: &mut Interpreter, source: &str) -> Result<()> {
+fn run(
+    engine: &mut Engine,
+    optimizer: Optimizer,
+    source: &str,
+    file: Option<&Path>,
+    format: ErrorFormat,
+) -> Result<()> {
     let lexer = Lexer::new(source);
-    let tokens = lexer.scan();
+    let (tokens, mut diagnostics) = lexer.scan();
+
+    let mut parser = Parser::new(&tokens);
+    let (ast, parser_diagnostics) = parser.parse();
+    diagnostics.extend(parser_diagnostics);
 
-    let mut parser = Parser::new(source, &tokens);
-    let ast = parser.parse();
+    // Resolving a partial program would only report spurious errors
+    if !diagnostics.has_errors() {
+        let mut resolver = Resolver::new();
+        diagnostics.extend(resolver.resolve(&ast));
 
-    let mut resolver = Resolver::new(source);
-    resolver.resolve(&ast);
+        if !diagnostics.has_errors() {
+            engine.resolve_locals(resolver.locals);
+        }
+    }
 
-    if !resolver.had_error {
-        interpreter.resolve_locals(resolver.locals);
-        interpreter.interpret(source, &ast);
+    report(engine, source, file, format, &diagnostics)?;
+
+    if !diagnostics.has_errors() {
+        let ast = optimizer.optimize(ast);
+        let diagnostics = engine.interpret(&ast);
+        report(engine, source, file, format, &diagnostics)?;
+    }
+
+    Ok(())
+}
+
+fn report(
+    engine: &mut Engine,
+    source: &str,
+    file: Option<&Path>,
+    format: ErrorFormat,
+    diagnostics: &Diagnostics,
+) -> Result<()> {
+    match format {
+        ErrorFormat::Human => engine.report(source, diagnostics),
+        ErrorFormat::Json => {
+            let source_map = SourceMap::new(source);
+            let stderr = engine.stderr();
+
+            for diagnostic in diagnostics {
+                let object = json::diagnostic(file, &source_map, diagnostic);
+                writeln!(stderr, "{object}")?;
+            }
+        }
     }
 
     Ok(())
@@ -0,0 +1,10 @@
+use std::path::PathBuf;
+
+/// Writes `source` to the file `name` in the tests' temporary directory,
+/// returning its path
+pub fn write(name: &str, source: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, source).unwrap();
+
+    path
+}
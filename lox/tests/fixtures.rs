@@ -0,0 +1,68 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Backends every fixture is run with, which must all behave the same
+const BACKENDS: [&str; 3] = ["interpreter", "vm", "jit"];
+
+/// Prefix of a first line listing extra arguments to run a fixture with
+const ARGS_PREFIX: &str = "// args:";
+
+/// Runs `fixture` with `backend`, returning what it wrote to stdout and
+/// stderr
+fn run(fixture: &Path, backend: &str) -> (String, String) {
+    let source = fs::read_to_string(fixture).unwrap();
+    let args = source
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(ARGS_PREFIX))
+        .unwrap_or_default();
+
+    // Diagnostics name the file as given, so it is run from its directory
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .current_dir(fixture.parent().unwrap())
+        .args(["--backend", backend, "--error-format", "json", "-s"])
+        .arg(fixture.file_name().unwrap())
+        .args(args.split_whitespace())
+        .output()
+        .unwrap();
+
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// The expected output of `fixture` with the given extension, empty if the
+/// file doesn't exist
+fn expected(fixture: &Path, extension: &str) -> String {
+    fs::read_to_string(fixture.with_extension(extension)).unwrap_or_default()
+}
+
+#[test]
+fn fixtures_match_their_expected_output() {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut fixtures: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+    fixtures.sort();
+
+    assert!(!fixtures.is_empty());
+
+    for fixture in &fixtures {
+        let stdout = expected(fixture, "out");
+        let stderr = expected(fixture, "err");
+
+        for backend in BACKENDS {
+            let (actual_stdout, actual_stderr) = run(fixture, backend);
+            let name = fixture.display();
+
+            assert_eq!(actual_stdout, stdout, "stdout of {name} with {backend}");
+            assert_eq!(actual_stderr, stderr, "stderr of {name} with {backend}");
+        }
+    }
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::process::Command;
+
+use common::write;
+use serde_json::Value;
+
+/// Runs `source` with JSON diagnostics, returning one object per line of
+/// stderr
+fn diagnostics(name: &str, source: &str) -> Vec<Value> {
+    let path = write(name, source);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(["--error-format", "json", "-s"])
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn syntax_errors_are_one_object_per_line() {
+    let diagnostics = diagnostics("syntax.lox", "var = ;\nprint(1 +);\n");
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["code"], "E0204");
+    assert_eq!(diagnostics[0]["severity"], "error");
+    assert_eq!(diagnostics[0]["message"], "Expected identifier");
+    assert_eq!(diagnostics[0]["start"]["line"], 1);
+    assert_eq!(diagnostics[0]["start"]["column"], 4);
+    assert_eq!(diagnostics[1]["code"], "E0201");
+    assert_eq!(diagnostics[1]["start"]["offset"], 17);
+    assert!(diagnostics[1]["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("syntax.lox"));
+}
+
+#[test]
+fn runtime_errors_include_their_trace() {
+    let source = "fun f() {\n  return missing;\n}\nf();\n";
+    let diagnostics = diagnostics("runtime.lox", source);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "E0403");
+    assert_eq!(diagnostics[0]["trace"][0]["function"], "f");
+    assert_eq!(diagnostics[0]["trace"][0]["start"]["line"], 4);
+}
+
+#[test]
+fn warnings_are_reported_with_their_severity() {
+    let diagnostics = diagnostics("warning.lox", "fun f() {\n  var unused = 1;\n}\n");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], "warning");
+}
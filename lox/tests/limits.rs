@@ -0,0 +1,60 @@
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::write;
+
+/// Backends that can bound how long a program runs
+const BACKENDS: [&str; 3] = ["interpreter", "vm", "jit"];
+
+/// Runs the file at `path` with `backend` and extra arguments, returning what
+/// it wrote to stderr
+fn run(path: &Path, backend: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(["--backend", backend, "--error-format", "json", "-s"])
+        .arg(path)
+        .args(args)
+        .output()
+        .unwrap();
+
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn fuel_stops_every_backend() {
+    let path = write("fuel.lox", "while (true) {}\n");
+
+    for backend in BACKENDS {
+        let stderr = run(&path, backend, &["--fuel", "1000"]);
+
+        assert!(stderr.contains(r#""code":"E0419""#), "{backend}: {stderr}");
+    }
+}
+
+#[test]
+fn timeouts_stop_every_backend() {
+    let path = write("timeout.lox", "while (true) {}\n");
+
+    for backend in BACKENDS {
+        let stderr = run(&path, backend, &["--timeout", "0.05"]);
+
+        assert!(stderr.contains(r#""code":"E0420""#), "{backend}: {stderr}");
+    }
+}
+
+#[test]
+fn memory_limits_are_only_supported_by_the_interpreter() {
+    let path = write("memory.lox", "var s = \"x\"; while (true) s = s + s;\n");
+
+    let stderr = run(&path, "interpreter", &["--memory-limit", "65536"]);
+    assert!(stderr.contains(r#""code":"E0422""#), "{stderr}");
+
+    for backend in ["vm", "jit"] {
+        let stderr = run(&path, backend, &["--memory-limit", "65536"]);
+
+        assert!(
+            stderr.contains("only supported by the interpreter"),
+            "{stderr}"
+        );
+    }
+}
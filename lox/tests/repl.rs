@@ -0,0 +1,44 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs the REPL of the given backend, feeding it `input`, and returns
+/// what it printed
+fn repl(backend: &str, input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(["--backend", backend])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn read_line_shares_stdin_with_the_prompt() {
+    let input = "print(readLine());\nhello\nprint(1 + 1);\n\n";
+
+    for backend in ["interpreter", "vm"] {
+        assert_eq!(repl(backend, input), "> hello\n> 2\n> ", "{backend}");
+    }
+}
+
+#[test]
+fn functions_outlive_the_line_declaring_them() {
+    let input = "fun f(n) { var a = n * 2; fun g() { return a + 1; } return g; }\n\
+                 var h = f(3);\n\
+                 print(h());\n\n";
+
+    assert_eq!(repl("interpreter", input), "> > > 7\n> ");
+}
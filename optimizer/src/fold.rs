@@ -0,0 +1,65 @@
+use parser::{BinaryOperatorKind, Literal, UnaryOperatorKind};
+
+/// The result of a binary operator applied to literals, as the interpreter
+/// would compute it. Returns `None` if the operation raises an error, which is
+/// left to be reported at runtime
+pub fn binary(left: &Literal, operator: BinaryOperatorKind, right: &Literal) -> Option<Literal> {
+    use BinaryOperatorKind as B;
+    use Literal as L;
+
+    Some(match (operator, left, right) {
+        (B::DoubleEquals, a, b) => L::Boolean(a == b),
+        (B::BangEqual, a, b) => L::Boolean(a != b),
+        (B::Plus, L::Number(a), L::Number(b)) => L::Number(a + b),
+        (B::Plus, a @ L::String(_), b) | (B::Plus, a, b @ L::String(_)) => {
+            L::String(format!("{a}{b}").into())
+        }
+        (B::Minus, L::Number(a), L::Number(b)) => L::Number(a - b),
+        (B::Star, L::Number(a), L::Number(b)) => L::Number(a * b),
+        (B::Slash, L::Number(a), L::Number(b)) => L::Number(a / b),
+        (B::GreaterThan | B::GreaterEqual | B::LessThan | B::LessEqual, a, b) => {
+            L::Boolean(compare(a, operator, b)?)
+        }
+        _ => return None,
+    })
+}
+
+fn compare(left: &Literal, operator: BinaryOperatorKind, right: &Literal) -> Option<bool> {
+    use BinaryOperatorKind as B;
+    use Literal as L;
+
+    Some(match (left, right) {
+        (L::String(a), L::String(b)) => match operator {
+            B::LessThan => a < b,
+            B::LessEqual => a <= b,
+            B::GreaterThan => a > b,
+            _ => a >= b,
+        },
+        (L::Number(a), L::Number(b)) => match operator {
+            B::LessThan => a < b,
+            B::LessEqual => a <= b,
+            B::GreaterThan => a > b,
+            _ => a >= b,
+        },
+        (L::Boolean(a), L::Boolean(b)) => match operator {
+            B::LessThan => !a && *b,
+            B::LessEqual => a <= b,
+            B::GreaterThan => *a && !b,
+            _ => a >= b,
+        },
+        // Mirrors the interpreter, for which `nil` is both less and
+        // greater than itself
+        (L::Nil, L::Nil) => matches!(operator, B::LessThan | B::GreaterThan),
+        _ => return None,
+    })
+}
+
+/// The result of a unary operator applied to a literal, `None` if it raises
+/// an error at runtime
+pub fn unary(operator: UnaryOperatorKind, operand: &Literal) -> Option<Literal> {
+    Some(match (operator, operand) {
+        (UnaryOperatorKind::Minus, Literal::Number(number)) => Literal::Number(-number),
+        (UnaryOperatorKind::Minus, _) => return None,
+        (UnaryOperatorKind::Bang, literal) => Literal::Boolean(!literal.is_truthy()),
+    })
+}
@@ -0,0 +1,7 @@
+#![deny(clippy::pedantic, clippy::nursery)]
+#![allow(clippy::module_name_repetitions)]
+
+mod fold;
+mod optimizer;
+
+pub use optimizer::{OptimizationLevel, Optimizer};
@@ -0,0 +1,332 @@
+use std::rc::Rc;
+
+use parser::{BinaryOperatorKind, Expression, Function, LogicalOperatorKind, Statement};
+
+use crate::fold;
+
+/// How much a program is transformed before it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptimizationLevel {
+    /// The program runs as written
+    #[default]
+    None,
+
+    /// Operations on literals are computed ahead of time and parentheses are
+    /// removed
+    Fold,
+
+    /// Branches and loops made unreachable by literal conditions are also
+    /// removed
+    Full,
+}
+
+/// Rewrites resolved programs into equivalent ones that do less work at
+/// runtime. Operations that would raise an error are kept as they are, so
+/// the error is still reported at runtime, at the same position
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Optimizer {
+    level: OptimizationLevel,
+}
+
+impl Optimizer {
+    #[must_use]
+    pub const fn new(level: OptimizationLevel) -> Self {
+        Self { level }
+    }
+
+    /// Returns the optimized program. The identifiers of the nodes that are
+    /// kept are preserved, so the variables resolved beforehand still apply
+    #[must_use]
+    pub fn optimize(self, program: Vec<Statement>) -> Vec<Statement> {
+        if self.level == OptimizationLevel::None {
+            return program;
+        }
+
+        self.statements(&program)
+    }
+
+    fn statements(self, statements: &[Statement]) -> Vec<Statement> {
+        statements
+            .iter()
+            .filter_map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    /// Optimizes a statement nested in another one, which can't be removed
+    fn nested_statement(self, statement: &Statement) -> Statement {
+        self.statement(statement)
+            .unwrap_or_else(|| Statement::Block(Box::new([])))
+    }
+
+    /// Returns `None` if the statement does nothing
+    fn statement(self, statement: &Statement) -> Option<Statement> {
+        let is_full = self.level == OptimizationLevel::Full;
+
+        Some(match statement {
+            Statement::Expression(expression) => Statement::Expression(self.expression(expression)),
+            Statement::Declaration {
+                span,
+                identifier,
+                initializer,
+            } => Statement::Declaration {
+                span: *span,
+                identifier: *identifier,
+                initializer: initializer
+                    .as_ref()
+                    .map(|initializer| self.expression(initializer)),
+            },
+            Statement::Block(statements) => {
+                Statement::Block(self.statements(statements).into_boxed_slice())
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expression(condition);
+
+                match condition {
+                    Expression::Literal { ref value, .. } if is_full => {
+                        if value.is_truthy() {
+                            return self.statement(then_branch);
+                        }
+
+                        return else_branch
+                            .as_ref()
+                            .and_then(|else_branch| self.statement(else_branch));
+                    }
+                    condition => Statement::If {
+                        condition,
+                        then_branch: self.nested_statement(then_branch).into(),
+                        else_branch: else_branch
+                            .as_ref()
+                            .map(|else_branch| self.nested_statement(else_branch).into()),
+                    },
+                }
+            }
+            Statement::While { condition, body } => {
+                let condition = self.expression(condition);
+
+                if is_full && is_falsey(&condition) {
+                    return None;
+                }
+
+                Statement::While {
+                    condition,
+                    body: self.nested_statement(body).into(),
+                }
+            }
+            Statement::For {
+                condition,
+                increment,
+                body,
+            } => {
+                let condition = self.expression(condition);
+
+                if is_full && is_falsey(&condition) {
+                    return None;
+                }
+
+                Statement::For {
+                    condition,
+                    increment: increment
+                        .as_ref()
+                        .map(|increment| self.expression(increment)),
+                    body: self.nested_statement(body).into(),
+                }
+            }
+            Statement::Break { span } => Statement::Break { span: *span },
+            Statement::Continue { span } => Statement::Continue { span: *span },
+            Statement::Function(function) => Statement::Function(self.function(function)),
+            Statement::Return { span, expression } => Statement::Return {
+                span: *span,
+                expression: expression
+                    .as_ref()
+                    .map(|expression| self.expression(expression)),
+            },
+            Statement::Class {
+                span,
+                identifier,
+                super_class,
+                methods,
+            } => Statement::Class {
+                span: *span,
+                identifier: *identifier,
+                super_class: super_class
+                    .as_ref()
+                    .map(|super_class| self.expression(super_class)),
+                methods: methods.iter().map(|method| self.function(method)).collect(),
+            },
+        })
+    }
+
+    fn function(self, function: &Function) -> Function {
+        Function {
+            span: function.span,
+            identifier: function.identifier,
+            parameters: Rc::clone(&function.parameters),
+            body: self.statements(&function.body).into(),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn expression(self, expression: &Expression) -> Expression {
+        let is_full = self.level == OptimizationLevel::Full;
+        let span = expression.span();
+
+        match expression {
+            Expression::Ternary {
+                condition,
+                truthy,
+                falsey,
+            } => match self.expression(condition) {
+                Expression::Literal { ref value, .. } if is_full => {
+                    if value.is_truthy() {
+                        self.expression(truthy)
+                    } else {
+                        self.expression(falsey)
+                    }
+                }
+                condition => Expression::Ternary {
+                    condition: condition.into(),
+                    truthy: self.expression(truthy).into(),
+                    falsey: self.expression(falsey).into(),
+                },
+            },
+            Expression::Binary {
+                left,
+                right,
+                operator,
+            } => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+
+                match (&left, &right) {
+                    // Evaluating a literal has no effect
+                    (Expression::Literal { .. }, _)
+                        if matches!(operator.kind, BinaryOperatorKind::Comma) =>
+                    {
+                        return right;
+                    }
+                    (
+                        Expression::Literal { value: a, .. },
+                        Expression::Literal { value: b, .. },
+                    ) => {
+                        if let Some(value) = fold::binary(a, operator.kind, b) {
+                            return Expression::Literal { span, value };
+                        }
+                    }
+                    _ => (),
+                }
+
+                Expression::Binary {
+                    left: left.into(),
+                    right: right.into(),
+                    operator: *operator,
+                }
+            }
+            Expression::Logical {
+                left,
+                right,
+                operator,
+            } => match self.expression(left) {
+                Expression::Literal { ref value, .. } if is_full => {
+                    let is_left = match operator.kind {
+                        LogicalOperatorKind::And => !value.is_truthy(),
+                        LogicalOperatorKind::Or => value.is_truthy(),
+                    };
+
+                    if is_left {
+                        Expression::Literal {
+                            span,
+                            value: value.clone(),
+                        }
+                    } else {
+                        self.expression(right)
+                    }
+                }
+                left => Expression::Logical {
+                    left: left.into(),
+                    right: self.expression(right).into(),
+                    operator: *operator,
+                },
+            },
+            Expression::Unary {
+                expression,
+                operator,
+            } => {
+                let expression = self.expression(expression);
+
+                if let Expression::Literal { ref value, .. } = expression {
+                    if let Some(value) = fold::unary(operator.kind, value) {
+                        return Expression::Literal { span, value };
+                    }
+                }
+
+                Expression::Unary {
+                    expression: expression.into(),
+                    operator: *operator,
+                }
+            }
+            Expression::GroupingExpression { expression, .. } => self.expression(expression),
+            Expression::Literal { span, value } => Expression::Literal {
+                span: *span,
+                value: value.clone(),
+            },
+            Expression::Variable(reference) => Expression::Variable(reference.clone()),
+            Expression::Assignment { reference, value } => Expression::Assignment {
+                reference: reference.clone(),
+                value: self.expression(value).into(),
+            },
+            Expression::AnonymousFunction {
+                span,
+                parameters,
+                body,
+            } => Expression::AnonymousFunction {
+                span: *span,
+                parameters: Rc::clone(parameters),
+                body: self.statements(body).into(),
+            },
+            Expression::Call { span, callee, args } => Expression::Call {
+                span: *span,
+                callee: self.expression(callee).into(),
+                args: args.iter().map(|arg| self.expression(arg)).collect(),
+            },
+            Expression::Get {
+                span,
+                object,
+                identifier,
+                id,
+            } => Expression::Get {
+                span: *span,
+                object: self.expression(object).into(),
+                identifier: *identifier,
+                id: *id,
+            },
+            Expression::Set {
+                span,
+                object,
+                identifier,
+                value,
+            } => Expression::Set {
+                span: *span,
+                object: self.expression(object).into(),
+                identifier: *identifier,
+                value: self.expression(value).into(),
+            },
+            Expression::This { span, id } => Expression::This {
+                span: *span,
+                id: *id,
+            },
+            Expression::Super { span, method, id } => Expression::Super {
+                span: *span,
+                method: *method,
+                id: *id,
+            },
+        }
+    }
+}
+
+const fn is_falsey(condition: &Expression) -> bool {
+    matches!(condition, Expression::Literal { value, .. } if !value.is_truthy())
+}
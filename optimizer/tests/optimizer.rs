@@ -0,0 +1,163 @@
+use interpreter::{InterpreterBuilder, OutputBuffer};
+use lexer::Lexer;
+use optimizer::{OptimizationLevel, Optimizer};
+use parser::{Expression, Literal, Parser, Statement};
+use resolver::Resolver;
+
+const LEVELS: [OptimizationLevel; 3] = [
+    OptimizationLevel::None,
+    OptimizationLevel::Fold,
+    OptimizationLevel::Full,
+];
+
+/// Parses `source` and optimizes it at the given level
+fn optimize(source: &str, level: OptimizationLevel) -> Vec<Statement> {
+    let (tokens, _) = Lexer::new(source).scan();
+    let (program, diagnostics) = Parser::new(&tokens).parse();
+    assert!(diagnostics.is_empty());
+
+    Optimizer::new(level).optimize(program)
+}
+
+/// Runs `source` optimized at the given level, returning what it printed and
+/// the codes and spans of the errors raised
+fn run(source: &str, level: OptimizationLevel) -> (String, Vec<(&'static str, usize, usize)>) {
+    let (tokens, _) = Lexer::new(source).scan();
+    let (program, _) = Parser::new(&tokens).parse();
+    let mut resolver = Resolver::new();
+    resolver.resolve(&program);
+
+    let output = OutputBuffer::default();
+    let mut interpreter = InterpreterBuilder::new().stdout(output.clone()).build();
+    interpreter.resolve_locals(resolver.locals);
+
+    let program = Optimizer::new(level).optimize(program);
+    let errors = interpreter
+        .interpret(&program)
+        .iter()
+        .map(|diagnostic| {
+            let span = diagnostic.primary.span;
+            (diagnostic.code, span.start, span.end)
+        })
+        .collect();
+
+    (output.contents(), errors)
+}
+
+#[test]
+fn nothing_changes_without_optimization() {
+    let program = optimize("1 + 2;", OptimizationLevel::None);
+
+    assert!(matches!(
+        program.as_slice(),
+        [Statement::Expression(Expression::Binary { .. })]
+    ));
+}
+
+#[test]
+fn operations_on_literals_are_folded() {
+    let program = optimize(
+        r#"(1 + 2) * 3 == 9; !nil; "a" + "b";"#,
+        OptimizationLevel::Fold,
+    );
+
+    assert!(matches!(
+        program.as_slice(),
+        [
+            Statement::Expression(Expression::Literal {
+                value: Literal::Boolean(true),
+                ..
+            }),
+            Statement::Expression(Expression::Literal {
+                value: Literal::Boolean(true),
+                ..
+            }),
+            Statement::Expression(Expression::Literal {
+                value: Literal::String(string),
+                ..
+            }),
+        ] if &**string == "ab"
+    ));
+}
+
+#[test]
+fn failing_operations_are_not_folded() {
+    let program = optimize(r#"1 - "a";"#, OptimizationLevel::Full);
+
+    assert!(matches!(
+        program.as_slice(),
+        [Statement::Expression(Expression::Binary { .. })]
+    ));
+}
+
+#[test]
+fn logical_operators_on_literals_are_folded_at_the_full_level() {
+    let source = "true and 1; nil or 2;";
+
+    assert!(matches!(
+        optimize(source, OptimizationLevel::Fold).as_slice(),
+        [
+            Statement::Expression(Expression::Logical { .. }),
+            Statement::Expression(Expression::Logical { .. }),
+        ]
+    ));
+    assert!(matches!(
+        optimize(source, OptimizationLevel::Full).as_slice(),
+        [
+            Statement::Expression(Expression::Literal {
+                value: Literal::Number(first),
+                ..
+            }),
+            Statement::Expression(Expression::Literal {
+                value: Literal::Number(second),
+                ..
+            }),
+        ] if *first == 1.0 && *second == 2.0
+    ));
+}
+
+#[test]
+fn dead_branches_are_removed_at_the_full_level() {
+    let source = "if (1 > 2) print(1); else print(2);\nwhile (false) print(3);";
+
+    assert_eq!(optimize(source, OptimizationLevel::Fold).len(), 2);
+    assert!(matches!(
+        optimize(source, OptimizationLevel::Full).as_slice(),
+        [Statement::Expression(Expression::Call { .. })]
+    ));
+}
+
+#[test]
+fn programs_behave_the_same_at_every_level() {
+    let sources = [
+        r#"
+        var a = 2 * 3 + 1;
+        if (a > 5 and true) print("big"); else print("small");
+        while (false) print("never");
+        for (var i = 0; i < 3 and !false; i = i + 1) print(i * (1 + 1));
+        print(true ? "yes" : "no");
+        print("con" + "cat" + a);
+        "#,
+        r"
+        fun f(x) {
+            if (false) return 0;
+            return x * (2 + 3);
+        }
+        print(f(4));
+        print(10 / (5 - 5));
+        ",
+        r#"
+        print("before");
+        var x = (1 + 2) * nil;
+        print("after");
+        "#,
+    ];
+
+    for source in sources {
+        let expected = run(source, OptimizationLevel::None);
+
+        for level in LEVELS {
+            assert_eq!(run(source, level), expected, "{level:?}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use lox_core::Diagnose;
 use thiserror::Error as ThisError;
 
 pub const MAX_NUMBER_OF_ARGUMENTS: usize = 255;
@@ -43,3 +44,23 @@ pub enum ParserError {
     #[error(r#"The "super" keyword must be followed by a dot"#)]
     ExpectedDotAfterSuper,
 }
+
+impl Diagnose for ParserError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::ExpectedExpression => "E0201",
+            Self::UnterminatedTernary => "E0202",
+            Self::ExpectedSemicolon => "E0203",
+            Self::ExpectedIdentifier => "E0204",
+            Self::ExpectedSemicolonOrInitializer => "E0205",
+            Self::InvalidAssignmentTarget => "E0206",
+            Self::ExpectedLeftCurly => "E0207",
+            Self::ExpectedRightCurly => "E0208",
+            Self::ExpectedLeftParen => "E0209",
+            Self::ExpectedRightParen => "E0210",
+            Self::ParameterLimitExceeded => "E0211",
+            Self::ArgumentLimitExceeded => "E0212",
+            Self::ExpectedDotAfterSuper => "E0213",
+        }
+    }
+}
@@ -1,73 +1,130 @@
-use crate::{BinaryOperator, Literal, LogicalOperator, Statement, UnaryOperator};
-use std::rc::Rc;
+use crate::{BinaryOperator, Literal, LogicalOperator, Parameter, Statement, UnaryOperator};
+use lox_core::{Span, Symbol};
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 #[derive(Debug)]
 pub enum Expression {
     Ternary {
-        condition: Box<Expression>,
-        truthy: Box<Expression>,
-        falsey: Box<Expression>,
+        condition: Box<Self>,
+        truthy: Box<Self>,
+        falsey: Box<Self>,
     },
     Binary {
-        left: Box<Expression>,
-        right: Box<Expression>,
+        left: Box<Self>,
+        right: Box<Self>,
         operator: BinaryOperator,
     },
     Logical {
-        left: Box<Expression>,
-        right: Box<Expression>,
+        left: Box<Self>,
+        right: Box<Self>,
         operator: LogicalOperator,
     },
     Unary {
-        expression: Box<Expression>,
+        expression: Box<Self>,
         operator: UnaryOperator,
     },
-    GroupingExpression(Box<Expression>),
-    Literal(Literal),
+    GroupingExpression {
+        span: Span,
+        expression: Box<Self>,
+    },
+    Literal {
+        span: Span,
+        value: Literal,
+    },
     Variable(Reference),
     Assignment {
         reference: Reference,
-        value: Box<Expression>,
+        value: Box<Self>,
     },
     AnonymousFunction {
-        parameters: Rc<[Rc<str>]>,
+        span: Span,
+        parameters: Rc<[Parameter]>,
         body: Rc<[Statement]>,
     },
     Call {
-        line: usize,
-        column: usize,
-        callee: Box<Expression>,
-        args: Box<[Expression]>,
+        /// From the callee to the closing parenthesis
+        span: Span,
+        callee: Box<Self>,
+        args: Box<[Self]>,
     },
     Get {
-        line: usize,
-        column: usize,
-        object: Box<Expression>,
-        identifier: Rc<str>,
+        /// The span of the property's identifier
+        span: Span,
+        object: Box<Self>,
+        identifier: Symbol,
+        id: NodeId,
     },
     Set {
-        line: usize,
-        column: usize,
-        object: Box<Expression>,
-        identifier: Rc<str>,
-        value: Box<Expression>,
+        /// The span of the property's identifier
+        span: Span,
+        object: Box<Self>,
+        identifier: Symbol,
+        value: Box<Self>,
     },
     This {
-        line: usize,
-        column: usize,
+        span: Span,
+        id: NodeId,
     },
     Super {
-        line: usize,
-        column: usize,
-        method: Rc<str>,
+        span: Span,
+        method: Symbol,
+        id: NodeId,
     },
 }
 
+impl Expression {
+    /// The span of the whole expression
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Ternary {
+                condition, falsey, ..
+            } => condition.span().to(falsey.span()),
+            Self::Binary { left, right, .. } | Self::Logical { left, right, .. } => {
+                left.span().to(right.span())
+            }
+            Self::Unary {
+                expression,
+                operator,
+            } => operator.span.to(expression.span()),
+            Self::GroupingExpression { span, .. }
+            | Self::Literal { span, .. }
+            | Self::AnonymousFunction { span, .. }
+            | Self::Call { span, .. }
+            | Self::This { span, .. }
+            | Self::Super { span, .. } => *span,
+            Self::Variable(reference) => reference.span,
+            Self::Assignment { reference, value } => reference.span.to(value.span()),
+            Self::Get { span, object, .. } => object.span().to(*span),
+            Self::Set { object, value, .. } => object.span().to(value.span()),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct Reference {
-    pub line: usize,
-    pub column: usize,
-    pub identifier: Rc<str>,
+    pub span: Span,
+    pub identifier: Symbol,
+    pub id: NodeId,
+}
+
+/// Identifies a node of the syntax tree that refers to a variable or a
+/// property, so the resolver and the interpreter can tell apart two of them
+/// with the same name and position
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Returns an id that is unique among every program parsed by the process
+    #[must_use]
+    pub fn next() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 impl std::fmt::Display for Expression {
@@ -92,8 +149,8 @@ impl std::fmt::Display for Expression {
                 expression,
                 operator,
             } => write!(f, "({} {expression})", operator.kind),
-            Self::GroupingExpression(expression) => write!(f, "(group {expression})"),
-            Self::Literal(literal) => write!(f, "{literal}"),
+            Self::GroupingExpression { expression, .. } => write!(f, "(group {expression})"),
+            Self::Literal { value, .. } => write!(f, "{value}"),
             Self::Variable(Reference { identifier, .. }) => write!(f, "(ident {identifier})"),
             Self::Assignment {
                 reference: Reference { identifier, .. },
@@ -1,7 +1,8 @@
-#[derive(Debug)]
+use lox_core::Span;
+
+#[derive(Debug, Clone, Copy)]
 pub struct BinaryOperator {
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
     pub kind: BinaryOperatorKind,
 }
 
@@ -13,7 +14,7 @@ impl std::ops::Deref for BinaryOperator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum BinaryOperatorKind {
     Plus,
     Minus,
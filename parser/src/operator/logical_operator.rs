@@ -1,7 +1,8 @@
-#[derive(Debug)]
+use lox_core::Span;
+
+#[derive(Debug, Clone, Copy)]
 pub struct LogicalOperator {
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
     pub kind: LogicalOperatorKind,
 }
 
@@ -13,7 +14,7 @@ impl std::ops::Deref for LogicalOperator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum LogicalOperatorKind {
     And,
     Or,
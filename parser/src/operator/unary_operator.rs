@@ -1,11 +1,12 @@
-#[derive(Debug)]
+use lox_core::Span;
+
+#[derive(Debug, Clone, Copy)]
 pub struct UnaryOperator {
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
     pub kind: UnaryOperatorKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum UnaryOperatorKind {
     Minus,
     Bang,
@@ -1,12 +1,12 @@
 use std::{ops::Not, rc::Rc};
 
 use lexer::{Token, TokenKind};
-use lox_core::{report, Error, Result};
+use lox_core::{Diagnostics, Error, Result, Span};
 
 use crate::{
     BinaryOperator, BinaryOperatorKind, Expression, Function, Literal, LogicalOperator,
-    LogicalOperatorKind, ParserError, Reference, Statement, UnaryOperator, UnaryOperatorKind,
-    MAX_NUMBER_OF_ARGUMENTS,
+    LogicalOperatorKind, NodeId, Parameter, ParserError, Reference, Statement, UnaryOperator,
+    UnaryOperatorKind, MAX_NUMBER_OF_ARGUMENTS,
 };
 
 macro_rules! match_token {
@@ -42,10 +42,8 @@ macro_rules! binary_operators {
             $(#[doc = $doc])?
             fn $step(&mut $self) -> Result<Expression, ParserError> {
                 if match_token!($self, $($tokens),+) {
-                    let token = $self.previous();
                     return Err(Error {
-                        line: token.line,
-                        column: token.column.saturating_sub(token.len()),
+                        span: $self.previous().span,
                         source: ParserError::ExpectedExpression,
                     });
                 }
@@ -62,8 +60,7 @@ macro_rules! binary_operators {
                         operator: match token.kind {
                             $(
                                 $tokens => BinaryOperator {
-                                    line: token.line,
-                                    column: token.column,
+                                    span: token.span,
                                     kind: $operators
                                 },
                             )+
@@ -93,10 +90,8 @@ macro_rules! logical_operators {
             $(#[doc = $doc])?
             fn $step(&mut $self) -> Result<Expression, ParserError> {
                 if match_token!($self, $($tokens),+) {
-                    let token = $self.previous();
                     return Err(Error {
-                        line: token.line,
-                        column: token.column.saturating_sub(token.len()),
+                        span: $self.previous().span,
                         source: ParserError::ExpectedExpression,
                     });
                 }
@@ -113,8 +108,7 @@ macro_rules! logical_operators {
                         operator: match token.kind {
                             $(
                                 $tokens => LogicalOperator {
-                                    line: token.line,
-                                    column: token.column,
+                                    span: token.span,
                                     kind: $operators
                                 },
                             )+
@@ -131,11 +125,9 @@ macro_rules! logical_operators {
 
 macro_rules! error {
     ($self: ident, $source: expr) => {{
-        let token = $self.previous();
-
+        // Point right after the last token, where something was expected
         return Err(Error {
-            line: token.line,
-            column: token.column + token.len(),
+            span: Span::at($self.previous().span.end),
             source: $source,
         });
     }};
@@ -143,36 +135,37 @@ macro_rules! error {
 
 pub struct Parser<'a> {
     current: usize,
-    source: &'a str,
     tokens: &'a [Token],
+    diagnostics: Diagnostics,
 }
 
 impl<'a> Parser<'a> {
     #[must_use]
-    pub const fn new(source: &'a str, tokens: &'a [Token]) -> Self {
+    pub const fn new(tokens: &'a [Token]) -> Self {
         Self {
             current: 0,
-            source,
             tokens,
+            diagnostics: Diagnostics::new(),
         }
     }
 
-    pub fn parse(&mut self) -> Vec<Statement> {
-        self.program()
+    /// Parses the program. After an error the parser synchronizes at the
+    /// next statement, so every statement that parses successfully is
+    /// returned along with the errors found in the others
+    pub fn parse(&mut self) -> (Vec<Statement>, Diagnostics) {
+        let statements = self.program();
+
+        (statements, std::mem::take(&mut self.diagnostics))
     }
 
     /// `program` -> `statement`* `EOF`
     fn program(&mut self) -> Vec<Statement> {
         let mut statements = vec![];
-        let mut had_error = false;
         while !self.is_done() {
             match self.declaration() {
-                Ok(stmt) if !had_error => statements.push(stmt),
-                Ok(_) => (),
+                Ok(stmt) => statements.push(stmt),
                 Err(err) => {
-                    had_error = true;
-                    statements.clear();
-                    report(self.source, &err);
+                    self.diagnostics.push(err);
                     self.sinchronyze();
                 }
             }
@@ -200,20 +193,18 @@ impl<'a> Parser<'a> {
 
     /// `var_declaration` -> "var" `IDENTIFIER` ("=" `expression`)? ";"
     fn var_declaration(&mut self) -> Result<Statement, ParserError> {
-        let var = self.previous().clone();
         if !match_token!(self, TokenKind::Identifier(_)) {
             error!(self, ParserError::ExpectedIdentifier);
         }
 
         let identifier = self.previous().clone();
         let name = match identifier.kind {
-            TokenKind::Identifier(ref ident) => Rc::clone(ident),
+            TokenKind::Identifier(ref ident) => *ident,
             _ => unreachable!(),
         };
 
         let declaration = Statement::Declaration {
-            line: var.line,
-            column: var.column,
+            span: identifier.span,
             identifier: name,
             initializer: match self.peek().kind {
                 TokenKind::Equals => {
@@ -223,8 +214,7 @@ impl<'a> Parser<'a> {
                 TokenKind::Semicolon => None,
                 _ => {
                     return Err(Error {
-                        line: identifier.line,
-                        column: identifier.line + identifier.len(),
+                        span: Span::at(identifier.span.end),
                         source: ParserError::ExpectedSemicolonOrInitializer,
                     })
                 }
@@ -240,7 +230,7 @@ impl<'a> Parser<'a> {
 
     /// `function_declaration` -> "fun" `named_function`
     fn function_declaration(&mut self) -> Result<Statement, ParserError> {
-        self.named_function(false)
+        self.named_function()
     }
 
     /// `class_declaration` -> "class" `IDENTIFIER` ( "<" `IDENTIFIER` )? "{" function* "}"
@@ -264,9 +254,9 @@ impl<'a> Parser<'a> {
                 let token = self.previous().clone();
 
                 Ok(Expression::Variable(Reference {
-                    line: token.line,
-                    column: token.column,
+                    span: token.span,
                     identifier,
+                    id: NodeId::next(),
                 }))
             })
             .transpose()?;
@@ -277,7 +267,7 @@ impl<'a> Parser<'a> {
 
         let mut methods = vec![];
         while !self.is_done() && !match_token!(peek: self, TokenKind::RightCurly) {
-            methods.push(match self.named_function(true)? {
+            methods.push(match self.named_function()? {
                 Statement::Function(function) => function,
                 _ => unreachable!(),
             });
@@ -288,8 +278,7 @@ impl<'a> Parser<'a> {
         }
 
         Ok(Statement::Class {
-            line: token.line,
-            column: token.column,
+            span: token.span,
             identifier,
             super_class,
             methods: methods.into(),
@@ -297,26 +286,22 @@ impl<'a> Parser<'a> {
     }
 
     /// `named_function` -> `IDENTIFIER` `anonymous_function`
-    fn named_function(&mut self, is_method: bool) -> Result<Statement, ParserError> {
-        let token = if is_method {
-            self.peek().clone()
-        } else {
-            self.previous().clone()
-        };
-
+    fn named_function(&mut self) -> Result<Statement, ParserError> {
         let TokenKind::Identifier(identifier) = self.peek().kind.clone() else {
             error!(self, ParserError::ExpectedIdentifier);
         };
 
-        self.next();
+        let span = self.next().span;
 
-        let Expression::AnonymousFunction { parameters, body } = self.anonymous_function()? else {
+        let Expression::AnonymousFunction {
+            parameters, body, ..
+        } = self.anonymous_function()?
+        else {
             unreachable!()
         };
 
         Ok(Statement::Function(Function {
-            line: token.line,
-            column: token.column,
+            span,
             identifier,
             parameters,
             body,
@@ -325,6 +310,8 @@ impl<'a> Parser<'a> {
 
     /// `anonymous_function` -> "("  `parameters`? ")" `block`
     fn anonymous_function(&mut self) -> Result<Expression, ParserError> {
+        let start = self.previous().span;
+
         if !match_token!(self, TokenKind::LeftParen) {
             error!(self, ParserError::ExpectedLeftParen);
         }
@@ -339,12 +326,15 @@ impl<'a> Parser<'a> {
             error!(self, ParserError::ExpectedLeftCurly);
         }
 
+        let body = match self.block()? {
+            Statement::Block(statements) => statements.into(),
+            _ => unreachable!(),
+        };
+
         Ok(Expression::AnonymousFunction {
+            span: start.to(self.previous().span),
             parameters,
-            body: match self.block()? {
-                Statement::Block(statements) => statements.into(),
-                _ => unreachable!(),
-            },
+            body,
         })
     }
 
@@ -353,7 +343,7 @@ impl<'a> Parser<'a> {
     ///     ("," `IDENTIFIER`){0, `MAX_NUMBER_OF_ARGUMENTS - 1`}
     ///     ","?
     /// )
-    fn parameters(&mut self) -> Result<Rc<[Rc<str>]>, ParserError> {
+    fn parameters(&mut self) -> Result<Rc<[Parameter]>, ParserError> {
         let mut parameters = Vec::with_capacity(MAX_NUMBER_OF_ARGUMENTS);
 
         loop {
@@ -365,21 +355,17 @@ impl<'a> Parser<'a> {
             if parameters.len() == MAX_NUMBER_OF_ARGUMENTS {
                 let token = self.peek().clone();
 
-                // Report the error, but don't return it,
+                // Record the error, but don't return it,
                 // as the parser is still in a valid state
-                report(
-                    self.source,
-                    &Error {
-                        line: token.line,
-                        column: token.column,
-                        source: ParserError::ParameterLimitExceeded,
-                    },
-                );
+                self.diagnostics.push(Error {
+                    span: token.span,
+                    source: ParserError::ParameterLimitExceeded,
+                });
             }
 
-            if let TokenKind::Identifier(ident) = self.peek().kind.clone() {
-                self.next();
-                parameters.push(ident);
+            if let TokenKind::Identifier(identifier) = self.peek().kind.clone() {
+                let span = self.next().span;
+                parameters.push(Parameter { span, identifier });
             } else {
                 error!(self, ParserError::ExpectedIdentifier);
             }
@@ -501,7 +487,10 @@ impl<'a> Parser<'a> {
             .not()
             .then(|| self.expression())
             .transpose()?
-            .unwrap_or(Expression::Literal(Literal::Boolean(true)));
+            .unwrap_or_else(|| Expression::Literal {
+                span: Span::at(self.previous().span.end),
+                value: Literal::Boolean(true),
+            });
 
         if !match_token!(self, TokenKind::Semicolon) {
             error!(self, ParserError::ExpectedSemicolon);
@@ -539,10 +528,7 @@ impl<'a> Parser<'a> {
             error!(self, ParserError::ExpectedSemicolon);
         }
 
-        Ok(Statement::Break {
-            line: token.line,
-            column: token.column,
-        })
+        Ok(Statement::Break { span: token.span })
     }
 
     /// `continue_statement` -> "continue" ";"
@@ -553,10 +539,7 @@ impl<'a> Parser<'a> {
             error!(self, ParserError::ExpectedSemicolon);
         }
 
-        Ok(Statement::Continue {
-            line: token.line,
-            column: token.column,
-        })
+        Ok(Statement::Continue { span: token.span })
     }
 
     /// `return_statement` -> "return" `expression`? ";"
@@ -564,8 +547,7 @@ impl<'a> Parser<'a> {
         let token = self.previous().clone();
         if match_token!(self, TokenKind::Semicolon) {
             return Ok(Statement::Return {
-                line: token.line,
-                column: token.column,
+                span: token.span,
                 expression: None,
             });
         }
@@ -577,8 +559,7 @@ impl<'a> Parser<'a> {
         }
 
         Ok(Statement::Return {
-            line: token.line,
-            column: token.column,
+            span: token.span,
             expression,
         })
     }
@@ -626,14 +607,13 @@ impl<'a> Parser<'a> {
                 Expression::Get {
                     object,
                     identifier,
-                    line,
-                    column,
+                    span,
+                    ..
                 } => Expression::Set {
                     object,
                     identifier,
                     value,
-                    line,
-                    column,
+                    span,
                 },
                 _ => error!(self, ParserError::InvalidAssignmentTarget),
             };
@@ -730,8 +710,7 @@ impl<'a> Parser<'a> {
         Ok(Expression::Unary {
             expression,
             operator: UnaryOperator {
-                line: operator.line,
-                column: operator.column,
+                span: operator.span,
                 kind: match operator.kind {
                     TokenKind::Bang => UnaryOperatorKind::Bang,
                     TokenKind::Minus => UnaryOperatorKind::Minus,
@@ -747,17 +726,17 @@ impl<'a> Parser<'a> {
 
         loop {
             if match_token!(self, TokenKind::LeftParen) {
-                let token = self.previous();
-                expression = Expression::Call {
-                    line: token.line,
-                    column: token.column,
-                    callee: expression.into(),
-                    args: self.arguments()?,
-                };
+                let args = self.arguments()?;
 
                 if !match_token!(self, TokenKind::RightParen) {
                     error!(self, ParserError::ExpectedRightParen);
                 }
+
+                expression = Expression::Call {
+                    span: expression.span().to(self.previous().span),
+                    callee: expression.into(),
+                    args,
+                };
             } else if match_token!(self, TokenKind::Dot) {
                 let TokenKind::Identifier(identifier) = self.peek().kind.clone() else {
                     error!(self, ParserError::ExpectedIdentifier);
@@ -766,10 +745,10 @@ impl<'a> Parser<'a> {
                 let token = self.next();
 
                 expression = Expression::Get {
-                    line: token.line,
-                    column: token.column,
+                    span: token.span,
                     object: expression.into(),
                     identifier,
+                    id: NodeId::next(),
                 }
             } else {
                 break;
@@ -796,16 +775,12 @@ impl<'a> Parser<'a> {
             if args.len() == MAX_NUMBER_OF_ARGUMENTS {
                 let token = self.peek().clone();
 
-                // Report the error, but don't return it,
+                // Record the error, but don't return it,
                 // as the parser is still in a valid state
-                report(
-                    self.source,
-                    &Error {
-                        line: token.line,
-                        column: token.column,
-                        source: ParserError::ArgumentLimitExceeded,
-                    },
-                );
+                self.diagnostics.push(Error {
+                    span: token.span,
+                    source: ParserError::ArgumentLimitExceeded,
+                });
             }
 
             // Using `assignment` to bypass the `comma` operator,
@@ -834,20 +809,20 @@ impl<'a> Parser<'a> {
         if match_token!(self, TokenKind::Identifier(_)) {
             let token = self.previous();
             return Ok(Expression::Variable(Reference {
-                line: token.line,
-                column: token.column,
+                span: token.span,
                 identifier: match token.kind {
-                    TokenKind::Identifier(ref ident) => Rc::clone(ident),
+                    TokenKind::Identifier(ref ident) => *ident,
                     _ => unreachable!(),
                 },
+                id: NodeId::next(),
             }));
         }
 
         if match_token!(self, TokenKind::This) {
             let token = self.previous();
             return Ok(Expression::This {
-                line: token.line,
-                column: token.column,
+                span: token.span,
+                id: NodeId::next(),
             });
         }
 
@@ -864,40 +839,47 @@ impl<'a> Parser<'a> {
             self.next();
 
             return Ok(Expression::Super {
-                line: token.line,
-                column: token.column,
+                span: token.span.to(self.previous().span),
                 method: identifier,
+                id: NodeId::next(),
             });
         }
 
-        if match_token!(self, TokenKind::True) {
-            return Ok(Expression::Literal(Literal::Boolean(true)));
-        }
-
-        if match_token!(self, TokenKind::False) {
-            return Ok(Expression::Literal(Literal::Boolean(false)));
-        }
-
-        if match_token!(self, TokenKind::Nil) {
-            return Ok(Expression::Literal(Literal::Nil));
-        }
+        if match_token!(
+            self,
+            TokenKind::True
+                | TokenKind::False
+                | TokenKind::Nil
+                | TokenKind::Number { .. }
+                | TokenKind::String(_)
+        ) {
+            let token = self.previous();
 
-        if match_token!(self, TokenKind::Number { .. } | TokenKind::String(_)) {
-            return Ok(Expression::Literal(match self.previous().kind {
-                TokenKind::String(ref string) => Literal::String(Rc::clone(string)),
-                TokenKind::Number { value, .. } => Literal::Number(value),
-                _ => unreachable!(),
-            }));
+            return Ok(Expression::Literal {
+                span: token.span,
+                value: match token.kind {
+                    TokenKind::True => Literal::Boolean(true),
+                    TokenKind::False => Literal::Boolean(false),
+                    TokenKind::Nil => Literal::Nil,
+                    TokenKind::String(ref string) => Literal::String(Rc::clone(string)),
+                    TokenKind::Number { value, .. } => Literal::Number(value),
+                    _ => unreachable!(),
+                },
+            });
         }
 
         if match_token!(self, TokenKind::LeftParen) {
+            let start = self.previous().span;
             let expression = self.expression()?.into();
 
             if !match_token!(self, TokenKind::RightParen) {
                 error!(self, ParserError::ExpectedRightParen);
             }
 
-            return Ok(Expression::GroupingExpression(expression));
+            return Ok(Expression::GroupingExpression {
+                span: start.to(self.previous().span),
+                expression,
+            });
         }
 
         if match_token!(self, TokenKind::Fun) {
@@ -1,59 +1,111 @@
 use std::rc::Rc;
 
+use lox_core::{Span, Symbol};
+
 use crate::Expression;
 
 #[derive(Debug)]
 pub enum Statement {
     Expression(Expression),
     Declaration {
-        line: usize,
-        column: usize,
-        identifier: Rc<str>,
+        span: Span,
+        identifier: Symbol,
         initializer: Option<Expression>,
     },
-    Block(Box<[Statement]>),
+    Block(Box<[Self]>),
     If {
         condition: Expression,
-        then_branch: Box<Statement>,
-        else_branch: Option<Box<Statement>>,
+        then_branch: Box<Self>,
+        else_branch: Option<Box<Self>>,
     },
     For {
         condition: Expression,
         increment: Option<Expression>,
-        body: Box<Statement>,
+        body: Box<Self>,
     },
     While {
         condition: Expression,
-        body: Box<Statement>,
+        body: Box<Self>,
     },
     Break {
-        line: usize,
-        column: usize,
+        span: Span,
     },
     Continue {
-        line: usize,
-        column: usize,
+        span: Span,
     },
     Function(Function),
     Return {
-        line: usize,
-        column: usize,
+        span: Span,
         expression: Option<Expression>,
     },
     Class {
-        line: usize,
-        column: usize,
-        identifier: Rc<str>,
+        span: Span,
+        identifier: Symbol,
         super_class: Option<Expression>,
         methods: Rc<[Function]>,
     },
 }
 
+impl Statement {
+    /// The span of the statement, from its first to its last expression or
+    /// identifier. Keywords and braces around them are not included, and
+    /// empty blocks have no span
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        let span = match self {
+            Self::Expression(expression) => expression.span(),
+            Self::Declaration {
+                span, initializer, ..
+            } => initializer
+                .as_ref()
+                .map_or(*span, |initializer| span.to(initializer.span())),
+            Self::Block(statements) => {
+                let first = statements.iter().find_map(Self::span)?;
+                let last = statements.iter().rev().find_map(Self::span)?;
+
+                first.to(last)
+            }
+            Self::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let span = condition.span();
+                let span = then_branch.span().map_or(span, |then| span.to(then));
+
+                else_branch
+                    .as_ref()
+                    .and_then(|else_branch| else_branch.span())
+                    .map_or(span, |falsey| span.to(falsey))
+            }
+            Self::For {
+                condition, body, ..
+            }
+            | Self::While { condition, body } => {
+                let span = condition.span();
+                body.span().map_or(span, |body| span.to(body))
+            }
+            Self::Break { span } | Self::Continue { span } | Self::Class { span, .. } => *span,
+            Self::Function(function) => function.span,
+            Self::Return { span, expression } => expression
+                .as_ref()
+                .map_or(*span, |expression| span.to(expression.span())),
+        };
+
+        Some(span)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
-    pub line: usize,
-    pub column: usize,
-    pub identifier: Rc<str>,
-    pub parameters: Rc<[Rc<str>]>,
+    pub span: Span,
+    pub identifier: Symbol,
+    pub parameters: Rc<[Parameter]>,
     pub body: Rc<[Statement]>,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub span: Span,
+    pub identifier: Symbol,
+}
@@ -0,0 +1,41 @@
+use lexer::Lexer;
+use lox_core::{Diagnostics, Severity};
+use parser::Parser;
+
+fn diagnostics(source: &str) -> Diagnostics {
+    let (tokens, mut diagnostics) = Lexer::new(source).scan();
+    let (_, parser_diagnostics) = Parser::new(&tokens).parse();
+    diagnostics.extend(parser_diagnostics);
+
+    diagnostics
+}
+
+fn codes(diagnostics: &Diagnostics) -> Vec<&'static str> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect()
+}
+
+#[test]
+fn every_syntax_error_is_reported() {
+    let diagnostics = diagnostics("var = 1;\nprint(1 +);\nvar ok = 2;\nif x) {}");
+
+    assert_eq!(codes(&diagnostics), ["E0204", "E0201", "E0209"]);
+    assert!(diagnostics.has_errors());
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Error));
+}
+
+#[test]
+fn lexer_errors_are_reported_along_with_parser_errors() {
+    let diagnostics = diagnostics("var a = 1 @ 2;\nvar b = \"unterminated");
+
+    assert_eq!(codes(&diagnostics), ["E0102", "E0101", "E0203", "E0201"]);
+}
+
+#[test]
+fn valid_programs_have_no_diagnostics() {
+    assert!(diagnostics("var a = 1; print(a);").is_empty());
+}
@@ -0,0 +1,33 @@
+use lexer::Lexer;
+use parser::{Parser, Statement};
+
+#[test]
+fn expressions_span_their_source() {
+    let source = "var total = (1 + 22) * count;\nfoo.bar(total, \"x\");";
+    let (tokens, _) = Lexer::new(source).scan();
+    let (program, diagnostics) = Parser::new(&tokens).parse();
+
+    assert!(diagnostics.is_empty());
+
+    let Statement::Declaration {
+        initializer: Some(ref initializer),
+        span,
+        ..
+    } = program[0]
+    else {
+        panic!("expected a declaration");
+    };
+    let Statement::Expression(ref call) = program[1] else {
+        panic!("expected an expression");
+    };
+
+    assert_eq!(&source[span.start..span.end], "total");
+    assert_eq!(
+        &source[initializer.span().start..initializer.span().end],
+        "(1 + 22) * count"
+    );
+    assert_eq!(
+        &source[call.span().start..call.span().end],
+        "foo.bar(total, \"x\")"
+    );
+}
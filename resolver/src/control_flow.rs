@@ -0,0 +1,293 @@
+use lox_core::{Diagnostic, Diagnostics, Error, Label, Span, Symbol};
+use parser::{Expression, Function, Statement};
+
+use crate::ResolverWarning;
+
+/// Walks the program looking for code that never runs and functions that
+/// only return a value on some of their paths
+#[derive(Debug, Default)]
+pub(crate) struct ControlFlow {
+    diagnostics: Diagnostics,
+}
+
+/// The `return` statements found in the body of a function
+#[derive(Debug, Default)]
+struct Returns {
+    /// The first `return` with a value
+    with_value: Option<Span>,
+
+    /// Whether there is a `return;`
+    without_value: bool,
+}
+
+impl ControlFlow {
+    #[must_use]
+    pub(crate) fn analyze(statements: &[Statement]) -> Diagnostics {
+        let mut control_flow = Self::default();
+        control_flow.block(statements);
+
+        control_flow.diagnostics
+    }
+
+    /// Analyzes a list of statements, returning whether control never
+    /// reaches the end of it
+    fn block(&mut self, statements: &[Statement]) -> bool {
+        let mut diverges = false;
+
+        for (i, statement) in statements.iter().enumerate() {
+            if self.statement(statement) && !diverges {
+                diverges = true;
+
+                let unreachable = &statements[i + 1..];
+                let first = unreachable.iter().find_map(Statement::span);
+                let last = unreachable.iter().rev().find_map(Statement::span);
+
+                if let (Some(first), Some(last)) = (first, last) {
+                    self.diagnostics.push(Error {
+                        span: first.to(last),
+                        source: ResolverWarning::UnreachableCode,
+                    });
+                }
+            }
+        }
+
+        diverges
+    }
+
+    /// Analyzes a statement, returning whether control never reaches the
+    /// statement after it
+    fn statement(&mut self, statement: &Statement) -> bool {
+        match statement {
+            Statement::Expression(expression) => {
+                self.expression(expression);
+                false
+            }
+            Statement::Declaration { initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+
+                false
+            }
+            Statement::Block(statements) => self.block(statements),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition);
+                let then_diverges = self.statement(then_branch);
+
+                else_branch
+                    .as_ref()
+                    .is_some_and(|else_branch| self.statement(else_branch) && then_diverges)
+            }
+            Statement::For {
+                condition,
+                increment,
+                body,
+            } => {
+                self.condition(condition);
+                self.statement(body);
+
+                if let Some(increment) = increment {
+                    self.expression(increment);
+                }
+
+                is_infinite(condition, body)
+            }
+            Statement::While { condition, body } => {
+                self.condition(condition);
+                self.statement(body);
+
+                is_infinite(condition, body)
+            }
+            Statement::Break { .. } | Statement::Continue { .. } => true,
+            Statement::Function(function) => {
+                self.function(function);
+                false
+            }
+            Statement::Return { expression, .. } => {
+                if let Some(expression) = expression {
+                    self.expression(expression);
+                }
+
+                true
+            }
+            Statement::Class {
+                super_class,
+                methods,
+                ..
+            } => {
+                if let Some(super_class) = super_class {
+                    self.expression(super_class);
+                }
+
+                for method in methods.iter() {
+                    // Initializers always return `this`
+                    if method.identifier == Symbol::INIT {
+                        self.block(&method.body);
+                    } else {
+                        self.function(method);
+                    }
+                }
+
+                false
+            }
+        }
+    }
+
+    /// Warns about loops whose condition is always falsey
+    fn condition(&mut self, condition: &Expression) {
+        self.expression(condition);
+
+        if let Expression::Literal { span, value } = condition {
+            if !value.is_truthy() {
+                self.diagnostics.push(Error {
+                    span: *span,
+                    source: ResolverWarning::LoopNeverRuns,
+                });
+            }
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.function_body(function.identifier.as_str(), function.span, &function.body);
+    }
+
+    fn function_body(&mut self, identifier: &str, span: Span, body: &[Statement]) {
+        let always_returns = self.block(body);
+
+        let mut returns = Returns::default();
+        for statement in body {
+            returns.collect(statement);
+        }
+
+        let Some(with_value) = returns.with_value else {
+            return;
+        };
+
+        if always_returns && !returns.without_value {
+            return;
+        }
+
+        let warning = Error {
+            span,
+            source: ResolverWarning::InconsistentReturn(identifier.into()),
+        };
+
+        self.diagnostics.push(
+            Diagnostic::from(warning)
+                .with_secondary(Label::new(with_value).with_message("Returns a value here")),
+        );
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Ternary {
+                condition,
+                truthy,
+                falsey,
+            } => {
+                self.expression(condition);
+                self.expression(truthy);
+                self.expression(falsey);
+            }
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::Unary { expression, .. }
+            | Expression::GroupingExpression { expression, .. } => self.expression(expression),
+            Expression::Assignment { value, .. } => self.expression(value),
+            Expression::AnonymousFunction { span, body, .. } => {
+                self.function_body("anonymous function", *span, body);
+            }
+            Expression::Call { callee, args, .. } => {
+                self.expression(callee);
+
+                for arg in args.iter() {
+                    self.expression(arg);
+                }
+            }
+            Expression::Get { object, .. } => self.expression(object),
+            Expression::Set { object, value, .. } => {
+                self.expression(object);
+                self.expression(value);
+            }
+            Expression::Literal { .. }
+            | Expression::Variable(_)
+            | Expression::This { .. }
+            | Expression::Super { .. } => (),
+        }
+    }
+}
+
+/// Whether a loop only ends by returning, its condition being always truthy
+/// and its body not containing any `break`
+fn is_infinite(condition: &Expression, body: &Statement) -> bool {
+    matches!(condition, Expression::Literal { value, .. } if value.is_truthy()) && !breaks(body)
+}
+
+/// Whether `statement` contains a `break` exiting the loop it is the body
+/// of, the ones of nested loops exiting those instead
+fn breaks(statement: &Statement) -> bool {
+    match statement {
+        Statement::Break { .. } => true,
+        Statement::Block(statements) => statements.iter().any(breaks),
+        Statement::If {
+            then_branch,
+            else_branch,
+            ..
+        } => breaks(then_branch) || else_branch.as_deref().is_some_and(breaks),
+        Statement::Expression(_)
+        | Statement::Declaration { .. }
+        | Statement::For { .. }
+        | Statement::While { .. }
+        | Statement::Continue { .. }
+        | Statement::Function(_)
+        | Statement::Return { .. }
+        | Statement::Class { .. } => false,
+    }
+}
+
+impl Returns {
+    /// Records the `return` statements in `statement`, skipping the ones
+    /// of nested functions
+    fn collect(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Return {
+                expression: Some(expression),
+                span,
+            } => {
+                self.with_value.get_or_insert(span.to(expression.span()));
+            }
+            Statement::Return {
+                expression: None, ..
+            } => self.without_value = true,
+            Statement::Block(statements) => {
+                for statement in statements.iter() {
+                    self.collect(statement);
+                }
+            }
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.collect(then_branch);
+
+                if let Some(else_branch) = else_branch {
+                    self.collect(else_branch);
+                }
+            }
+            Statement::For { body, .. } | Statement::While { body, .. } => self.collect(body),
+            Statement::Expression(_)
+            | Statement::Declaration { .. }
+            | Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Function(_)
+            | Statement::Class { .. } => (),
+        }
+    }
+}
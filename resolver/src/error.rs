@@ -1,5 +1,6 @@
 use std::rc::Rc;
 
+use lox_core::Diagnose;
 use thiserror::Error as ErrorTrait;
 
 #[derive(Debug, ErrorTrait)]
@@ -31,3 +32,31 @@ pub enum ResolverError {
     #[error(r#"Unexpected "this" keyword outside of subclass"#)]
     UnexpectedSuperKeyword,
 }
+
+impl Diagnose for ResolverError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::AttemptedToAccessVariableInItsOwnInitializer => "E0301",
+            Self::AttemptedToRedeclareVariable(_) => "E0302",
+            Self::UnexpectedReturnStatement => "E0303",
+            Self::UnexpectedBreakStatement => "E0304",
+            Self::UnexpectedContinueStatement => "E0305",
+            Self::UnexpectedThisKeyword => "E0306",
+            Self::CannotReturnFromInitializer => "E0307",
+            Self::ClassCannotInheritFromItself => "E0308",
+            Self::UnexpectedSuperKeyword => "E0309",
+        }
+    }
+
+    fn help(&self) -> Vec<String> {
+        match self {
+            Self::CannotReturnFromInitializer => {
+                vec![r#"Initializers always return "this", use "return;" to exit early"#.into()]
+            }
+            Self::UnexpectedSuperKeyword => {
+                vec![r#"Declare a superclass with "class Name < SuperClass""#.into()]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
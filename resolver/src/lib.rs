@@ -1,5 +1,8 @@
+mod control_flow;
 mod error;
 mod resolver;
+mod warning;
 
 pub use error::ResolverError;
-pub use resolver::Resolver;
+pub use resolver::{Binding, BindingKind, Locals, Resolver, Slot};
+pub use warning::ResolverWarning;
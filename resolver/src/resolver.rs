@@ -1,19 +1,67 @@
-use std::{collections::HashMap, rc::Rc};
+use std::collections::HashMap;
 
-use lox_core::{report, Error, Result};
-use parser::{Expression, Function, Reference, Statement};
+use lox_core::{
+    BuildIdHasher, Diagnostic, Diagnostics, Error, Label, Result, Span, Symbol, SymbolMap,
+};
+use parser::{Expression, Function, NodeId, Parameter, Reference, Statement};
 
-use crate::ResolverError;
+use crate::{control_flow::ControlFlow, ResolverError, ResolverWarning};
+
+/// Where each local variable of a program is stored, keyed by the node
+/// referring to it. Globals are not included
+pub type Locals = HashMap<NodeId, Slot, BuildIdHasher>;
 
 #[derive(Debug)]
-pub struct Resolver<'a> {
-    pub source: &'a str,
-    pub scopes: Vec<HashMap<Rc<str>, bool>>,
-    pub locals: HashMap<Reference, usize>,
+pub struct Resolver {
+    pub scopes: Vec<SymbolMap<Binding>>,
+    pub locals: Locals,
     pub had_error: bool,
+    diagnostics: Diagnostics,
     pub is_in_loop: bool,
     pub function_kind: FunctionKind,
     pub class_kind: ClassKind,
+
+    /// Where each global declared so far was declared, so the locals
+    /// shadowing them can be reported
+    globals: SymbolMap<Span>,
+}
+
+/// A name declared in a local scope
+#[derive(Debug, Clone, Copy)]
+pub struct Binding {
+    pub kind: BindingKind,
+    pub span: Span,
+
+    /// Whether the initializer of the binding has been resolved
+    pub is_defined: bool,
+
+    /// Whether the binding is read anywhere
+    pub is_used: bool,
+
+    /// Position of the binding among the ones of its scope
+    pub slot: usize,
+}
+
+/// Where a local variable is stored at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Slot {
+    /// Number of scopes between the one the variable is used in and the one
+    /// it is declared in
+    pub depth: usize,
+
+    /// Position of the variable among the ones declared in its scope
+    pub index: usize,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum BindingKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+
+    /// `this` and `super`, declared by the resolver itself
+    Implicit,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -31,30 +79,48 @@ pub enum ClassKind {
     Subclass,
 }
 
-impl<'a> Resolver<'a> {
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
     #[must_use]
-    pub fn new(source: &'a str) -> Self {
+    pub fn new() -> Self {
         Self {
-            source,
             scopes: Vec::new(),
-            locals: HashMap::new(),
+            locals: HashMap::default(),
             had_error: false,
+            diagnostics: Diagnostics::new(),
             is_in_loop: false,
             function_kind: FunctionKind::None,
             class_kind: ClassKind::None,
+            globals: SymbolMap::default(),
         }
     }
 
-    pub fn resolve(&mut self, statements: &[Statement]) {
+    /// Resolves the program, returning every error and warning found,
+    /// including the ones of the control flow analysis. Only errors set
+    /// `had_error`
+    pub fn resolve(&mut self, statements: &[Statement]) -> Diagnostics {
         for statement in statements {
-            match self.resolve_statement(statement) {
-                Ok(()) => (),
-                Err(error) => {
-                    report(self.source, &error);
-                    self.had_error = true
-                }
+            if let Err(error) = self.resolve_statement(statement) {
+                self.diagnostics.push(error);
+                self.had_error = true;
+
+                // The scopes and kinds left behind by the error would affect
+                // the next statements
+                self.scopes.clear();
+                self.is_in_loop = false;
+                self.function_kind = FunctionKind::None;
+                self.class_kind = ClassKind::None;
             }
         }
+
+        self.diagnostics.extend(ControlFlow::analyze(statements));
+
+        std::mem::take(&mut self.diagnostics)
     }
 
     fn resolve_statement(&mut self, statement: &Statement) -> Result<(), ResolverError> {
@@ -63,16 +129,15 @@ impl<'a> Resolver<'a> {
             Statement::Declaration {
                 identifier,
                 initializer,
-                line,
-                column,
+                span,
             } => {
-                self.declare(identifier, *line, *column)?;
+                self.declare(*identifier, *span, BindingKind::Variable)?;
 
                 if let Some(initializer) = initializer {
                     self.resolve_expression(initializer)?;
                 }
 
-                self.define(identifier);
+                self.define(*identifier);
             }
             Statement::Block(statements) => {
                 self.begin_scope();
@@ -119,20 +184,18 @@ impl<'a> Resolver<'a> {
 
                 self.is_in_loop = is_in_loop;
             }
-            Statement::Break { line, column } => {
+            Statement::Break { span } => {
                 if !self.is_in_loop {
                     return Err(Error {
-                        line: *line,
-                        column: *column,
+                        span: *span,
                         source: ResolverError::UnexpectedBreakStatement,
                     });
                 }
             }
-            Statement::Continue { line, column } => {
+            Statement::Continue { span } => {
                 if !self.is_in_loop {
                     return Err(Error {
-                        line: *line,
-                        column: *column,
+                        span: *span,
                         source: ResolverError::UnexpectedContinueStatement,
                     });
                 }
@@ -141,24 +204,18 @@ impl<'a> Resolver<'a> {
                 identifier,
                 parameters,
                 body,
-                line,
-                column,
+                span,
             }) => {
-                self.declare(identifier, *line, *column)?;
-                self.define(identifier);
+                self.declare(*identifier, *span, BindingKind::Function)?;
+                self.define(*identifier);
                 self.resolve_function(parameters, body, FunctionKind::Function)?;
             }
-            Statement::Return {
-                expression,
-                line,
-                column,
-            } => {
+            Statement::Return { expression, span } => {
                 let is_in_function = self.function_kind != FunctionKind::None;
 
                 if !is_in_function {
                     return Err(Error {
-                        line: *line,
-                        column: *column,
+                        span: *span,
                         source: ResolverError::UnexpectedReturnStatement,
                     });
                 }
@@ -166,8 +223,7 @@ impl<'a> Resolver<'a> {
                 if let Some(expression) = expression {
                     if self.function_kind == FunctionKind::Initializer {
                         return Err(Error {
-                            line: *line,
-                            column: *column,
+                            span: *span,
                             source: ResolverError::CannotReturnFromInitializer,
                         });
                     }
@@ -176,8 +232,7 @@ impl<'a> Resolver<'a> {
                 }
             }
             Statement::Class {
-                line,
-                column,
+                span,
                 identifier,
                 super_class,
                 methods,
@@ -185,8 +240,8 @@ impl<'a> Resolver<'a> {
                 let class_kind = self.class_kind;
 
                 self.class_kind = ClassKind::Class;
-                self.declare(identifier, *line, *column)?;
-                self.define(identifier);
+                self.declare(*identifier, *span, BindingKind::Class)?;
+                self.define(*identifier);
 
                 if let Some(super_class) = super_class {
                     self.class_kind = ClassKind::Subclass;
@@ -196,25 +251,21 @@ impl<'a> Resolver<'a> {
 
                     if reference.identifier.as_ref() == identifier.as_ref() {
                         return Err(Error {
-                            line: reference.line,
-                            column: reference.column,
+                            span: reference.span,
                             source: ResolverError::ClassCannotInheritFromItself,
                         });
                     }
 
-                    self.begin_scope();
-                    self.declare(&"super".into(), *line, *column)?;
-                    self.define(&"super".into());
+                    // The superclass is evaluated in the scope of the class,
+                    // before the scope holding `super` is created
                     self.resolve_expression(super_class)?;
+                    self.begin_scope();
+                    self.declare(Symbol::SUPER, *span, BindingKind::Implicit)?;
+                    self.define(Symbol::SUPER);
                 }
 
-                self.begin_scope();
-
-                self.declare(&"this".into(), *line, *column)?;
-                self.define(&"this".into());
-
                 for method in methods.iter() {
-                    let method_type = if method.identifier.as_ref() == "init" {
+                    let method_type = if method.identifier == Symbol::INIT {
                         FunctionKind::Initializer
                     } else {
                         FunctionKind::Method
@@ -227,7 +278,6 @@ impl<'a> Resolver<'a> {
                     self.end_scope();
                 }
 
-                self.end_scope();
                 self.class_kind = class_kind;
             }
         }
@@ -250,30 +300,33 @@ impl<'a> Resolver<'a> {
                 self.resolve_expression(left)?;
                 self.resolve_expression(right)?;
             }
-            Expression::GroupingExpression(expression) | Expression::Unary { expression, .. } => {
-                self.resolve_expression(expression)?
-            }
-            Expression::Literal(_) => (),
+            Expression::GroupingExpression { expression, .. }
+            | Expression::Unary { expression, .. } => self.resolve_expression(expression)?,
+            Expression::Literal { .. } => (),
             Expression::Variable(reference) => {
                 if let Some(false) = self
                     .scopes
                     .last()
                     .and_then(|x| x.get(&reference.identifier))
+                    .map(|binding| binding.is_defined)
                 {
                     return Err(Error {
-                        line: reference.line,
-                        column: reference.column,
+                        span: reference.span,
                         source: ResolverError::AttemptedToAccessVariableInItsOwnInitializer,
                     });
                 }
 
-                self.resolve_local(reference);
+                self.resolve_local(reference, true);
             }
             Expression::Assignment { reference, value } => {
                 self.resolve_expression(value)?;
-                self.resolve_local(reference);
+
+                // Assigning to a variable doesn't count as using it
+                self.resolve_local(reference, false);
             }
-            Expression::AnonymousFunction { body, parameters } => {
+            Expression::AnonymousFunction {
+                body, parameters, ..
+            } => {
                 self.resolve_function(parameters, body, FunctionKind::Function)?;
             }
             Expression::Call { callee, args, .. } => {
@@ -288,37 +341,35 @@ impl<'a> Resolver<'a> {
                 self.resolve_expression(object)?;
                 self.resolve_expression(value)?;
             }
-            Expression::This { line, column } => {
+            Expression::This { span, id } => {
                 if self.class_kind == ClassKind::None {
                     return Err(Error {
-                        line: *line,
-                        column: *column,
+                        span: *span,
                         source: ResolverError::UnexpectedThisKeyword,
                     });
                 }
 
                 let reference = Reference {
-                    line: *line,
-                    column: *column,
-                    identifier: "this".into(),
+                    span: *span,
+                    identifier: Symbol::THIS,
+                    id: *id,
                 };
-                self.resolve_local(&reference)
+                self.resolve_local(&reference, true);
             }
-            Expression::Super { line, column, .. } => {
+            Expression::Super { span, id, .. } => {
                 if self.class_kind != ClassKind::Subclass {
                     return Err(Error {
-                        line: *line,
-                        column: *column,
+                        span: *span,
                         source: ResolverError::UnexpectedSuperKeyword,
                     });
                 }
 
                 let reference = Reference {
-                    line: *line,
-                    column: *column,
-                    identifier: "super".into(),
+                    span: *span,
+                    identifier: Symbol::SUPER,
+                    id: *id,
                 };
-                self.resolve_local(&reference)
+                self.resolve_local(&reference, true);
             }
         }
 
@@ -326,52 +377,137 @@ impl<'a> Resolver<'a> {
     }
 
     fn begin_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+        self.scopes.push(SymbolMap::default());
     }
 
+    /// Closes the innermost scope, warning about the bindings that were
+    /// never used. Names starting with an underscore are exempt
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+
+        let mut unused: Vec<_> = scope
+            .into_iter()
+            .filter(|(identifier, binding)| {
+                !binding.is_used && !identifier.as_str().starts_with('_')
+            })
+            .filter_map(|(identifier, binding)| {
+                let warning = match binding.kind {
+                    BindingKind::Variable => ResolverWarning::UnusedVariable(identifier.into()),
+                    BindingKind::Parameter => ResolverWarning::UnusedParameter(identifier.into()),
+                    BindingKind::Function => ResolverWarning::UnusedFunction(identifier.into()),
+                    BindingKind::Class | BindingKind::Implicit => return None,
+                };
+
+                Some(Error {
+                    span: binding.span,
+                    source: warning,
+                })
+            })
+            .collect();
+
+        // Scopes are unordered, so the warnings are sorted by position
+        unused.sort_by_key(|warning| warning.span.start);
+
+        for warning in unused {
+            self.diagnostics.push(warning);
+        }
     }
 
     fn declare(
         &mut self,
-        identifier: &Rc<str>,
-        line: usize,
-        column: usize,
+        identifier: Symbol,
+        span: Span,
+        kind: BindingKind,
     ) -> Result<(), ResolverError> {
+        let Some(scope) = self.scopes.last() else {
+            self.globals.entry(identifier).or_insert(span);
+            return Ok(());
+        };
+
+        if scope.contains_key(&identifier) {
+            return Err(Error {
+                span,
+                source: ResolverError::AttemptedToRedeclareVariable(identifier.into()),
+            });
+        }
+
+        if kind != BindingKind::Implicit {
+            self.warn_if_shadowing(identifier, span);
+        }
+
         if let Some(scope) = self.scopes.last_mut() {
-            if scope.contains_key(identifier) {
-                return Err(Error {
-                    line,
-                    column,
-                    source: ResolverError::AttemptedToRedeclareVariable(Rc::clone(identifier)),
-                });
-            }
+            // Variables are stored in the order they are declared in
+            let slot = scope.len();
 
-            scope.insert(Rc::clone(identifier), false);
+            scope.insert(
+                identifier,
+                Binding {
+                    kind,
+                    span,
+                    is_defined: false,
+                    is_used: false,
+                    slot,
+                },
+            );
         }
 
         Ok(())
     }
 
-    fn define(&mut self, identifier: &Rc<str>) {
-        if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(Rc::clone(identifier), true);
+    fn warn_if_shadowing(&mut self, identifier: Symbol, span: Span) {
+        let outer = self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&identifier))
+            .filter(|binding| binding.kind != BindingKind::Implicit)
+            .map(|binding| binding.span)
+            .or_else(|| self.globals.get(&identifier).copied());
+
+        if let Some(outer) = outer {
+            let warning = Error {
+                span,
+                source: ResolverWarning::ShadowedBinding(identifier.into()),
+            };
+
+            self.diagnostics.push(
+                Diagnostic::from(warning)
+                    .with_secondary(Label::new(outer).with_message("Previously declared here")),
+            );
         }
     }
 
-    fn resolve_local(&mut self, reference: &Reference) {
+    fn define(&mut self, identifier: Symbol) {
+        if let Some(binding) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(&identifier))
+        {
+            binding.is_defined = true;
+        }
+    }
+
+    fn resolve_local(&mut self, reference: &Reference, is_read: bool) {
+        let innermost = self.scopes.len().saturating_sub(1);
+
         for i in (0..self.scopes.len()).rev() {
-            if self.scopes[i].contains_key(&reference.identifier) {
-                self.locals
-                    .insert(reference.clone(), self.scopes.len() - 1 - i);
+            if let Some(binding) = self.scopes[i].get_mut(&reference.identifier) {
+                binding.is_used |= is_read;
+
+                let slot = Slot {
+                    depth: innermost - i,
+                    index: binding.slot,
+                };
+                self.locals.insert(reference.id, slot);
+                return;
             }
         }
     }
 
     fn resolve_function(
         &mut self,
-        parameters: &[Rc<str>],
+        parameters: &[Parameter],
         body: &[Statement],
         function_kind: FunctionKind,
     ) -> Result<(), ResolverError> {
@@ -381,10 +517,19 @@ impl<'a> Resolver<'a> {
         self.function_kind = function_kind;
         self.begin_scope();
 
+        // Methods find the instance they are called on in the first slot of
+        // their scope, before their parameters
+        if matches!(
+            function_kind,
+            FunctionKind::Method | FunctionKind::Initializer
+        ) {
+            self.declare(Symbol::THIS, Span::default(), BindingKind::Implicit)?;
+            self.define(Symbol::THIS);
+        }
+
         for parameter in parameters {
-            // Paramenters are imune to declaration errors
-            self.declare(parameter, 0, 0)?;
-            self.define(parameter);
+            self.declare(parameter.identifier, parameter.span, BindingKind::Parameter)?;
+            self.define(parameter.identifier);
         }
 
         for statement in body {
@@ -0,0 +1,79 @@
+use std::rc::Rc;
+
+use lox_core::{Diagnose, Severity};
+use thiserror::Error as ErrorTrait;
+
+/// Problems that don't prevent a program from running, but are likely
+/// to be mistakes
+#[derive(Debug, ErrorTrait)]
+pub enum ResolverWarning {
+    #[error(r#"Unused variable "{0}""#)]
+    UnusedVariable(Rc<str>),
+
+    #[error(r#"Unused parameter "{0}""#)]
+    UnusedParameter(Rc<str>),
+
+    #[error(r#"Unused function "{0}""#)]
+    UnusedFunction(Rc<str>),
+
+    #[error(r#""{0}" shadows a binding of an outer scope"#)]
+    ShadowedBinding(Rc<str>),
+
+    #[error("Unreachable code")]
+    UnreachableCode,
+
+    #[error("The body of the loop never runs")]
+    LoopNeverRuns,
+
+    #[error(r#"Not every path of "{0}" returns a value"#)]
+    InconsistentReturn(Rc<str>),
+}
+
+impl Diagnose for ResolverWarning {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable(_) => "W0301",
+            Self::UnusedParameter(_) => "W0302",
+            Self::UnusedFunction(_) => "W0303",
+            Self::ShadowedBinding(_) => "W0304",
+            Self::UnreachableCode => "W0305",
+            Self::LoopNeverRuns => "W0306",
+            Self::InconsistentReturn(_) => "W0307",
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn help(&self) -> Vec<String> {
+        match self {
+            Self::UnusedVariable(identifier)
+            | Self::UnusedParameter(identifier)
+            | Self::UnusedFunction(identifier) => {
+                vec![format!(
+                    r#"If this is intentional, rename it to "_{identifier}""#
+                )]
+            }
+            Self::InconsistentReturn(_) => {
+                vec![
+                    r#"Paths without a value return "nil", consider returning it explicitly"#
+                        .into(),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn notes(&self) -> Vec<String> {
+        match self {
+            Self::UnreachableCode => {
+                vec![
+                    r#"It follows a "return", "break" or "continue" statement, or a loop that never ends"#
+                        .into(),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
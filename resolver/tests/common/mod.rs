@@ -0,0 +1,21 @@
+use lexer::Lexer;
+use lox_core::Severity;
+use parser::Parser;
+use resolver::Resolver;
+
+/// The codes and messages of the warnings reported for `source`
+pub fn warnings(source: &str) -> Vec<(&'static str, String)> {
+    let (tokens, _) = Lexer::new(source).scan();
+    let (program, diagnostics) = Parser::new(&tokens).parse();
+    assert!(diagnostics.is_empty());
+
+    let diagnostics = Resolver::new().resolve(&program);
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Warning));
+
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.message))
+        .collect()
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::warnings;
+
+#[test]
+fn code_after_return_is_unreachable() {
+    let warnings = warnings("fun f() { return 1; print(2); }\nf();");
+
+    assert_eq!(warnings, [("W0305", "Unreachable code".into())]);
+}
+
+#[test]
+fn code_after_infinite_loop_is_unreachable() {
+    let warnings = warnings("fun f() { while (true) {} print(1); }\nf();");
+
+    assert_eq!(warnings, [("W0305", "Unreachable code".into())]);
+}
+
+#[test]
+fn loop_with_false_condition_never_runs() {
+    let warnings = warnings("while (false) { print(1); }");
+
+    assert_eq!(
+        warnings,
+        [("W0306", "The body of the loop never runs".into())]
+    );
+}
+
+#[test]
+fn missing_return_is_inconsistent() {
+    let warnings = warnings("fun f(x) { if (x) return 1; }\nf(true);");
+
+    assert_eq!(
+        warnings,
+        [("W0307", r#"Not every path of "f" returns a value"#.into())]
+    );
+}
+
+#[test]
+fn infinite_loop_diverges() {
+    let source = "fun f(x) { while (true) { if (x) return 1; } }\nf(true);";
+
+    assert_eq!(warnings(source), []);
+}
+
+#[test]
+fn infinite_loop_with_break_falls_through() {
+    let warnings = warnings(
+        r"
+        fun f(x) {
+            while (true) {
+                if (x) break;
+                if (!x) return 1;
+            }
+        }
+        f(true);
+        ",
+    );
+
+    assert_eq!(
+        warnings,
+        [("W0307", r#"Not every path of "f" returns a value"#.into())]
+    );
+}
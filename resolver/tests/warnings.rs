@@ -0,0 +1,75 @@
+mod common;
+
+use common::warnings;
+
+#[test]
+fn unused_locals_are_reported() {
+    let warnings = warnings(
+        r"
+        fun f(used, unused) {
+            var a = used;
+            var b = 2;
+            fun helper() {}
+            return a;
+        }
+        f(1, 2);
+        ",
+    );
+
+    assert_eq!(
+        warnings,
+        [
+            ("W0302", r#"Unused parameter "unused""#.into()),
+            ("W0301", r#"Unused variable "b""#.into()),
+            ("W0303", r#"Unused function "helper""#.into()),
+        ]
+    );
+}
+
+#[test]
+fn underscored_and_global_bindings_are_not_reported() {
+    let source = "var global = 1;\nfun f(_ignored) { var _skip = 1; }\nf(1);";
+
+    assert_eq!(warnings(source), []);
+}
+
+#[test]
+fn shadowing_locals_are_reported() {
+    let warnings = warnings(
+        r"
+        {
+            var a = 1;
+            {
+                var a = 2;
+                print(a);
+            }
+            print(a);
+        }
+        ",
+    );
+
+    assert_eq!(
+        warnings,
+        [("W0304", r#""a" shadows a binding of an outer scope"#.into())]
+    );
+}
+
+#[test]
+fn shadowing_globals_declared_before_is_reported() {
+    let warnings = warnings(
+        r"
+        fun before() { var later = 1; return later; }
+        var value = 1;
+        fun after() { var value = 2; return value; }
+        var later = 3;
+        ",
+    );
+
+    assert_eq!(
+        warnings,
+        [(
+            "W0304",
+            r#""value" shadows a binding of an outer scope"#.into()
+        )]
+    );
+}
@@ -0,0 +1,129 @@
+use std::{
+    io::{BufRead, Write},
+    time::Instant,
+};
+
+use interpreter::{Limits, Sandbox, Stdlib, Streams, DEFAULT_MAX_CALL_DEPTH};
+
+use crate::{jit::Jit, Value, Vm};
+
+/// Configures a [`Vm`] before it is built
+///
+/// ```ignore
+/// let mut vm = VmBuilder::new()
+///     .max_call_depth(500)
+///     .sandbox(Sandbox::pure().stdout(true))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct VmBuilder {
+    streams: Streams,
+    max_call_depth: usize,
+    limits: Limits,
+    sandbox: Sandbox,
+    jit: bool,
+}
+
+impl Default for VmBuilder {
+    fn default() -> Self {
+        Self {
+            streams: Streams::default(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            limits: Limits::default(),
+            sandbox: Sandbox::default(),
+            jit: false,
+        }
+    }
+}
+
+impl VmBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls nested deeper than this raise a stack overflow error
+    #[must_use]
+    pub const fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Sets how many calls and loop iterations can run before
+    /// [`RuntimeError::OutOfFuel`](interpreter::RuntimeError::OutOfFuel) is
+    /// raised, unlimited by default
+    #[must_use]
+    pub const fn fuel(mut self, fuel: u64) -> Self {
+        self.limits.fuel = Some(fuel);
+        self
+    }
+
+    /// Sets the instant after which
+    /// [`RuntimeError::DeadlineExceeded`](interpreter::RuntimeError::DeadlineExceeded)
+    /// is raised, unlimited by default
+    #[must_use]
+    pub const fn deadline(mut self, deadline: Instant) -> Self {
+        self.limits.deadline = Some(deadline);
+        self
+    }
+
+    /// The capabilities granted to programs. Host natives can't be
+    /// registered on the virtual machine, so only the core natives are
+    /// affected
+    #[must_use]
+    pub const fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Whether functions that are called often and only do arithmetic are
+    /// compiled to machine code. Ignored when the `jit` feature is disabled
+    /// or Cranelift doesn't support the platform. Compiled code isn't
+    /// charged fuel and doesn't check the deadline, so it isn't run while
+    /// either is set
+    #[must_use]
+    pub const fn jit(mut self, jit: bool) -> Self {
+        self.jit = jit;
+        self
+    }
+
+    #[must_use]
+    pub fn stdout(mut self, stdout: impl Write + 'static) -> Self {
+        self.streams.stdout = Box::new(stdout);
+        self
+    }
+
+    #[must_use]
+    pub fn stdin(mut self, stdin: impl BufRead + 'static) -> Self {
+        self.streams.stdin = Box::new(stdin);
+        self
+    }
+
+    #[must_use]
+    pub fn stderr(mut self, stderr: impl Write + 'static) -> Self {
+        self.streams.stderr = Box::new(stderr);
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> Vm {
+        let globals = Value::globals(self.sandbox)
+            .into_iter()
+            .map(|(identifier, value)| (identifier.into(), value))
+            .collect();
+
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals,
+            open_upvalues: Vec::new(),
+            streams: self.streams,
+            call_stack: Vec::new(),
+            trace: None,
+            max_call_depth: self.max_call_depth,
+            limits: self.limits,
+            jit: if self.jit { Jit::new() } else { None },
+            bailout: None,
+        }
+    }
+}
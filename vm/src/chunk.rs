@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use lox_core::Span;
+
+use crate::{Function, Value};
+
+/// A single operation of the virtual machine. Operands index into the
+/// constant pool, the functions of the chunk or the slots of the current
+/// call frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// Pushes a constant
+    Constant(u16),
+    Nil,
+    True,
+    False,
+
+    /// Pushes the value of a variable that was declared without an
+    /// initializer, so reading it before it is assigned can be reported
+    Unassigned,
+    Pop,
+
+    /// Pushes a local variable, whose name is given for error messages
+    GetLocal {
+        slot: u16,
+        name: u16,
+    },
+    SetLocal(u16),
+
+    /// Pushes a captured variable, whose name is given for error messages
+    GetUpvalue {
+        index: u16,
+        name: u16,
+    },
+    SetUpvalue(u16),
+    DefineGlobal(u16),
+    GetGlobal(u16),
+    SetGlobal(u16),
+    GetProperty(u16),
+    SetProperty(u16),
+
+    /// Pops the superclass and the instance below it, pushing the method of
+    /// the superclass bound to the instance
+    GetSuper(u16),
+    Equal,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+
+    /// Jumps to an absolute position in the chunk
+    Jump(u32),
+
+    /// Jumps to an absolute position if the value on top of the stack is
+    /// falsey, without popping it
+    JumpIfFalse(u32),
+
+    /// Calls the value below the given number of arguments
+    Call(u8),
+
+    /// Creates a closure of one of the functions of the chunk
+    Closure(u16),
+
+    /// Moves the local on top of the stack to the heap, as closures captured
+    /// it, and pops it
+    CloseUpvalue,
+    Return,
+
+    /// Pushes a new class. If it has a superclass, it is read from the top of
+    /// the stack and left there for `super` to refer to
+    Class {
+        name: u16,
+        has_super_class: bool,
+    },
+
+    /// Pops a closure and adds it as a method of the class below it
+    Method(u16),
+}
+
+/// Compiled code of a function, along with the data it refers to
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+
+    /// Span of the source code each instruction was compiled from, used to
+    /// locate runtime errors
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    pub functions: Vec<Rc<Function>>,
+}
+
+impl Chunk {
+    /// Appends an instruction, returning its position
+    pub fn write(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.code.push(instruction);
+        self.spans.push(span);
+
+        self.code.len() - 1
+    }
+}
@@ -0,0 +1,804 @@
+use std::rc::Rc;
+
+use lox_core::{Error, Result, Span, Symbol, SymbolMap};
+use parser::{
+    BinaryOperatorKind, Expression, Function as FunctionDeclaration, Literal, LogicalOperatorKind,
+    Parameter, Statement, UnaryOperatorKind,
+};
+
+use crate::{CompileError, Function, Instruction, UpvalueSource, Value};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum FunctionKind {
+    /// The top level of a program
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+/// A variable stored in the stack frame of the function being compiled
+#[derive(Debug)]
+struct Local {
+    identifier: Symbol,
+    depth: usize,
+
+    /// Whether a closure captured the variable, so it has to be moved to
+    /// the heap when its scope ends
+    is_captured: bool,
+}
+
+#[derive(Debug)]
+struct Loop {
+    /// Number of locals declared outside of the loop, the ones above are
+    /// popped by `break` and `continue`
+    locals: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// State of a function whose compilation is in progress
+#[derive(Debug)]
+struct FunctionState {
+    function: Function,
+    kind: FunctionKind,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+
+    /// Constants holding identifiers, so each is only stored once
+    identifiers: SymbolMap<u16>,
+}
+
+impl FunctionState {
+    fn new(identifier: Option<Rc<str>>, kind: FunctionKind) -> Self {
+        // The first slot holds the receiver of methods and the function
+        // itself otherwise, which can't be referred to
+        let receiver = match kind {
+            FunctionKind::Method | FunctionKind::Initializer => "this",
+            FunctionKind::Script | FunctionKind::Function => "",
+        };
+
+        Self {
+            function: Function {
+                identifier,
+                ..Function::default()
+            },
+            kind,
+            locals: vec![Local {
+                identifier: receiver.into(),
+                depth: 0,
+                is_captured: false,
+            }],
+            scope_depth: 0,
+            loops: Vec::new(),
+            identifiers: SymbolMap::default(),
+        }
+    }
+}
+
+/// Compiles a resolved program to bytecode. Variables are resolved again
+/// here, since the compiler lays them out on a stack rather than in
+/// environments
+#[derive(Debug)]
+pub struct Compiler {
+    /// The functions being compiled, innermost last
+    functions: Vec<FunctionState>,
+}
+
+impl Compiler {
+    /// Compiles the program to a function taking no arguments
+    ///
+    /// # Errors
+    /// This function will error if the program exceeds a limit of the
+    /// bytecode format, like the number of constants of a function
+    pub fn compile(program: &[Statement]) -> Result<Function, CompileError> {
+        let mut compiler = Self {
+            functions: vec![FunctionState::new(None, FunctionKind::Script)],
+        };
+
+        for statement in program {
+            compiler.statement(statement)?;
+        }
+
+        compiler.emit(Instruction::Nil, Span::default());
+        compiler.emit(Instruction::Return, Span::default());
+
+        Ok(compiler.end_function())
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        match statement {
+            Statement::Expression(expression) => {
+                self.expression(expression)?;
+                self.emit(Instruction::Pop, expression.span());
+            }
+            Statement::Declaration {
+                span,
+                identifier,
+                initializer,
+            } => {
+                match initializer {
+                    Some(initializer) => self.expression(initializer)?,
+                    None => {
+                        self.emit(Instruction::Unassigned, *span);
+                    }
+                }
+
+                self.define_variable(*identifier, *span)?;
+            }
+            Statement::Block(statements) => {
+                self.begin_scope();
+
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+
+                self.end_scope();
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition)?;
+
+                let span = condition.span();
+                let else_jump = self.emit(Instruction::JumpIfFalse(0), span);
+                self.emit(Instruction::Pop, span);
+                self.statement(then_branch)?;
+
+                let end_jump = self.emit(Instruction::Jump(0), span);
+                self.patch_jump(else_jump, span)?;
+                self.emit(Instruction::Pop, span);
+
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+
+                self.patch_jump(end_jump, span)?;
+            }
+            Statement::While { condition, body } => self.loop_statement(condition, None, body)?,
+            Statement::For {
+                condition,
+                increment,
+                body,
+            } => self.loop_statement(condition, increment.as_ref(), body)?,
+            Statement::Break { span } => {
+                self.pop_loop_locals(*span);
+
+                let jump = self.emit(Instruction::Jump(0), *span);
+                if let Some(current) = self.current().loops.last_mut() {
+                    current.breaks.push(jump);
+                }
+            }
+            Statement::Continue { span } => {
+                self.pop_loop_locals(*span);
+
+                let jump = self.emit(Instruction::Jump(0), *span);
+                if let Some(current) = self.current().loops.last_mut() {
+                    current.continues.push(jump);
+                }
+            }
+            Statement::Function(FunctionDeclaration {
+                span,
+                identifier,
+                parameters,
+                body,
+            }) => {
+                let is_local = self.current().scope_depth > 0;
+
+                // Declared before the body is compiled so it can call itself
+                if is_local {
+                    self.declare_local(*identifier, *span)?;
+                }
+
+                self.function(
+                    Some(*identifier),
+                    parameters,
+                    body,
+                    FunctionKind::Function,
+                    *span,
+                )?;
+
+                if !is_local {
+                    let name = self.identifier(*identifier, *span)?;
+                    self.emit(Instruction::DefineGlobal(name), *span);
+                }
+            }
+            Statement::Return { span, expression } => {
+                if self.current().kind == FunctionKind::Initializer {
+                    self.load_variable(Symbol::THIS, *span)?;
+                } else if let Some(expression) = expression {
+                    self.expression(expression)?;
+                } else {
+                    self.emit(Instruction::Nil, *span);
+                }
+
+                self.emit(Instruction::Return, *span);
+            }
+            Statement::Class {
+                span,
+                identifier,
+                super_class,
+                methods,
+            } => self.class(*identifier, super_class.as_ref(), methods, *span)?,
+        }
+
+        Ok(())
+    }
+
+    fn loop_statement(
+        &mut self,
+        condition: &Expression,
+        increment: Option<&Expression>,
+        body: &Statement,
+    ) -> Result<(), CompileError> {
+        let span = condition.span();
+        let start = self.current().function.chunk.code.len();
+
+        self.expression(condition)?;
+        let exit_jump = self.emit(Instruction::JumpIfFalse(0), span);
+        self.emit(Instruction::Pop, span);
+
+        let locals = self.current().locals.len();
+        self.current().loops.push(Loop {
+            locals,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+
+        self.statement(body)?;
+
+        let current = self.current().loops.pop().unwrap_or_else(|| unreachable!());
+        for jump in current.continues {
+            self.patch_jump(jump, span)?;
+        }
+
+        if let Some(increment) = increment {
+            self.expression(increment)?;
+            self.emit(Instruction::Pop, span);
+        }
+
+        self.emit(Instruction::Jump(Self::jump_target(start, span)?), span);
+        self.patch_jump(exit_jump, span)?;
+        self.emit(Instruction::Pop, span);
+
+        // Breaking skips the pop above, as the condition was already popped
+        for jump in current.breaks {
+            self.patch_jump(jump, span)?;
+        }
+
+        Ok(())
+    }
+
+    fn class(
+        &mut self,
+        identifier: Symbol,
+        super_class: Option<&Expression>,
+        methods: &[FunctionDeclaration],
+        span: Span,
+    ) -> Result<(), CompileError> {
+        let is_local = self.current().scope_depth > 0;
+        let name = self.identifier(identifier, span)?;
+
+        if is_local {
+            self.declare_local(identifier, span)?;
+        }
+
+        if let Some(super_class) = super_class {
+            // The class takes the slot of its local before the superclass
+            // is pushed, and is moved there once its methods are added
+            if is_local {
+                self.emit(Instruction::Unassigned, span);
+            }
+
+            self.expression(super_class)?;
+
+            self.begin_scope();
+            self.declare_local(Symbol::SUPER, span)?;
+
+            let class = Instruction::Class {
+                name,
+                has_super_class: true,
+            };
+            self.emit(class, super_class.span());
+        } else {
+            let class = Instruction::Class {
+                name,
+                has_super_class: false,
+            };
+            self.emit(class, span);
+        }
+
+        for method in methods {
+            let kind = if method.identifier == Symbol::INIT {
+                FunctionKind::Initializer
+            } else {
+                FunctionKind::Method
+            };
+
+            self.function(
+                Some(method.identifier),
+                &method.parameters,
+                &method.body,
+                kind,
+                method.span,
+            )?;
+
+            let name = self.identifier(method.identifier, method.span)?;
+            self.emit(Instruction::Method(name), method.span);
+        }
+
+        match (super_class.is_some(), is_local) {
+            (true, true) => {
+                self.store_variable(identifier, span)?;
+                self.emit(Instruction::Pop, span);
+                self.end_scope();
+            }
+            (true, false) => {
+                self.emit(Instruction::DefineGlobal(name), span);
+                self.end_scope();
+            }
+            (false, false) => {
+                self.emit(Instruction::DefineGlobal(name), span);
+            }
+            // The class is already in the slot of its local
+            (false, true) => (),
+        }
+
+        Ok(())
+    }
+
+    /// Compiles a function, leaving a closure of it on the stack
+    fn function(
+        &mut self,
+        identifier: Option<Symbol>,
+        parameters: &[Parameter],
+        body: &[Statement],
+        kind: FunctionKind,
+        span: Span,
+    ) -> Result<(), CompileError> {
+        self.functions
+            .push(FunctionState::new(identifier.map(Into::into), kind));
+        self.begin_scope();
+
+        for parameter in parameters {
+            self.declare_local(parameter.identifier, parameter.span)?;
+        }
+
+        for statement in body {
+            self.statement(statement)?;
+        }
+
+        if kind == FunctionKind::Initializer {
+            self.load_variable(Symbol::THIS, span)?;
+        } else {
+            self.emit(Instruction::Nil, span);
+        }
+        self.emit(Instruction::Return, span);
+
+        let mut function = self.end_function();
+        function.arity = parameters.len();
+
+        let functions = &mut self.current().function.chunk.functions;
+        let index = u16::try_from(functions.len()).map_err(|_| Error {
+            span,
+            source: CompileError::TooManyFunctions,
+        })?;
+        functions.push(Rc::new(function));
+
+        self.emit(Instruction::Closure(index), span);
+
+        Ok(())
+    }
+
+    fn end_function(&mut self) -> Function {
+        self.functions
+            .pop()
+            .unwrap_or_else(|| unreachable!())
+            .function
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn expression(&mut self, expression: &Expression) -> Result<(), CompileError> {
+        match expression {
+            Expression::Ternary {
+                condition,
+                truthy,
+                falsey,
+            } => {
+                let span = condition.span();
+                self.expression(condition)?;
+
+                let else_jump = self.emit(Instruction::JumpIfFalse(0), span);
+                self.emit(Instruction::Pop, span);
+                self.expression(truthy)?;
+
+                let end_jump = self.emit(Instruction::Jump(0), span);
+                self.patch_jump(else_jump, span)?;
+                self.emit(Instruction::Pop, span);
+                self.expression(falsey)?;
+                self.patch_jump(end_jump, span)?;
+            }
+            Expression::Binary {
+                left,
+                right,
+                operator,
+            } => {
+                self.expression(left)?;
+
+                if matches!(operator.kind, BinaryOperatorKind::Comma) {
+                    self.emit(Instruction::Pop, operator.span);
+                    self.expression(right)?;
+                    return Ok(());
+                }
+
+                self.expression(right)?;
+
+                let instruction = match operator.kind {
+                    BinaryOperatorKind::Plus => Instruction::Add,
+                    BinaryOperatorKind::Minus => Instruction::Subtract,
+                    BinaryOperatorKind::Star => Instruction::Multiply,
+                    BinaryOperatorKind::Slash => Instruction::Divide,
+                    BinaryOperatorKind::DoubleEquals | BinaryOperatorKind::BangEqual => {
+                        Instruction::Equal
+                    }
+                    BinaryOperatorKind::GreaterThan => Instruction::Greater,
+                    BinaryOperatorKind::GreaterEqual => Instruction::GreaterEqual,
+                    BinaryOperatorKind::LessThan => Instruction::Less,
+                    BinaryOperatorKind::LessEqual => Instruction::LessEqual,
+                    BinaryOperatorKind::Comma => unreachable!(),
+                };
+                self.emit(instruction, operator.span);
+
+                if matches!(operator.kind, BinaryOperatorKind::BangEqual) {
+                    self.emit(Instruction::Not, operator.span);
+                }
+            }
+            Expression::Logical {
+                left,
+                right,
+                operator,
+            } => {
+                self.expression(left)?;
+
+                match operator.kind {
+                    LogicalOperatorKind::And => {
+                        let end_jump = self.emit(Instruction::JumpIfFalse(0), operator.span);
+                        self.emit(Instruction::Pop, operator.span);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump, operator.span)?;
+                    }
+                    LogicalOperatorKind::Or => {
+                        let else_jump = self.emit(Instruction::JumpIfFalse(0), operator.span);
+                        let end_jump = self.emit(Instruction::Jump(0), operator.span);
+                        self.patch_jump(else_jump, operator.span)?;
+                        self.emit(Instruction::Pop, operator.span);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump, operator.span)?;
+                    }
+                }
+            }
+            Expression::Unary {
+                expression,
+                operator,
+            } => {
+                self.expression(expression)?;
+
+                let instruction = match operator.kind {
+                    UnaryOperatorKind::Minus => Instruction::Negate,
+                    UnaryOperatorKind::Bang => Instruction::Not,
+                };
+                self.emit(instruction, operator.span);
+            }
+            Expression::GroupingExpression { expression, .. } => self.expression(expression)?,
+            Expression::Literal { span, value } => {
+                let instruction = match value {
+                    Literal::Nil => Instruction::Nil,
+                    Literal::Boolean(true) => Instruction::True,
+                    Literal::Boolean(false) => Instruction::False,
+                    value => Instruction::Constant(self.constant(value.clone().into(), *span)?),
+                };
+                self.emit(instruction, *span);
+            }
+            Expression::Variable(reference) => {
+                self.load_variable(reference.identifier, reference.span)?;
+            }
+            Expression::Assignment { reference, value } => {
+                self.expression(value)?;
+                self.store_variable(reference.identifier, reference.span)?;
+            }
+            Expression::AnonymousFunction {
+                span,
+                parameters,
+                body,
+            } => self.function(None, parameters, body, FunctionKind::Function, *span)?,
+            Expression::Call { span, callee, args } => {
+                self.expression(callee)?;
+
+                for arg in args {
+                    self.expression(arg)?;
+                }
+
+                // The parser limits the number of arguments to 255
+                let count = u8::try_from(args.len()).unwrap_or(u8::MAX);
+                self.emit(Instruction::Call(count), *span);
+            }
+            Expression::Get {
+                span,
+                object,
+                identifier,
+                ..
+            } => {
+                self.expression(object)?;
+
+                let name = self.identifier(*identifier, *span)?;
+                self.emit(Instruction::GetProperty(name), *span);
+            }
+            Expression::Set {
+                span,
+                object,
+                identifier,
+                value,
+            } => {
+                self.expression(object)?;
+                self.expression(value)?;
+
+                let name = self.identifier(*identifier, *span)?;
+                self.emit(Instruction::SetProperty(name), *span);
+            }
+            Expression::This { span, .. } => self.load_variable(Symbol::THIS, *span)?,
+            Expression::Super { span, method, .. } => {
+                self.load_variable(Symbol::THIS, *span)?;
+                self.load_variable(Symbol::SUPER, *span)?;
+
+                let name = self.identifier(*method, *span)?;
+                self.emit(Instruction::GetSuper(name), *span);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn current(&mut self) -> &mut FunctionState {
+        self.functions.last_mut().unwrap_or_else(|| unreachable!())
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.current().function.chunk.write(instruction, span)
+    }
+
+    fn constant(&mut self, value: Value, span: Span) -> Result<u16, CompileError> {
+        let constants = &mut self.current().function.chunk.constants;
+        let index = u16::try_from(constants.len()).map_err(|_| Error {
+            span,
+            source: CompileError::TooManyConstants,
+        })?;
+
+        constants.push(value);
+        Ok(index)
+    }
+
+    /// Adds a constant holding an identifier, reusing it if it already exists
+    fn identifier(&mut self, identifier: Symbol, span: Span) -> Result<u16, CompileError> {
+        if let Some(&index) = self.current().identifiers.get(&identifier) {
+            return Ok(index);
+        }
+
+        let index = self.constant(Value::String(identifier.into()), span)?;
+        self.current().identifiers.insert(identifier, index);
+
+        Ok(index)
+    }
+
+    fn jump_target(position: usize, span: Span) -> Result<u32, CompileError> {
+        u32::try_from(position).map_err(|_| Error {
+            span,
+            source: CompileError::FunctionTooLong,
+        })
+    }
+
+    /// Makes the jump at `position` land on the next instruction
+    fn patch_jump(&mut self, position: usize, span: Span) -> Result<(), CompileError> {
+        let code = &mut self.current().function.chunk.code;
+        let target = Self::jump_target(code.len(), span)?;
+
+        code[position] = match code[position] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            _ => unreachable!(),
+        };
+
+        Ok(())
+    }
+
+    fn begin_scope(&mut self) {
+        self.current().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        let current = self.current();
+        current.scope_depth -= 1;
+
+        let depth = current.scope_depth;
+        let first = current
+            .locals
+            .iter()
+            .rposition(|local| local.depth <= depth)
+            .map_or(0, |position| position + 1);
+
+        let instructions: Vec<_> = current
+            .locals
+            .drain(first..)
+            .rev()
+            .map(|local| {
+                if local.is_captured {
+                    Instruction::CloseUpvalue
+                } else {
+                    Instruction::Pop
+                }
+            })
+            .collect();
+
+        for instruction in instructions {
+            self.emit(instruction, Span::default());
+        }
+    }
+
+    /// Pops the locals declared in the innermost loop, without forgetting
+    /// them, since the code following `break` or `continue` still sees them
+    fn pop_loop_locals(&mut self, span: Span) {
+        let current = self.current();
+        let Some(first) = current.loops.last().map(|current| current.locals) else {
+            return;
+        };
+
+        let instructions: Vec<_> = current.locals[first..]
+            .iter()
+            .rev()
+            .map(|local| {
+                if local.is_captured {
+                    Instruction::CloseUpvalue
+                } else {
+                    Instruction::Pop
+                }
+            })
+            .collect();
+
+        for instruction in instructions {
+            self.emit(instruction, span);
+        }
+    }
+
+    fn declare_local(&mut self, identifier: Symbol, span: Span) -> Result<(), CompileError> {
+        let current = self.current();
+
+        if current.locals.len() > usize::from(u16::MAX) {
+            return Err(Error {
+                span,
+                source: CompileError::TooManyLocals,
+            });
+        }
+
+        current.locals.push(Local {
+            identifier,
+            depth: current.scope_depth,
+            is_captured: false,
+        });
+
+        Ok(())
+    }
+
+    /// Turns the value on top of the stack into a variable, local to the
+    /// current scope or global
+    fn define_variable(&mut self, identifier: Symbol, span: Span) -> Result<(), CompileError> {
+        if self.current().scope_depth > 0 {
+            return self.declare_local(identifier, span);
+        }
+
+        let name = self.identifier(identifier, span)?;
+        self.emit(Instruction::DefineGlobal(name), span);
+
+        Ok(())
+    }
+
+    fn load_variable(&mut self, identifier: Symbol, span: Span) -> Result<(), CompileError> {
+        let innermost = self.functions.len() - 1;
+        let name = self.identifier(identifier, span)?;
+
+        let instruction = if let Some(slot) = self.resolve_local(innermost, identifier) {
+            Instruction::GetLocal { slot, name }
+        } else if let Some(index) = self.resolve_upvalue(innermost, identifier, span)? {
+            Instruction::GetUpvalue { index, name }
+        } else {
+            Instruction::GetGlobal(name)
+        };
+
+        self.emit(instruction, span);
+        Ok(())
+    }
+
+    fn store_variable(&mut self, identifier: Symbol, span: Span) -> Result<(), CompileError> {
+        let innermost = self.functions.len() - 1;
+
+        let instruction = if let Some(slot) = self.resolve_local(innermost, identifier) {
+            Instruction::SetLocal(slot)
+        } else if let Some(index) = self.resolve_upvalue(innermost, identifier, span)? {
+            Instruction::SetUpvalue(index)
+        } else {
+            Instruction::SetGlobal(self.identifier(identifier, span)?)
+        };
+
+        self.emit(instruction, span);
+        Ok(())
+    }
+
+    /// Finds the slot of a local of the function at `depth`, the innermost
+    /// declaration winning
+    fn resolve_local(&self, depth: usize, identifier: Symbol) -> Option<u16> {
+        let slot = self.functions[depth]
+            .locals
+            .iter()
+            .rposition(|local| local.identifier == identifier)?;
+
+        // The number of locals is checked when they are declared
+        u16::try_from(slot).ok()
+    }
+
+    /// Finds a variable of an enclosing function, capturing it in every
+    /// function between that one and the one at `depth`
+    fn resolve_upvalue(
+        &mut self,
+        depth: usize,
+        identifier: Symbol,
+        span: Span,
+    ) -> Result<Option<u16>, CompileError> {
+        if depth == 0 {
+            return Ok(None);
+        }
+
+        if let Some(slot) = self.resolve_local(depth - 1, identifier) {
+            self.functions[depth - 1].locals[usize::from(slot)].is_captured = true;
+
+            let source = UpvalueSource {
+                is_local: true,
+                index: slot,
+            };
+            return self.add_upvalue(depth, source, span).map(Some);
+        }
+
+        let Some(index) = self.resolve_upvalue(depth - 1, identifier, span)? else {
+            return Ok(None);
+        };
+
+        let source = UpvalueSource {
+            is_local: false,
+            index,
+        };
+        self.add_upvalue(depth, source, span).map(Some)
+    }
+
+    fn add_upvalue(
+        &mut self,
+        depth: usize,
+        source: UpvalueSource,
+        span: Span,
+    ) -> Result<u16, CompileError> {
+        let upvalues = &mut self.functions[depth].function.upvalues;
+
+        let index = upvalues
+            .iter()
+            .position(|upvalue| *upvalue == source)
+            .unwrap_or_else(|| {
+                upvalues.push(source);
+                upvalues.len() - 1
+            });
+
+        u16::try_from(index).map_err(|_| Error {
+            span,
+            source: CompileError::TooManyUpvalues,
+        })
+    }
+}
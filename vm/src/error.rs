@@ -0,0 +1,37 @@
+use lox_core::Diagnose;
+use thiserror::Error as ErrorTrait;
+
+/// Limits of the bytecode format that a program exceeded
+#[derive(Debug, ErrorTrait)]
+pub enum CompileError {
+    #[error("A function cannot contain more than 65536 constants")]
+    TooManyConstants,
+
+    #[error("A function cannot declare more than 65536 local variables")]
+    TooManyLocals,
+
+    #[error("A function cannot capture more than 65536 variables")]
+    TooManyUpvalues,
+
+    #[error("A function cannot contain more than 65536 functions")]
+    TooManyFunctions,
+
+    #[error("The code of a function is too long")]
+    FunctionTooLong,
+}
+
+impl Diagnose for CompileError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::TooManyConstants => "E0501",
+            Self::TooManyLocals => "E0502",
+            Self::TooManyUpvalues => "E0503",
+            Self::TooManyFunctions => "E0504",
+            Self::FunctionTooLong => "E0505",
+        }
+    }
+
+    fn help(&self) -> Vec<String> {
+        vec!["Split the function into smaller ones".into()]
+    }
+}
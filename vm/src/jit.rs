@@ -0,0 +1,698 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    rc::{Rc, Weak},
+    sync::atomic::AtomicBool,
+};
+
+use cranelift::{
+    codegen::{
+        ir::{
+            types, AbiParam, Block, InstBuilder, MemFlags, StackSlotData, StackSlotKind,
+            Value as IrValue,
+        },
+        settings::{self, Configurable},
+        Context,
+    },
+    frontend::{FunctionBuilder, FunctionBuilderContext, Variable},
+    prelude::{FloatCC, IntCC},
+};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, FuncId, Module};
+
+use crate::{Function, Instruction, Value};
+
+/// Number of calls after which a function is compiled to machine code
+const HOT_CALL_THRESHOLD: u32 = 1_000;
+
+/// Compiled functions calling themselves recurse on the native stack, which
+/// is much smaller than the virtual machine's. Deeper calls bail out, which
+/// keeps their frames within a few hundred kilobytes
+const MAX_NATIVE_DEPTH: usize = 4_096;
+
+/// Number of functions seen after which those dropped since are forgotten.
+/// It doubles with the number of functions still alive, so sweeping takes
+/// constant time per call on average
+const MIN_SWEEP_THRESHOLD: usize = 256;
+
+/// Shared by compiled functions and the virtual machine, so compiled code
+/// stops where the virtual machine would raise a stack overflow
+#[repr(C)]
+struct JitContext {
+    /// Number of calls in progress, including the virtual machine's
+    depth: u64,
+    max_depth: u64,
+
+    /// Set from another thread to stop the program. Compiled code bails out
+    /// when it sees it, leaving the virtual machine to raise the error
+    interrupted: *const AtomicBool,
+
+    /// Set when compiled code bails out, its result must then be discarded
+    failed: u8,
+}
+
+const DEPTH_OFFSET: i32 = 0;
+const MAX_DEPTH_OFFSET: i32 = 8;
+const INTERRUPTED_OFFSET: i32 = 16;
+const FAILED_OFFSET: i32 = 24;
+
+/// The arguments are passed as an array, so every function has the same
+/// signature whatever its arity
+type Entry = unsafe extern "C" fn(*mut JitContext, *const f64) -> f64;
+
+/// What is known about the values on the stack while compiling a function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Number,
+    Boolean,
+
+    /// The first slot of the frame, holding the function being called
+    Callee,
+
+    /// The function itself, read from its global to call itself
+    Recursion,
+}
+
+/// A function compiled to machine code
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    entry: Entry,
+
+    recursion: Option<Rc<str>>,
+}
+
+impl Compiled {
+    /// The global the function calls itself through, which must still hold
+    /// the function when it is called
+    pub const fn recursion(&self) -> Option<&Rc<str>> {
+        self.recursion.as_ref()
+    }
+
+    /// Runs the function. `depth` is the number of calls in progress,
+    /// including this one. Returns `None` if it bailed out before reaching
+    /// `max_depth`, recursed too deep for the native stack or saw
+    /// `interrupted` set, the call must then be made by the virtual machine
+    pub fn call(
+        &self,
+        args: &[f64],
+        depth: usize,
+        max_depth: usize,
+        interrupted: &AtomicBool,
+    ) -> Option<f64> {
+        let mut context = JitContext {
+            depth: depth as u64,
+            max_depth: max_depth.min(depth + MAX_NATIVE_DEPTH) as u64,
+            interrupted,
+            failed: 0,
+        };
+
+        // SAFETY: the entry was compiled for a function of this arity, which
+        // the virtual machine checked against the arguments, and only reads
+        // the arguments, the context and the flag it points to
+        let result = unsafe { (self.entry)(&raw mut context, args.as_ptr()) };
+
+        (context.failed == 0).then_some(result)
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    /// Number of calls so far
+    Cold(u32),
+    Compiled(Compiled),
+
+    /// The function does more than arithmetic
+    Unsupported,
+}
+
+/// Compiles frequently called functions whose values are only numbers and
+/// booleans to machine code. They can't have side effects, so when compiled
+/// code bails out the call is simply made again by the virtual machine
+pub struct Jit {
+    module: JITModule,
+
+    /// The functions seen so far. A weak reference keeps the allocation of a
+    /// function, but not its code, so its address isn't reused by another
+    /// function until its entry is swept
+    functions: HashMap<*const Function, (Weak<Function>, State)>,
+
+    /// Number of functions seen after which dropped ones are swept
+    sweep_threshold: usize,
+}
+
+impl std::fmt::Debug for Jit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jit").finish_non_exhaustive()
+    }
+}
+
+impl Jit {
+    /// Returns `None` if Cranelift doesn't support the host
+    pub fn new() -> Option<Box<Self>> {
+        let mut flags = settings::builder();
+        flags.set("use_colocated_libcalls", "false").ok()?;
+        flags.set("is_pic", "false").ok()?;
+        flags.set("opt_level", "speed").ok()?;
+
+        let isa = cranelift_native::builder()
+            .ok()?
+            .finish(settings::Flags::new(flags))
+            .ok()?;
+
+        Some(Box::new(Self {
+            module: JITModule::new(JITBuilder::with_isa(isa, default_libcall_names())),
+            functions: HashMap::new(),
+            sweep_threshold: MIN_SWEEP_THRESHOLD,
+        }))
+    }
+
+    /// Records a call of the function, returning its compiled code once it is
+    /// hot, if it can be compiled
+    pub fn compiled(&mut self, function: &Rc<Function>) -> Option<&Compiled> {
+        if self.functions.len() >= self.sweep_threshold {
+            self.functions
+                .retain(|_, (function, _)| function.strong_count() > 0);
+            self.sweep_threshold = MIN_SWEEP_THRESHOLD.max(self.functions.len() * 2);
+        }
+
+        let (_, state) = self
+            .functions
+            .entry(Rc::as_ptr(function))
+            .or_insert_with(|| (Rc::downgrade(function), State::Cold(0)));
+
+        if let State::Cold(ref mut calls) = *state {
+            *calls += 1;
+
+            if *calls < HOT_CALL_THRESHOLD {
+                return None;
+            }
+
+            *state = match analyse(function) {
+                Some(analysis) => Self::compile(&mut self.module, function, &analysis)
+                    .map_or(State::Unsupported, State::Compiled),
+                None => State::Unsupported,
+            };
+        }
+
+        match *state {
+            State::Compiled(ref compiled) => Some(compiled),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn compile(
+        module: &mut JITModule,
+        function: &Function,
+        analysis: &Analysis,
+    ) -> Option<Compiled> {
+        let pointer = module.target_config().pointer_type();
+        let mut context = module.make_context();
+        context.func.signature.params.push(AbiParam::new(pointer));
+        context.func.signature.params.push(AbiParam::new(pointer));
+        context
+            .func
+            .signature
+            .returns
+            .push(AbiParam::new(types::F64));
+
+        let id = module
+            .declare_anonymous_function(&context.func.signature)
+            .ok()?;
+
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        let itself = module.declare_func_in_func(id, builder.func);
+        let flags = MemFlags::trusted();
+
+        // Every position of the stack is a variable, holding booleans as 0
+        // or 1 so all of them are floats
+        for position in 0..analysis.max_height {
+            builder.declare_var(variable(position), types::F64);
+        }
+
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+
+        let shared = builder.block_params(entry)[0];
+        let arguments = builder.block_params(entry)[1];
+        let zero = builder.ins().f64const(0.0);
+        builder.def_var(variable(0), zero);
+
+        for index in 0..function.arity {
+            let offset = i32::try_from(index * 8).ok()?;
+            let argument = builder.ins().load(types::F64, flags, arguments, offset);
+            builder.def_var(variable(index + 1), argument);
+        }
+
+        // Sets the failure flag and returns, as deep as the calls go
+        let unwind = builder.create_block();
+
+        let blocks: HashMap<usize, Block> = analysis
+            .block_starts
+            .iter()
+            .filter(|&&start| matches!(analysis.stacks.get(start), Some(Some(_))))
+            .map(|&start| (start, builder.create_block()))
+            .collect();
+
+        let mut is_open = true;
+        for (ip, instruction) in function.chunk.code.iter().enumerate() {
+            let Some(ref stack) = analysis.stacks[ip] else {
+                continue;
+            };
+
+            if let Some(&block) = blocks.get(&ip) {
+                if is_open {
+                    builder.ins().jump(block, &[]);
+                }
+
+                builder.switch_to_block(block);
+                is_open = true;
+            }
+
+            let height = stack.len();
+            let top = height.wrapping_sub(1);
+
+            match *instruction {
+                Instruction::Constant(index) => {
+                    let Value::Number(number) = function.chunk.constants[usize::from(index)] else {
+                        unreachable!()
+                    };
+
+                    let value = builder.ins().f64const(number);
+                    builder.def_var(variable(height), value);
+                }
+                Instruction::True | Instruction::False => {
+                    let boolean = if *instruction == Instruction::True {
+                        1.0
+                    } else {
+                        0.0
+                    };
+
+                    let value = builder.ins().f64const(boolean);
+                    builder.def_var(variable(height), value);
+                }
+                // Only the number of values on the stack changes
+                Instruction::Pop | Instruction::GetGlobal(_) => (),
+                Instruction::GetLocal { slot, .. } => {
+                    let value = builder.use_var(variable(usize::from(slot)));
+                    builder.def_var(variable(height), value);
+                }
+                Instruction::SetLocal(slot) => {
+                    let value = builder.use_var(variable(top));
+                    builder.def_var(variable(usize::from(slot)), value);
+                }
+                Instruction::Equal => {
+                    let value = if stack[top] == stack[top - 1] {
+                        let a = builder.use_var(variable(top - 1));
+                        let b = builder.use_var(variable(top));
+                        let condition = builder.ins().fcmp(FloatCC::Equal, a, b);
+                        boolean(&mut builder, condition)
+                    } else {
+                        builder.ins().f64const(0.0)
+                    };
+
+                    builder.def_var(variable(top - 1), value);
+                }
+                Instruction::Greater
+                | Instruction::GreaterEqual
+                | Instruction::Less
+                | Instruction::LessEqual => {
+                    let condition = match instruction {
+                        Instruction::Greater => FloatCC::GreaterThan,
+                        Instruction::GreaterEqual => FloatCC::GreaterThanOrEqual,
+                        Instruction::Less => FloatCC::LessThan,
+                        _ => FloatCC::LessThanOrEqual,
+                    };
+
+                    // Booleans held as 0 and 1 compare the same way
+                    let a = builder.use_var(variable(top - 1));
+                    let b = builder.use_var(variable(top));
+                    let condition = builder.ins().fcmp(condition, a, b);
+                    let value = boolean(&mut builder, condition);
+                    builder.def_var(variable(top - 1), value);
+                }
+                Instruction::Add
+                | Instruction::Subtract
+                | Instruction::Multiply
+                | Instruction::Divide => {
+                    let a = builder.use_var(variable(top - 1));
+                    let b = builder.use_var(variable(top));
+
+                    let value = match instruction {
+                        Instruction::Add => builder.ins().fadd(a, b),
+                        Instruction::Subtract => builder.ins().fsub(a, b),
+                        Instruction::Multiply => builder.ins().fmul(a, b),
+                        _ => builder.ins().fdiv(a, b),
+                    };
+                    builder.def_var(variable(top - 1), value);
+                }
+                Instruction::Not => {
+                    let value = if stack[top] == Type::Boolean {
+                        let one = builder.ins().f64const(1.0);
+                        let value = builder.use_var(variable(top));
+                        builder.ins().fsub(one, value)
+                    } else {
+                        builder.ins().f64const(0.0)
+                    };
+
+                    builder.def_var(variable(top), value);
+                }
+                Instruction::Negate => {
+                    let value = builder.use_var(variable(top));
+                    let value = builder.ins().fneg(value);
+                    builder.def_var(variable(top), value);
+                }
+                Instruction::Jump(target) => {
+                    // Loops can run forever, so every iteration checks
+                    // whether the program was interrupted
+                    if (target as usize) <= ip {
+                        let next = builder.create_block();
+                        check_interrupted(&mut builder, shared, pointer, unwind, next);
+                        builder.switch_to_block(next);
+                    }
+
+                    builder.ins().jump(blocks[&(target as usize)], &[]);
+                    is_open = false;
+                }
+                // Numbers are always truthy
+                Instruction::JumpIfFalse(_) if stack[top] == Type::Number => (),
+                Instruction::JumpIfFalse(target) => {
+                    let value = builder.use_var(variable(top));
+                    let zero = builder.ins().f64const(0.0);
+                    let is_false = builder.ins().fcmp(FloatCC::Equal, value, zero);
+
+                    builder.ins().brif(
+                        is_false,
+                        blocks[&(target as usize)],
+                        &[],
+                        blocks[&(ip + 1)],
+                        &[],
+                    );
+                    is_open = false;
+                }
+                Instruction::Call(count) => {
+                    let count = usize::from(count);
+                    let callee = height - count - 1;
+
+                    let depth = builder.ins().load(types::I64, flags, shared, DEPTH_OFFSET);
+                    let max_depth = builder
+                        .ins()
+                        .load(types::I64, flags, shared, MAX_DEPTH_OFFSET);
+                    let overflows =
+                        builder
+                            .ins()
+                            .icmp(IntCC::UnsignedGreaterThanOrEqual, depth, max_depth);
+
+                    let check = builder.create_block();
+                    builder.ins().brif(overflows, unwind, &[], check, &[]);
+                    builder.switch_to_block(check);
+
+                    // Recursion can run for a long time too
+                    let call = builder.create_block();
+                    check_interrupted(&mut builder, shared, pointer, unwind, call);
+                    builder.switch_to_block(call);
+
+                    let deeper = builder.ins().iadd_imm(depth, 1);
+                    builder.ins().store(flags, deeper, shared, DEPTH_OFFSET);
+
+                    let size = u32::try_from(count.max(1) * 8).ok()?;
+                    let slot = builder.create_sized_stack_slot(StackSlotData::new(
+                        StackSlotKind::ExplicitSlot,
+                        size,
+                        3,
+                    ));
+
+                    for index in 0..count {
+                        let argument = builder.use_var(variable(callee + 1 + index));
+                        let offset = i32::try_from(index * 8).ok()?;
+                        builder.ins().stack_store(argument, slot, offset);
+                    }
+
+                    let array = builder.ins().stack_addr(pointer, slot, 0);
+                    let call_instruction = builder.ins().call(itself, &[shared, array]);
+                    let value = builder.inst_results(call_instruction)[0];
+                    builder.ins().store(flags, depth, shared, DEPTH_OFFSET);
+
+                    let failed = builder.ins().load(types::I8, flags, shared, FAILED_OFFSET);
+                    let after = builder.create_block();
+                    builder.ins().brif(failed, unwind, &[], after, &[]);
+                    builder.switch_to_block(after);
+
+                    builder.def_var(variable(callee), value);
+                }
+                Instruction::Return => {
+                    let value = builder.use_var(variable(top));
+                    builder.ins().return_(&[value]);
+                    is_open = false;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        builder.switch_to_block(unwind);
+        let failed = builder.ins().iconst(types::I8, 1);
+        builder.ins().store(flags, failed, shared, FAILED_OFFSET);
+        let zero = builder.ins().f64const(0.0);
+        builder.ins().return_(&[zero]);
+
+        builder.seal_all_blocks();
+        builder.finalize();
+
+        Self::define(module, id, &mut context)?;
+
+        // SAFETY: the code was compiled with the signature of `Entry`
+        let entry =
+            unsafe { std::mem::transmute::<*const u8, Entry>(module.get_finalized_function(id)) };
+
+        Some(Compiled {
+            entry,
+            recursion: analysis.recursion.clone(),
+        })
+    }
+
+    fn define(module: &mut JITModule, id: FuncId, context: &mut Context) -> Option<()> {
+        let defined = module.define_function(id, context);
+        module.clear_context(context);
+        defined.ok()?;
+
+        module.finalize_definitions().ok()
+    }
+}
+
+fn variable(position: usize) -> Variable {
+    // The height of the stack is bounded by the number of locals, which
+    // fits in a `u16`
+    Variable::from_u32(u32::try_from(position).unwrap_or(u32::MAX))
+}
+
+/// Jumps to `unwind` if the program was interrupted, to `next` otherwise
+fn check_interrupted(
+    builder: &mut FunctionBuilder,
+    shared: IrValue,
+    pointer: types::Type,
+    unwind: Block,
+    next: Block,
+) {
+    let flags = MemFlags::trusted();
+    let interrupted = builder
+        .ins()
+        .load(pointer, flags, shared, INTERRUPTED_OFFSET);
+    let interrupted = builder.ins().atomic_load(types::I8, flags, interrupted);
+    builder.ins().brif(interrupted, unwind, &[], next, &[]);
+}
+
+/// Turns the result of a comparison into 0 or 1
+fn boolean(builder: &mut FunctionBuilder, condition: IrValue) -> IrValue {
+    let one = builder.ins().f64const(1.0);
+    let zero = builder.ins().f64const(0.0);
+    builder.ins().select(condition, one, zero)
+}
+
+/// The types of the values on the stack before each instruction of a
+/// function that can be compiled
+#[derive(Debug)]
+struct Analysis {
+    /// `None` for unreachable instructions
+    stacks: Vec<Option<Vec<Type>>>,
+
+    /// Instructions that can be jumped to, or follow a jump
+    block_starts: BTreeSet<usize>,
+    max_height: usize,
+    recursion: Option<Rc<str>>,
+}
+
+/// Checks that the function only does arithmetic on numbers and booleans,
+/// calling nothing but itself. Returns `None` otherwise
+#[allow(clippy::too_many_lines)]
+fn analyse(function: &Function) -> Option<Analysis> {
+    let code = &function.chunk.code;
+    let mut analysis = Analysis {
+        stacks: vec![None; code.len()],
+        block_starts: BTreeSet::new(),
+        max_height: 0,
+        recursion: None,
+    };
+
+    let mut entry = vec![Type::Callee];
+    entry.extend(std::iter::repeat_n(Type::Number, function.arity));
+    analysis.stacks[0] = Some(entry);
+
+    let mut pending = vec![0];
+    while let Some(ip) = pending.pop() {
+        let mut stack = analysis.stacks[ip].clone()?;
+        let mut successors = vec![ip + 1];
+
+        match code[ip] {
+            Instruction::Constant(index) => match function.chunk.constants[usize::from(index)] {
+                Value::Number(_) => stack.push(Type::Number),
+                _ => return None,
+            },
+            Instruction::True | Instruction::False => stack.push(Type::Boolean),
+            Instruction::Pop => {
+                stack.pop()?;
+            }
+            Instruction::GetLocal { slot, .. } => {
+                let value = *stack.get(usize::from(slot))?;
+
+                if !matches!(value, Type::Number | Type::Boolean) {
+                    return None;
+                }
+
+                stack.push(value);
+            }
+            Instruction::SetLocal(slot) => {
+                let value = *stack.last()?;
+                *stack.get_mut(usize::from(slot))? = value;
+            }
+            Instruction::GetGlobal(name) => {
+                let Value::String(ref identifier) = function.chunk.constants[usize::from(name)]
+                else {
+                    return None;
+                };
+
+                if function.identifier.as_ref() != Some(identifier) {
+                    return None;
+                }
+
+                analysis.recursion = Some(Rc::clone(identifier));
+                stack.push(Type::Recursion);
+            }
+            Instruction::Equal => {
+                let b = stack.pop()?;
+                let a = stack.pop()?;
+
+                if !is_scalar(a) || !is_scalar(b) {
+                    return None;
+                }
+
+                stack.push(Type::Boolean);
+            }
+            Instruction::Greater
+            | Instruction::GreaterEqual
+            | Instruction::Less
+            | Instruction::LessEqual => {
+                let b = stack.pop()?;
+                let a = stack.pop()?;
+
+                if a != b || !is_scalar(a) {
+                    return None;
+                }
+
+                stack.push(Type::Boolean);
+            }
+            Instruction::Add
+            | Instruction::Subtract
+            | Instruction::Multiply
+            | Instruction::Divide => {
+                let b = stack.pop()?;
+                let a = stack.pop()?;
+
+                if a != Type::Number || b != Type::Number {
+                    return None;
+                }
+
+                stack.push(Type::Number);
+            }
+            Instruction::Not => {
+                let value = stack.pop()?;
+
+                if !is_scalar(value) {
+                    return None;
+                }
+
+                stack.push(Type::Boolean);
+            }
+            Instruction::Negate => {
+                if stack.last() != Some(&Type::Number) {
+                    return None;
+                }
+            }
+            Instruction::Jump(target) => {
+                successors = vec![target as usize];
+                analysis.block_starts.insert(target as usize);
+                analysis.block_starts.insert(ip + 1);
+            }
+            Instruction::JumpIfFalse(target) => {
+                let condition = *stack.last()?;
+
+                if !is_scalar(condition) {
+                    return None;
+                }
+
+                if condition == Type::Boolean {
+                    successors.push(target as usize);
+                }
+
+                analysis.block_starts.insert(target as usize);
+                analysis.block_starts.insert(ip + 1);
+            }
+            Instruction::Call(count) => {
+                let count = usize::from(count);
+                let callee = stack.len().checked_sub(count + 1)?;
+
+                if stack[callee] != Type::Recursion
+                    || count != function.arity
+                    || stack[callee + 1..]
+                        .iter()
+                        .any(|&value| value != Type::Number)
+                {
+                    return None;
+                }
+
+                stack.truncate(callee);
+                stack.push(Type::Number);
+            }
+            Instruction::Return => {
+                if stack.last() != Some(&Type::Number) {
+                    return None;
+                }
+
+                successors.clear();
+                analysis.block_starts.insert(ip + 1);
+            }
+            _ => return None,
+        }
+
+        analysis.max_height = analysis.max_height.max(stack.len());
+
+        for successor in successors {
+            match analysis.stacks.get(successor)? {
+                None => {
+                    analysis.stacks[successor] = Some(stack.clone());
+                    pending.push(successor);
+                }
+                // Compiled code keeps values in variables, so paths meeting
+                // at an instruction must agree on the types of the stack
+                Some(existing) if *existing == stack => (),
+                Some(_) => return None,
+            }
+        }
+    }
+
+    Some(analysis)
+}
+
+const fn is_scalar(value: Type) -> bool {
+    matches!(value, Type::Number | Type::Boolean)
+}
@@ -0,0 +1,35 @@
+// The signatures mirror those of the JIT
+#![allow(clippy::unused_self, clippy::needless_pass_by_ref_mut)]
+
+use std::{rc::Rc, sync::atomic::AtomicBool};
+
+use crate::Function;
+
+/// Stands in for the JIT on platforms Cranelift doesn't support, or when
+/// the `jit` feature is disabled. It is never built, so the virtual machine
+/// always runs bytecode
+#[derive(Debug)]
+pub struct Jit(());
+
+#[derive(Debug)]
+pub struct Compiled(());
+
+impl Jit {
+    pub const fn new() -> Option<Box<Self>> {
+        None
+    }
+
+    pub const fn compiled(&mut self, _: &Rc<Function>) -> Option<&Compiled> {
+        None
+    }
+}
+
+impl Compiled {
+    pub const fn recursion(&self) -> Option<&Rc<str>> {
+        None
+    }
+
+    pub const fn call(&self, _: &[f64], _: usize, _: usize, _: &AtomicBool) -> Option<f64> {
+        None
+    }
+}
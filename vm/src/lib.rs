@@ -0,0 +1,33 @@
+#![deny(clippy::pedantic, clippy::nursery)]
+#![allow(clippy::module_name_repetitions)]
+
+mod builder;
+mod chunk;
+mod compiler;
+mod error;
+#[cfg(all(
+    feature = "jit",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod jit;
+#[cfg(not(all(
+    feature = "jit",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+#[path = "jit_unsupported.rs"]
+mod jit;
+mod stdlib;
+mod value;
+mod vm;
+
+pub use builder::VmBuilder;
+pub use chunk::{Chunk, Instruction};
+pub use compiler::Compiler;
+pub use error::CompileError;
+pub use value::{
+    BoundMethod, Class, Closure, Function, Instance, Native, NativeFunction, Upvalue,
+    UpvalueSource, Value,
+};
+pub use vm::Vm;
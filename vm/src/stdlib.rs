@@ -0,0 +1,76 @@
+use std::{
+    cell::RefCell,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+use interpreter::{CallContext, RuntimeError, Stdlib, Streams};
+use lox_core::Result;
+
+use crate::{Class, Instance, Native, Value};
+
+impl Stdlib for Value {
+    type Host = Streams;
+
+    fn stdout(host: &mut Streams) -> &mut dyn Write {
+        &mut host.stdout
+    }
+
+    fn stdin(host: &mut Streams) -> &mut dyn BufRead {
+        &mut host.stdin
+    }
+
+    fn nil() -> Self {
+        Self::Nil
+    }
+
+    fn boolean(boolean: bool) -> Self {
+        Self::Boolean(boolean)
+    }
+
+    fn number(number: f64) -> Self {
+        Self::Number(number)
+    }
+
+    fn string(string: Rc<str>) -> Self {
+        Self::String(string)
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match *self {
+            Self::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.type_name()
+    }
+
+    fn native<F>(identifier: &str, arity: usize, function: F) -> Self
+    where
+        F: Fn(&mut Streams, &CallContext, &[Self]) -> Result<Self, RuntimeError> + 'static,
+    {
+        Self::Native(Rc::new(Native {
+            identifier: identifier.into(),
+            arity,
+            function: Rc::new(function),
+        }))
+    }
+
+    fn object(identifier: &str, fields: Vec<(&str, Self)>) -> Self {
+        let fields = fields
+            .into_iter()
+            .map(|(identifier, value)| (identifier.into(), value))
+            .collect();
+
+        Self::Instance(Rc::new(RefCell::new(Instance {
+            class: Rc::new(Class {
+                identifier: identifier.into(),
+                methods: RefCell::default(),
+                super_class: None,
+            }),
+            fields,
+        })))
+    }
+}
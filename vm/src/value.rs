@@ -0,0 +1,220 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use interpreter::{CallContext, RuntimeError, Streams};
+use lox_core::Result;
+use parser::Literal;
+
+use crate::Chunk;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(Rc<str>),
+    Number(f64),
+    Boolean(bool),
+    Nil,
+    Closure(Rc<Closure>),
+    BoundMethod(Rc<BoundMethod>),
+    Native(Rc<Native>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+
+    /// Held by variables declared without an initializer until they are
+    /// assigned, never visible to programs
+    Unassigned,
+}
+
+impl From<Literal> for Value {
+    fn from(literal: Literal) -> Self {
+        match literal {
+            Literal::String(string) => Self::String(string),
+            Literal::Number(number) => Self::Number(number),
+            Literal::Boolean(boolean) => Self::Boolean(boolean),
+            Literal::Nil => Self::Nil,
+        }
+    }
+}
+
+impl Value {
+    #[must_use]
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Number(_) => "number",
+            Self::Boolean(_) => "boolean",
+            Self::Nil | Self::Unassigned => "nil",
+            Self::Closure(_) | Self::BoundMethod(_) | Self::Native(_) | Self::Class(_) => {
+                "function"
+            }
+            Self::Instance(_) => "object",
+        }
+    }
+
+    /// Lox follows Ruby’s simple rule: `false` and `nil` are falsey,
+    /// and everything else is truthy.
+    #[must_use]
+    pub const fn is_truthy(&self) -> bool {
+        !matches!(self, Self::Nil | Self::Boolean(false))
+    }
+
+    /// The compiled function called by the value, if it is written in Lox
+    fn function(&self) -> Option<&Rc<Function>> {
+        match self {
+            Self::Closure(closure) => Some(&closure.function),
+            Self::BoundMethod(method) => Some(&method.method.function),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(string) => write!(f, "{string}"),
+            Self::Number(num) => write!(f, "{num}"),
+            Self::Boolean(true) => write!(f, "true"),
+            Self::Boolean(false) => write!(f, "false"),
+            Self::Nil | Self::Unassigned => write!(f, "nil"),
+            Self::Closure(closure) => write!(f, "{}", closure.function),
+            Self::BoundMethod(method) => write!(f, "{}", method.method.function),
+            Self::Native(native) => write!(f, "<native fn {}>", native.identifier),
+            Self::Class(class) => write!(f, "<class {}>", class.identifier),
+            Self::Instance(instance) => {
+                write!(f, "<{} instance>", instance.borrow().class.identifier)
+            }
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // Constants keep the lexer's strings, so equal literals of one
+            // program share their allocation. Identifiers and strings built
+            // while running have their own
+            (Self::String(a), Self::String(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (Self::Nil, Self::Nil) => true,
+            (Self::Native(a), Self::Native(b)) => Rc::ptr_eq(a, b),
+            (Self::Instance(a), Self::Instance(b)) => Rc::ptr_eq(a, b),
+            // Functions are equal when they run the same code, even if they
+            // are bound to different instances
+            (a, b) => match (a.function(), b.function()) {
+                (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// A function compiled to bytecode
+#[derive(Debug, Default)]
+pub struct Function {
+    /// The identifier of the function, `None` if it is anonymous
+    pub identifier: Option<Rc<str>>,
+    pub arity: usize,
+    pub chunk: Chunk,
+
+    /// Where the variables captured by the function come from
+    pub upvalues: Vec<UpvalueSource>,
+}
+
+impl std::fmt::Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.identifier {
+            Some(ref identifier) => write!(f, "<fn {identifier}>"),
+            None => write!(f, "<anonymous fn>"),
+        }
+    }
+}
+
+/// A variable captured by a function, either a local of the enclosing
+/// function or one of the variables the enclosing function captured itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpvalueSource {
+    pub is_local: bool,
+    pub index: u16,
+}
+
+/// A function along with the variables it captured
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+/// A captured variable, which lives on the stack until the scope declaring
+/// it ends
+#[derive(Debug)]
+pub enum Upvalue {
+    /// Index of the variable in the stack
+    Open(usize),
+    Closed(Value),
+}
+
+#[derive(Debug)]
+pub struct BoundMethod {
+    pub receiver: Value,
+    pub method: Rc<Closure>,
+}
+
+pub type NativeFunction =
+    Rc<dyn Fn(&mut Streams, &CallContext, &[Value]) -> Result<Value, RuntimeError>>;
+
+/// A function implemented in Rust
+pub struct Native {
+    pub identifier: Rc<str>,
+    pub arity: usize,
+    pub function: NativeFunction,
+}
+
+impl std::fmt::Debug for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn {}>", self.identifier)
+    }
+}
+
+#[derive(Debug)]
+pub struct Class {
+    pub identifier: Rc<str>,
+    pub methods: RefCell<HashMap<Rc<str>, Rc<Closure>>>,
+    pub super_class: Option<Rc<Self>>,
+}
+
+impl Class {
+    #[must_use]
+    pub fn find_method(&self, identifier: &str) -> Option<Rc<Closure>> {
+        if let Some(method) = self.methods.borrow().get(identifier) {
+            return Some(Rc::clone(method));
+        }
+
+        self.super_class
+            .as_ref()
+            .and_then(|super_class| super_class.find_method(identifier))
+    }
+
+    /// The initializer declared by the class itself, superclasses are not
+    /// searched
+    #[must_use]
+    pub fn initializer(&self) -> Option<Rc<Closure>> {
+        self.methods.borrow().get("init").cloned()
+    }
+
+    /// Names of the methods of the class and its superclasses
+    #[must_use]
+    pub fn method_names(&self) -> Vec<Rc<str>> {
+        let mut names: Vec<_> = self.methods.borrow().keys().cloned().collect();
+
+        if let Some(ref super_class) = self.super_class {
+            names.extend(super_class.method_names());
+        }
+
+        names
+    }
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: HashMap<Rc<str>, Value>,
+}
@@ -0,0 +1,774 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{BufRead, Write},
+    rc::Rc,
+    time::Instant,
+};
+
+use interpreter::{
+    CallContext, CallFrame, InterruptHandle, Limits, RuntimeError, Stdlib, Streams, NUMBER_METHODS,
+};
+use lox_core::{report, Diagnostic, Diagnostics, Error, Frame, Result, Span};
+use parser::Statement;
+
+use crate::{
+    jit::Jit, BoundMethod, Chunk, Class, Closure, Compiler, Instance, Instruction, Upvalue, Value,
+    VmBuilder,
+};
+
+/// A call of a Lox function in progress
+#[derive(Debug)]
+pub struct FunctionFrame {
+    closure: Rc<Closure>,
+
+    /// Position of the next instruction to run
+    ip: usize,
+
+    /// Index in the stack of the first slot of the function, holding the
+    /// callee or the receiver of methods
+    base: usize,
+}
+
+/// A stack-based virtual machine running programs compiled to bytecode
+///
+/// It runs programs like the tree-walking [`interpreter::Interpreter`],
+/// without the overhead of walking the syntax tree and looking up
+/// environments. Only the interpreter can be extended with host natives and
+/// foreign classes, or given a memory limit. Values are reference counted
+/// without collecting cycles, so objects referring to each other are never
+/// freed
+#[derive(Debug)]
+pub struct Vm {
+    pub(crate) stack: Vec<Value>,
+    pub(crate) frames: Vec<FunctionFrame>,
+    pub(crate) globals: HashMap<Rc<str>, Value>,
+
+    /// Upvalues still pointing into the stack, ordered by stack index
+    pub(crate) open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    pub(crate) streams: Streams,
+
+    /// The calls in progress, outermost first, shown in the stack trace of
+    /// runtime errors
+    pub(crate) call_stack: Vec<CallFrame>,
+
+    /// The call stack at the point the error being propagated was raised
+    pub(crate) trace: Option<Vec<CallFrame>>,
+
+    /// Calls nested deeper than this raise a stack overflow error
+    pub(crate) max_call_depth: usize,
+    pub(crate) limits: Limits,
+
+    /// Compiles hot functions to machine code, if enabled and supported
+    pub(crate) jit: Option<Box<Jit>>,
+
+    /// Depth of the call whose compiled code last bailed out. The virtual
+    /// machine runs the calls nested in it, which would bail out the same way
+    pub(crate) bailout: Option<usize>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    #[must_use]
+    pub fn new() -> Self {
+        VmBuilder::new().build()
+    }
+
+    /// The stream `print` writes to
+    pub fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.streams.stdout
+    }
+
+    /// The stream `readLine` reads from
+    pub fn stdin(&mut self) -> &mut dyn BufRead {
+        &mut self.streams.stdin
+    }
+
+    /// The stream errors are reported to
+    pub fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.streams.stderr
+    }
+
+    /// Calls and loop iterations left before [`RuntimeError::OutOfFuel`] is
+    /// raised, `None` if unlimited
+    #[must_use]
+    pub const fn fuel(&self) -> Option<u64> {
+        self.limits.fuel
+    }
+
+    /// Sets how many calls and loop iterations can run before the program is
+    /// stopped, `None` to run without limit
+    pub const fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.limits.fuel = fuel;
+    }
+
+    /// Sets the instant after which [`RuntimeError::DeadlineExceeded`] is
+    /// raised, `None` to run without limit
+    pub const fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.limits.deadline = deadline;
+    }
+
+    /// A handle to stop the virtual machine from another thread
+    #[must_use]
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.limits.interrupt_handle()
+    }
+
+    /// Compiles and runs the program, stopping at the first runtime error.
+    /// Globals are kept between calls
+    pub fn interpret(&mut self, program: &[Statement]) -> Diagnostics {
+        let function = match Compiler::compile(program) {
+            Ok(function) => function,
+            Err(error) => return error.into(),
+        };
+
+        let closure = Rc::new(Closure {
+            function: Rc::new(function),
+            upvalues: Vec::new(),
+        });
+
+        self.stack.push(Value::Closure(Rc::clone(&closure)));
+        self.frames.push(FunctionFrame {
+            closure,
+            ip: 0,
+            base: 0,
+        });
+
+        match self.run() {
+            Ok(()) => Diagnostics::new(),
+            Err(error) => {
+                if self.trace.is_none() {
+                    self.trace = Some(self.call_stack.clone());
+                }
+
+                let trace = self.trace.take().unwrap_or_default();
+
+                self.stack.clear();
+                self.frames.clear();
+                self.open_upvalues.clear();
+                self.call_stack.clear();
+
+                Diagnostic::from(error)
+                    .with_trace(trace.iter().rev().map(Frame::from).collect())
+                    .into()
+            }
+        }
+    }
+
+    /// Renders the diagnostics to the virtual machine's stderr
+    pub fn report(&mut self, source: &str, diagnostics: &Diagnostics) {
+        for diagnostic in diagnostics {
+            // Failing to write to stderr leaves nowhere else to report to
+            _ = report(&mut self.streams.stderr, source, diagnostic);
+        }
+    }
+
+    fn frame(&self) -> &FunctionFrame {
+        self.frames.last().unwrap_or_else(|| unreachable!())
+    }
+
+    fn chunk(&self) -> &Chunk {
+        &self.frame().closure.function.chunk
+    }
+
+    /// The span of the instruction being run
+    fn span(&self) -> Span {
+        let frame = self.frame();
+        frame.closure.function.chunk.spans[frame.ip - 1]
+    }
+
+    /// Builds an error located at the instruction being run
+    fn error(&self, source: RuntimeError) -> Error<RuntimeError> {
+        Error {
+            span: self.span(),
+            source,
+        }
+    }
+
+    /// The identifier held by a constant
+    fn identifier(&self, index: u16) -> Rc<str> {
+        match self.chunk().constants[usize::from(index)] {
+            Value::String(ref identifier) => Rc::clone(identifier),
+            _ => unreachable!(),
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or_else(|| unreachable!())
+    }
+
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    /// Raises an error if the variable is read before it is assigned
+    fn assigned(&self, value: Value, name: u16) -> Result<Value, RuntimeError> {
+        match value {
+            Value::Unassigned => {
+                Err(self.error(RuntimeError::UnassignedVariable(self.identifier(name))))
+            }
+            value => Ok(value),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn run(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().unwrap_or_else(|| unreachable!());
+            let instruction = frame.closure.function.chunk.code[frame.ip];
+            frame.ip += 1;
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    let value = self.chunk().constants[usize::from(index)].clone();
+                    self.stack.push(value);
+                }
+                Instruction::Nil => self.stack.push(Value::Nil),
+                Instruction::True => self.stack.push(Value::Boolean(true)),
+                Instruction::False => self.stack.push(Value::Boolean(false)),
+                Instruction::Unassigned => self.stack.push(Value::Unassigned),
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::GetLocal { slot, name } => {
+                    let value = self.stack[self.frame().base + usize::from(slot)].clone();
+                    let value = self.assigned(value, name)?;
+                    self.stack.push(value);
+                }
+                Instruction::SetLocal(slot) => {
+                    let index = self.frame().base + usize::from(slot);
+                    self.stack[index] = self.peek(0).clone();
+                }
+                Instruction::GetUpvalue { index, name } => {
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[usize::from(index)]);
+                    let value = match *upvalue.borrow() {
+                        Upvalue::Open(index) => self.stack[index].clone(),
+                        Upvalue::Closed(ref value) => value.clone(),
+                    };
+
+                    let value = self.assigned(value, name)?;
+                    self.stack.push(value);
+                }
+                Instruction::SetUpvalue(index) => {
+                    let upvalue = Rc::clone(&self.frame().closure.upvalues[usize::from(index)]);
+                    let value = self.peek(0).clone();
+
+                    let mut upvalue = upvalue.borrow_mut();
+                    match *upvalue {
+                        Upvalue::Open(index) => self.stack[index] = value,
+                        Upvalue::Closed(ref mut closed) => *closed = value,
+                    }
+                }
+                Instruction::DefineGlobal(name) => {
+                    let value = self.pop();
+                    self.globals.insert(self.identifier(name), value);
+                }
+                Instruction::GetGlobal(name) => {
+                    let identifier = self.identifier(name);
+
+                    let Some(value) = self.globals.get(&identifier).cloned() else {
+                        return Err(self.undeclared_variable(&identifier));
+                    };
+
+                    let value = self.assigned(value, name)?;
+                    self.stack.push(value);
+                }
+                Instruction::SetGlobal(name) => {
+                    let identifier = self.identifier(name);
+                    let value = self.peek(0).clone();
+
+                    match self.globals.get_mut(&identifier) {
+                        Some(global) => *global = value,
+                        None => return Err(self.undeclared_variable(&identifier)),
+                    }
+                }
+                Instruction::GetProperty(name) => {
+                    let object = self.pop();
+                    let value = self.get_property(object, &self.identifier(name))?;
+                    self.stack.push(value);
+                }
+                Instruction::SetProperty(name) => {
+                    let value = self.pop();
+                    let object = self.pop();
+
+                    let Value::Instance(instance) = object else {
+                        return Err(self.error(RuntimeError::TypeIsNotInstance(object.type_name())));
+                    };
+
+                    instance
+                        .borrow_mut()
+                        .fields
+                        .insert(self.identifier(name), value.clone());
+                    self.stack.push(value);
+                }
+                Instruction::GetSuper(name) => {
+                    let Value::Class(super_class) = self.pop() else {
+                        unreachable!()
+                    };
+                    let receiver = self.pop();
+                    let identifier = self.identifier(name);
+
+                    let Some(method) = super_class.find_method(&identifier) else {
+                        return Err(self.error(RuntimeError::undefined_property(
+                            &identifier,
+                            super_class.method_names(),
+                        )));
+                    };
+
+                    self.stack.push(Value::BoundMethod(Rc::new(BoundMethod {
+                        receiver,
+                        method,
+                    })));
+                }
+                Instruction::Equal => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(Value::Boolean(left == right));
+                }
+                Instruction::Greater
+                | Instruction::GreaterEqual
+                | Instruction::Less
+                | Instruction::LessEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = self.compare(instruction, left, right)?;
+                    self.stack.push(value);
+                }
+                Instruction::Add => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = self.add(left, right)?;
+                    self.stack.push(value);
+                }
+                Instruction::Subtract | Instruction::Multiply | Instruction::Divide => {
+                    let right = self.pop();
+                    let left = self.pop();
+
+                    let (a, b) = match (left, right) {
+                        (Value::Number(a), Value::Number(b)) => (a, b),
+                        (Value::Number(_), x) | (x, _) => {
+                            return Err(self.error(RuntimeError::TypeError {
+                                expected: "number",
+                                found: x.type_name(),
+                            }))
+                        }
+                    };
+
+                    self.stack.push(Value::Number(match instruction {
+                        Instruction::Subtract => a - b,
+                        Instruction::Multiply => a * b,
+                        _ => a / b,
+                    }));
+                }
+                Instruction::Not => {
+                    let value = self.pop();
+                    self.stack.push(Value::Boolean(!value.is_truthy()));
+                }
+                Instruction::Negate => match self.pop() {
+                    Value::Number(number) => self.stack.push(Value::Number(-number)),
+                    x => {
+                        return Err(self.error(RuntimeError::TypeError {
+                            expected: "number",
+                            found: x.type_name(),
+                        }))
+                    }
+                },
+                Instruction::Jump(target) => {
+                    // Jumping backwards starts another iteration of a loop
+                    if (target as usize) < self.frame().ip {
+                        self.tick()?;
+                    }
+
+                    self.jump(target);
+                }
+                Instruction::JumpIfFalse(target) => {
+                    if !self.peek(0).is_truthy() {
+                        self.jump(target);
+                    }
+                }
+                Instruction::Call(count) => {
+                    self.tick()?;
+                    self.call_value(usize::from(count))?;
+                }
+                Instruction::Closure(index) => {
+                    let frame = self.frame();
+                    let function =
+                        Rc::clone(&frame.closure.function.chunk.functions[usize::from(index)]);
+                    let base = frame.base;
+
+                    let upvalues = function
+                        .upvalues
+                        .iter()
+                        .map(|source| {
+                            if source.is_local {
+                                self.capture_upvalue(base + usize::from(source.index))
+                            } else {
+                                Rc::clone(&self.frame().closure.upvalues[usize::from(source.index)])
+                            }
+                        })
+                        .collect();
+
+                    self.stack
+                        .push(Value::Closure(Rc::new(Closure { function, upvalues })));
+                }
+                Instruction::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    let frame = self.frames.pop().unwrap_or_else(|| unreachable!());
+                    self.close_upvalues(frame.base);
+                    self.stack.truncate(frame.base);
+
+                    // The top level has no call frame, and finishes the program
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+
+                    self.call_stack.pop();
+                    self.stack.push(value);
+                }
+                Instruction::Class {
+                    name,
+                    has_super_class,
+                } => {
+                    let super_class = if has_super_class {
+                        match self.peek(0) {
+                            Value::Class(super_class) => Some(Rc::clone(super_class)),
+                            _ => return Err(self.error(RuntimeError::SuperClassMustBeAClass)),
+                        }
+                    } else {
+                        None
+                    };
+
+                    self.stack.push(Value::Class(Rc::new(Class {
+                        identifier: self.identifier(name),
+                        methods: RefCell::default(),
+                        super_class,
+                    })));
+                }
+                Instruction::Method(name) => {
+                    let Value::Closure(method) = self.pop() else {
+                        unreachable!()
+                    };
+                    let Value::Class(class) = self.peek(0) else {
+                        unreachable!()
+                    };
+
+                    class
+                        .methods
+                        .borrow_mut()
+                        .insert(self.identifier(name), method);
+                }
+            }
+        }
+    }
+
+    /// Charges one call or loop iteration, failing if any of the limits was
+    /// reached
+    fn tick(&mut self) -> Result<(), RuntimeError> {
+        self.limits.tick().map_err(|source| self.error(source))
+    }
+
+    fn jump(&mut self, target: u32) {
+        let frame = self.frames.last_mut().unwrap_or_else(|| unreachable!());
+
+        // Positions in a chunk are checked to fit in a `u32` when compiling
+        frame.ip = target as usize;
+    }
+
+    fn undeclared_variable(&self, identifier: &Rc<str>) -> Error<RuntimeError> {
+        self.error(RuntimeError::undeclared_variable(
+            identifier,
+            self.globals.keys(),
+        ))
+    }
+
+    fn get_property(&self, object: Value, identifier: &Rc<str>) -> Result<Value, RuntimeError> {
+        match object {
+            Value::Instance(ref instance) => {
+                let instance_ref = instance.borrow();
+
+                if let Some(value) = instance_ref.fields.get(identifier) {
+                    return Ok(value.clone());
+                }
+
+                if let Some(method) = instance_ref.class.find_method(identifier) {
+                    return Ok(Value::BoundMethod(Rc::new(BoundMethod {
+                        receiver: object.clone(),
+                        method,
+                    })));
+                }
+
+                Err(self.error(RuntimeError::undefined_property(
+                    identifier,
+                    instance_ref
+                        .fields
+                        .keys()
+                        .cloned()
+                        .chain(instance_ref.class.method_names()),
+                )))
+            }
+            Value::Number(number) => Value::number_method(number, identifier).ok_or_else(|| {
+                self.error(RuntimeError::undefined_property(identifier, NUMBER_METHODS))
+            }),
+            x => Err(self.error(RuntimeError::TypeIsNotInstance(x.type_name()))),
+        }
+    }
+
+    fn compare(
+        &self,
+        instruction: Instruction,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, RuntimeError> {
+        use Instruction as I;
+        use Value as L;
+
+        Ok(L::Boolean(match (left, right) {
+            (L::String(a), L::String(b)) => match instruction {
+                I::Less => a < b,
+                I::LessEqual => a <= b,
+                I::Greater => a > b,
+                I::GreaterEqual => a >= b,
+                _ => unreachable!(),
+            },
+            (L::Number(a), L::Number(b)) => match instruction {
+                I::Less => a < b,
+                I::LessEqual => a <= b,
+                I::Greater => a > b,
+                I::GreaterEqual => a >= b,
+                _ => unreachable!(),
+            },
+            (L::Boolean(a), L::Boolean(b)) => match instruction {
+                I::Less => !a && b,
+                I::LessEqual => a <= b,
+                I::Greater => a && !b,
+                I::GreaterEqual => a >= b,
+                _ => unreachable!(),
+            },
+            (L::Nil, L::Nil) => match instruction {
+                I::Less | I::Greater => true,
+                I::LessEqual | I::GreaterEqual => false,
+                _ => unreachable!(),
+            },
+            (a, b) => {
+                return Err(self.error(RuntimeError::TypeError {
+                    expected: a.type_name(),
+                    found: b.type_name(),
+                }))
+            }
+        }))
+    }
+
+    fn add(&self, left: Value, right: Value) -> Result<Value, RuntimeError> {
+        Ok(match (left, right) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (a @ Value::String(_), b) | (a, b @ Value::String(_)) => {
+                Value::String(format!("{a}{b}").into())
+            }
+            (Value::Number(_), x) => {
+                return Err(self.error(RuntimeError::TypeError {
+                    expected: "number",
+                    found: x.type_name(),
+                }))
+            }
+            (x, _) => {
+                return Err(self.error(RuntimeError::TypeError {
+                    expected: r#"number" or "string"#,
+                    found: x.type_name(),
+                }))
+            }
+        })
+    }
+
+    /// Calls the value below the `count` arguments on top of the stack
+    fn call_value(&mut self, count: usize) -> Result<(), RuntimeError> {
+        let slot = self.stack.len() - count - 1;
+        let callee = self.stack[slot].clone();
+
+        let (identifier, arity) = match callee {
+            Value::Closure(ref closure) => {
+                (closure.function.identifier.clone(), closure.function.arity)
+            }
+            Value::BoundMethod(ref method) => (
+                method.method.function.identifier.clone(),
+                method.method.function.arity,
+            ),
+            Value::Native(ref native) => (Some(Rc::clone(&native.identifier)), native.arity),
+            Value::Class(ref class) => (
+                Some(Rc::clone(&class.identifier)),
+                class
+                    .initializer()
+                    .map_or(0, |initializer| initializer.function.arity),
+            ),
+            ref x => return Err(self.error(RuntimeError::TypeIsNotCallable(x.type_name()))),
+        };
+
+        if self.call_stack.len() >= self.max_call_depth {
+            return Err(self.error(RuntimeError::StackOverflow(self.max_call_depth)));
+        }
+
+        if count != arity {
+            return Err(self.error(RuntimeError::ImcorrectNumberOfArguments {
+                expected: arity,
+                found: count,
+            }));
+        }
+
+        let call_site = self.span();
+        let closure = match callee {
+            Value::Closure(closure) => {
+                if let Some(value) = self.call_compiled(&closure, slot) {
+                    self.stack.truncate(slot);
+                    self.stack.push(Value::Number(value));
+
+                    return Ok(());
+                }
+
+                closure
+            }
+            Value::BoundMethod(method) => {
+                self.stack[slot] = method.receiver.clone();
+                Rc::clone(&method.method)
+            }
+            Value::Class(class) => {
+                let initializer = class.initializer();
+                self.stack[slot] = Value::Instance(Rc::new(RefCell::new(Instance {
+                    class,
+                    fields: HashMap::new(),
+                })));
+
+                match initializer {
+                    Some(initializer) => initializer,
+                    None => return Ok(()),
+                }
+            }
+            Value::Native(native) => {
+                self.call_stack.push(CallFrame {
+                    function: identifier,
+                    call_site,
+                });
+
+                let context = CallContext { span: call_site };
+                let result =
+                    (native.function)(&mut self.streams, &context, &self.stack[slot + 1..]);
+
+                if result.is_err() {
+                    self.trace = Some(self.call_stack.clone());
+                }
+
+                self.call_stack.pop();
+                self.stack.truncate(slot);
+                self.stack.push(result?);
+
+                return Ok(());
+            }
+            _ => unreachable!(),
+        };
+
+        self.call_stack.push(CallFrame {
+            function: identifier,
+            call_site,
+        });
+        self.frames.push(FunctionFrame {
+            closure,
+            ip: 0,
+            base: slot,
+        });
+
+        Ok(())
+    }
+
+    /// Runs the machine code of the closure instead of its bytecode, if it was
+    /// compiled and its guards hold
+    fn call_compiled(&mut self, closure: &Rc<Closure>, slot: usize) -> Option<f64> {
+        let depth = self.call_stack.len();
+
+        match self.bailout {
+            Some(bailout) if depth > bailout => return None,
+            _ => self.bailout = None,
+        }
+
+        // Compiled code can't be charged fuel or check the deadline
+        if self.limits.fuel.is_some() || self.limits.deadline.is_some() {
+            return None;
+        }
+
+        let compiled = self.jit.as_mut()?.compiled(&closure.function)?;
+
+        // Compiled code calls itself directly, as long as its global still
+        // holds it
+        if let Some(identifier) = compiled.recursion() {
+            match self.globals.get(identifier) {
+                Some(Value::Closure(global)) if Rc::ptr_eq(&global.function, &closure.function) => {
+                }
+                _ => return None,
+            }
+        }
+
+        let args = self.stack[slot + 1..]
+            .iter()
+            .map(|arg| match arg {
+                Value::Number(number) => Some(*number),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let result = compiled.call(
+            &args,
+            depth + 1,
+            self.max_call_depth,
+            &self.limits.interrupted,
+        );
+
+        if result.is_none() {
+            self.bailout = Some(depth);
+        }
+
+        result
+    }
+
+    /// Returns the upvalue pointing to the given stack slot, creating it if no
+    /// closure captured the slot yet
+    fn capture_upvalue(&mut self, index: usize) -> Rc<RefCell<Upvalue>> {
+        let position =
+            self.open_upvalues
+                .binary_search_by_key(&index, |upvalue| match *upvalue.borrow() {
+                    Upvalue::Open(index) => index,
+                    Upvalue::Closed(_) => unreachable!(),
+                });
+
+        match position {
+            Ok(position) => Rc::clone(&self.open_upvalues[position]),
+            Err(position) => {
+                let upvalue = Rc::new(RefCell::new(Upvalue::Open(index)));
+                self.open_upvalues.insert(position, Rc::clone(&upvalue));
+                upvalue
+            }
+        }
+    }
+
+    /// Moves the variables at or above the given stack slot that closures
+    /// captured to the heap
+    fn close_upvalues(&mut self, first: usize) {
+        while let Some(upvalue) = self.open_upvalues.last() {
+            let Upvalue::Open(index) = *upvalue.borrow() else {
+                unreachable!()
+            };
+
+            if index < first {
+                break;
+            }
+
+            *upvalue.borrow_mut() = Upvalue::Closed(self.stack[index].clone());
+            self.open_upvalues.pop();
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use lexer::Lexer;
+use parser::{Parser, Statement};
+
+/// Parses `source`, which must be free of syntax errors
+pub fn parse(source: &str) -> Vec<Statement> {
+    let (tokens, _) = Lexer::new(source).scan();
+    let (program, diagnostics) = Parser::new(&tokens).parse();
+    assert!(diagnostics.is_empty());
+
+    program
+}
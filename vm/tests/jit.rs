@@ -0,0 +1,142 @@
+mod common;
+
+use common::parse;
+use interpreter::OutputBuffer;
+use vm::VmBuilder;
+
+/// Runs `source` with or without compiling hot functions, returning what it
+/// printed and the codes of the errors raised
+fn run(source: &str, jit: bool, max_call_depth: usize) -> (String, Vec<&'static str>) {
+    let program = parse(source);
+
+    let output = OutputBuffer::default();
+    let mut vm = VmBuilder::new()
+        .jit(jit)
+        .max_call_depth(max_call_depth)
+        .stdout(output.clone())
+        .build();
+
+    let codes = vm
+        .interpret(&program)
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect();
+
+    (output.contents(), codes)
+}
+
+/// Asserts that `source` behaves the same with and without compiling hot
+/// functions, returning what it printed
+fn same_with_jit(source: &str, max_call_depth: usize) -> (String, Vec<&'static str>) {
+    let expected = run(source, false, max_call_depth);
+    assert_eq!(run(source, true, max_call_depth), expected);
+
+    expected
+}
+
+#[test]
+fn hot_numeric_functions_give_the_same_results() {
+    let source = r"
+        fun root(x) {
+            var guess = x;
+            for (var i = 0; i < 20; i = i + 1) guess = (guess + x / guess) / 2;
+            return guess;
+        }
+        fun fib(n) {
+            if (n < 2) return n;
+            return fib(n - 1) + fib(n - 2);
+        }
+        var total = 0;
+        for (var i = 1; i < 3000; i = i + 1) total = total + root(i * i);
+        print(total);
+        print(fib(22));
+        print(fib(0.5));
+    ";
+
+    let (output, errors) = same_with_jit(source, 10_000);
+
+    assert_eq!(output, "4498500\n17711\n0.5\n");
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn compiled_functions_fall_back_for_other_types() {
+    let source = r#"
+        fun add(a, b) { return a + b; }
+        for (var i = 0; i < 2000; i = i + 1) add(i, i);
+        print(add(1, 2));
+        print(add("a", "b"));
+        add(nil, 1);
+    "#;
+
+    assert_eq!(
+        same_with_jit(source, 10_000),
+        ("3\nab\n".into(), vec!["E0401"])
+    );
+}
+
+#[test]
+fn recursion_deeper_than_the_native_stack_allows_works() {
+    let source = r"
+        fun depth(n) {
+            if (n == 0) return 0;
+            return depth(n - 1) + 1;
+        }
+        for (var i = 0; i < 2000; i = i + 1) depth(3);
+        print(depth(50000));
+    ";
+
+    assert_eq!(same_with_jit(source, 100_000), ("50000\n".into(), vec![]));
+}
+
+#[test]
+fn stack_overflows_in_compiled_code_are_reported() {
+    let source = r"
+        fun depth(n) {
+            if (n == 0) return 0;
+            return depth(n - 1) + 1;
+        }
+        for (var i = 0; i < 2000; i = i + 1) depth(3);
+        print(depth(99));
+        depth(100);
+    ";
+
+    assert_eq!(same_with_jit(source, 100), ("99\n".into(), vec!["E0418"]));
+}
+
+#[test]
+fn redefined_functions_are_called() {
+    let source = r"
+        fun value(n) { return n + 1; }
+        fun call(n) { return value(n); }
+        for (var i = 0; i < 2000; i = i + 1) call(i);
+        print(call(1));
+        fun value(n) { return n * 10; }
+        print(call(1));
+    ";
+
+    assert_eq!(same_with_jit(source, 10_000), ("2\n10\n".into(), vec![]));
+}
+
+#[test]
+fn functions_replaced_by_later_programs_are_compiled_again() {
+    let output = OutputBuffer::default();
+    let mut vm = VmBuilder::new().jit(true).stdout(output.clone()).build();
+
+    // Enough programs for the functions they drop to be forgotten, and their
+    // memory possibly reused by the next ones
+    for offset in 0..300 {
+        let source = format!(
+            "fun add(n) {{ return n + {offset}; }}
+            var total = 0;
+            for (var i = 0; i < 1100; i = i + 1) total = add(total);
+            print(total);"
+        );
+        assert!(vm.interpret(&parse(&source)).is_empty());
+    }
+
+    let expected: String = (0..300)
+        .map(|offset| format!("{}\n", offset * 1100))
+        .collect();
+    assert_eq!(output.contents(), expected);
+}
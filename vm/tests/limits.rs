@@ -0,0 +1,105 @@
+mod common;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::parse;
+use vm::{Vm, VmBuilder};
+
+/// The codes of the errors raised by running `source`
+fn error_codes(vm: &mut Vm, source: &str) -> Vec<&'static str> {
+    vm.interpret(&parse(source))
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect()
+}
+
+/// Stops `vm` from another thread after a short while
+fn interrupt_soon(vm: &Vm) -> thread::JoinHandle<()> {
+    let handle = vm.interrupt_handle();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    })
+}
+
+#[test]
+fn running_out_of_fuel_stops_the_program() {
+    let mut vm = VmBuilder::new().fuel(1_000).build();
+
+    assert_eq!(error_codes(&mut vm, "while (true) {}"), ["E0419"]);
+    assert_eq!(vm.fuel(), Some(0));
+
+    vm.set_fuel(Some(1_000));
+    assert!(error_codes(&mut vm, "fun f() {} f();").is_empty());
+    assert_eq!(vm.fuel(), Some(999));
+}
+
+#[test]
+fn calls_are_charged_fuel() {
+    let mut vm = VmBuilder::new().fuel(1_000).build();
+
+    let source = "fun f(n) { if (n > 0) f(n - 1); } f(2000);";
+
+    assert_eq!(error_codes(&mut vm, source), ["E0419"]);
+}
+
+#[test]
+fn passing_the_deadline_stops_the_program() {
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let mut vm = VmBuilder::new().deadline(deadline).build();
+
+    assert_eq!(error_codes(&mut vm, "while (true) {}"), ["E0420"]);
+
+    vm.set_deadline(None);
+    assert!(error_codes(&mut vm, "var x = 1 + 2;").is_empty());
+}
+
+#[test]
+fn interrupting_stops_the_program() {
+    let mut vm = Vm::new();
+    let interrupter = interrupt_soon(&vm);
+
+    assert_eq!(error_codes(&mut vm, "while (true) {}"), ["E0421"]);
+    interrupter.join().unwrap();
+
+    assert!(error_codes(&mut vm, "var x = 1 + 2;").is_empty());
+}
+
+#[test]
+fn interrupting_stops_compiled_code() {
+    let mut vm = VmBuilder::new().jit(true).build();
+
+    let source = r"
+        fun spin(n) {
+            while (n > 0) n = n + 1;
+            return n;
+        }
+        for (var i = 0; i < 2000; i = i + 1) spin(0);
+    ";
+    assert!(error_codes(&mut vm, source).is_empty());
+
+    let interrupter = interrupt_soon(&vm);
+
+    assert_eq!(error_codes(&mut vm, "spin(1);"), ["E0421"]);
+    interrupter.join().unwrap();
+}
+
+#[test]
+fn limits_apply_with_the_jit_enabled() {
+    let mut vm = VmBuilder::new().jit(true).fuel(100_000).build();
+
+    let source = r"
+        fun spin(n) {
+            while (n > 0) n = n + 1;
+            return n;
+        }
+        for (var i = 0; i < 2000; i = i + 1) spin(0);
+        spin(1);
+    ";
+
+    assert_eq!(error_codes(&mut vm, source), ["E0419"]);
+}